@@ -33,9 +33,8 @@ fn move_all_reindeer(reindeer: &mut Vec<Reindeer>) {
     }
 }
 
-fn main() {
-    // Read input
-    let file = File::open("input").unwrap();
+fn load(filename: &str) -> Vec<Reindeer> {
+    let file = File::open(filename).unwrap();
     let lines = io::BufReader::new(file).lines();
     let mut reindeer: Vec<Reindeer> = Vec::new();
     for line in lines {
@@ -53,8 +52,11 @@ fn main() {
             });
         }
     }
+    return reindeer;
+}
 
-    // Part 1
+fn part1(filename: &str) -> usize {
+    let mut reindeer = load(filename);
     for _ in 0 .. 2503 {
         move_all_reindeer(&mut reindeer);
     }
@@ -64,19 +66,14 @@ fn main() {
             furthest_position = r.position;
         }
     }
-    println!("{}", furthest_position);
-
-    // Reset for part 2
-    for r in &mut reindeer {
-        r.position = 0;
-        r.resting = true;
-        r.remaining_time = 0;
-    }
+    return furthest_position;
+}
 
-    // Part 2
+fn part2(filename: &str) -> usize {
+    let mut reindeer = load(filename);
     for _ in 0 .. 2503 {
         move_all_reindeer(&mut reindeer);
-        furthest_position = 0;
+        let mut furthest_position: usize = 0;
         for r in &reindeer {
             if furthest_position < r.position {
                 furthest_position = r.position;
@@ -94,6 +91,23 @@ fn main() {
             highest_score = r.score;
         }
     }
-    println!("{}", highest_score);
+    return highest_score;
+}
+
+pub struct Solution;
+
+impl aoc::Puzzle for Solution {
+    fn part1(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part1(filename).to_string());
+    }
+
+    fn part2(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part2(filename).to_string());
+    }
+}
+
+fn main() {
+    println!("{}", part1("input"));
+    println!("{}", part2("input"));
 }
 
@@ -80,9 +80,8 @@ fn find_best_with_calories(ingredients: &mut Vec<Ingredient>, index: usize,
 }
 
 
-fn main() {
-    // Read input
-    let file = File::open("input").unwrap();
+fn load(filename: &str) -> Vec<Ingredient> {
+    let file = File::open(filename).unwrap();
     let lines = io::BufReader::new(file).lines();
     let mut ingredients: Vec<Ingredient> = Vec::new();
     for line in lines {
@@ -100,13 +99,31 @@ fn main() {
             });
         }
     }
-    
-    // Part 1
-    let best_overall = find_best(&mut ingredients, 0, 100);
-    println!("{}", best_overall);
+    return ingredients;
+}
+
+fn part1(filename: &str) -> i32 {
+    return find_best(&mut load(filename), 0, 100);
+}
+
+fn part2(filename: &str) -> i32 {
+    return find_best_with_calories(&mut load(filename), 0, 100, 500);
+}
+
+pub struct Solution;
 
-    // Part 2
-    let best_overall_with_calories = find_best_with_calories(&mut ingredients, 0, 100, 500);
-    println!("{}", best_overall_with_calories);
+impl aoc::Puzzle for Solution {
+    fn part1(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part1(filename).to_string());
+    }
+
+    fn part2(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part2(filename).to_string());
+    }
+}
+
+fn main() {
+    println!("{}", part1("input"));
+    println!("{}", part2("input"));
 }
 
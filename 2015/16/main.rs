@@ -16,10 +16,11 @@ cars: 2
 perfumes: 1
 */
 
-fn part1() {
+fn part1(filename: &str) -> String {
     // Read input and do part 1
-    let file = File::open("input").unwrap();
+    let file = File::open(filename).unwrap();
     let lines = io::BufReader::new(file).lines();
+    let mut found: Vec<String> = Vec::new();
     for line in lines {
         if let Ok(line_string) = line {
             let decomma = line_string.replace(",", "");
@@ -51,16 +52,18 @@ fn part1() {
             }
 
             if !wrong {
-                println!("{}", line_vec[1]);
+                found.push(line_vec[1].to_string());
             }
         }
     }
+    return found.join("\n");
 }
 
-fn part2() {
+fn part2(filename: &str) -> String {
     // Read input and do part 2
-    let file = File::open("input").unwrap();
+    let file = File::open(filename).unwrap();
     let lines = io::BufReader::new(file).lines();
+    let mut found: Vec<String> = Vec::new();
     for line in lines {
         if let Ok(line_string) = line {
             let decomma = line_string.replace(",", "");
@@ -92,13 +95,26 @@ fn part2() {
             }
 
             if !wrong {
-                println!("{}", line_vec[1]);
+                found.push(line_vec[1].to_string());
             }
         }
     }
+    return found.join("\n");
+}
+
+pub struct Solution;
+
+impl aoc::Puzzle for Solution {
+    fn part1(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part1(filename));
+    }
+
+    fn part2(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part2(filename));
+    }
 }
 
 fn main() {
-    part1();
-    part2();
+    println!("{}", part1("input"));
+    println!("{}", part2("input"));
 }
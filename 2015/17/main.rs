@@ -22,9 +22,9 @@ fn count_permutations(containers: &Vec<u32>, limit: usize, eggnog_left: u32, con
     return count;
 }
 
-fn main() {
+fn load(filename: &str) -> Vec<u32> {
     // Read input (container sizes)
-    let file = File::open("input").unwrap();
+    let file = File::open(filename).unwrap();
     let lines = io::BufReader::new(file).lines();
     let mut containers: Vec<u32> = Vec::new();
     for line in lines {
@@ -35,17 +35,38 @@ fn main() {
         }
     }
     containers.sort();
+    return containers;
+}
 
-    // Part 1
-    let count = count_permutations(&containers, containers.len(), 150, containers.len());
-    println!("{}", count);
+fn part1(filename: &str) -> u32 {
+    let containers = load(filename);
+    return count_permutations(&containers, containers.len(), 150, containers.len());
+}
 
-    // Part 2
+fn part2(filename: &str) -> u32 {
+    let containers = load(filename);
     for num_containers in 1 .. containers.len() {
         let count = count_permutations(&containers, containers.len(), 150, num_containers);
         if count != 0 {
-            println!("{}", count);
-            break;
+            return count;
         }
     }
+    return 0;
+}
+
+pub struct Solution;
+
+impl aoc::Puzzle for Solution {
+    fn part1(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part1(filename).to_string());
+    }
+
+    fn part2(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part2(filename).to_string());
+    }
+}
+
+fn main() {
+    println!("{}", part1("input"));
+    println!("{}", part2("input"));
 }
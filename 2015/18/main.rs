@@ -28,9 +28,9 @@ fn advance(board_in: &BoardType, board_out: &mut BoardType) {
     }
 }
 
-fn get_input() -> BoardType {
+fn get_input(filename: &str) -> BoardType {
     // Read input (initial state of the board)
-    let file = File::open("input").unwrap();
+    let file = File::open(filename).unwrap();
     let lines = io::BufReader::new(file).lines();
     let mut board1: BoardType = [[false; SIZE]; SIZE];
     let mut y = 0;
@@ -75,18 +75,19 @@ fn light_corners(board: &mut BoardType) {
 }
 
 
-fn main() {
-    // Part 1
-    let mut board1 = get_input();
+fn part1(filename: &str) -> usize {
+    let mut board1 = get_input(filename);
     let mut board2: BoardType = [[false; SIZE]; SIZE];
     for _ in 0 .. 50 {
         advance(&board1, &mut board2);
         advance(&board2, &mut board1);
     }
-    println!("{}", count_on(&board1));
+    return count_on(&board1);
+}
 
-    // Part 2
-    board1 = get_input();
+fn part2(filename: &str) -> usize {
+    let mut board1 = get_input(filename);
+    let mut board2: BoardType = [[false; SIZE]; SIZE];
     light_corners(&mut board1);
     for _ in 0 .. 50 {
         advance(&board1, &mut board2);
@@ -94,5 +95,22 @@ fn main() {
         advance(&board2, &mut board1);
         light_corners(&mut board1);
     }
-    println!("{}", count_on(&board1));
+    return count_on(&board1);
+}
+
+pub struct Solution;
+
+impl aoc::Puzzle for Solution {
+    fn part1(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part1(filename).to_string());
+    }
+
+    fn part2(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part2(filename).to_string());
+    }
+}
+
+fn main() {
+    println!("{}", part1("input"));
+    println!("{}", part2("input"));
 }
@@ -45,9 +45,9 @@ fn apply_rules(initial_state: &Molecule, rules: &Vec<Rule>) -> HashSet<Molecule>
     return new_states;
 }
 
-fn main() {
+fn part1(filename: &str) -> usize {
     // Read rules
-    let file = File::open("input").unwrap();
+    let file = File::open(filename).unwrap();
     let lines = io::BufReader::new(file).lines();
     let mut initial_state: Molecule = Vec::new();
     let mut rules: Vec<Rule> = Vec::new();
@@ -73,5 +73,21 @@ fn main() {
 
     // Produce new states
     let new_states = apply_rules(&initial_state, &rules);
-    println!("{}", new_states.len());
+    return new_states.len();
+}
+
+pub struct Solution;
+
+impl aoc::Puzzle for Solution {
+    fn part1(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part1(filename).to_string());
+    }
+
+    fn part2(self: &Self, _filename: &str) -> Result<String, String> {
+        return Err("part 2 is solved by part2.py".to_string());
+    }
+}
+
+fn main() {
+    println!("{}", part1("input"));
 }
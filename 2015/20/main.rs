@@ -1,7 +1,5 @@
 
-const INPUT: u64 = 34000000;
-
-
+use std::fs;
 use std::collections::BinaryHeap;
 use std::cmp::Ordering;
 
@@ -29,7 +27,7 @@ impl PartialOrd for HeapItem {
 }
 
 
-fn solver(delivery_limit: u64, deliveries_per_house: u64) -> u64 {
+fn solver(input: u64, delivery_limit: u64, deliveries_per_house: u64) -> u64 {
     let mut heap: BinaryHeap<HeapItem> = BinaryHeap::new();
     let mut house_number = 1;
 
@@ -55,16 +53,43 @@ fn solver(delivery_limit: u64, deliveries_per_house: u64) -> u64 {
         }
 
         // stop when the input number is reached
-        if (sum_of_elf_numbers * deliveries_per_house) >= INPUT {
+        if (sum_of_elf_numbers * deliveries_per_house) >= input {
             return house_number;
         }
         house_number += 1;
     }
 }
 
-fn main() {
-    // part 1: infinite deliveries per elf, and 10 presents per house
-    println!("{}", solver(u64::MAX, 10));
-    // part 2: 50 deliveries per elf, and 11 presents per house
-    println!("{}", solver(50, 11));
+// The input is the number of presents to look for
+fn load(filename: &str) -> Result<u64, String> {
+    let text = fs::read_to_string(filename).map_err(|e| format!("{}: {}", filename, e))?;
+    return text.trim().parse().map_err(|_| format!("{}: '{}' is not a number", filename, text.trim()));
+}
+
+fn part1(filename: &str) -> Result<u64, String> {
+    // infinite deliveries per elf, and 10 presents per house
+    return Ok(solver(load(filename)?, u64::MAX, 10));
+}
+
+fn part2(filename: &str) -> Result<u64, String> {
+    // 50 deliveries per elf, and 11 presents per house
+    return Ok(solver(load(filename)?, 50, 11));
+}
+
+pub struct Solution;
+
+impl aoc::Puzzle for Solution {
+    fn part1(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part1(filename)?.to_string());
+    }
+
+    fn part2(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part2(filename)?.to_string());
+    }
+}
+
+fn main() -> Result<(), String> {
+    println!("{}", part1("input")?);
+    println!("{}", part2("input")?);
+    return Ok(());
 }
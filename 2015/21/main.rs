@@ -1,4 +1,5 @@
 
+use std::fs;
 use std::ops::Add;
 
 #[derive(Copy, Clone)]
@@ -60,21 +61,35 @@ fn attack (attacker: &Modifier, defender: &Modifier, defender_hp: &mut u32) {
     }
 }
 
-fn is_winner (player: &Modifier) -> bool {
-    let mut player_hp: u32 = 100;
-    let mut boss_hp: u32 = 104;
-    let boss = Modifier {
+// The boss's hit points and stats, from lines like "Hit Points: 104"
+fn load(filename: &str) -> Result<(u32, Modifier), String> {
+    let text = fs::read_to_string(filename).map_err(|e| format!("{}: {}", filename, e))?;
+    let mut stats: Vec<u32> = Vec::new();
+    for line in text.lines() {
+        let value = line.split_once(':').and_then(|(_, value)| value.trim().parse().ok())
+            .ok_or(format!("{}: '{}' should be like 'Hit Points: 104'", filename, line))?;
+        stats.push(value);
+    }
+    if stats.len() != 3 {
+        return Err(format!("{}: there should be hit points, damage and armor", filename));
+    }
+    return Ok((stats[0], Modifier {
         cost: 0,
-        damage: 8,
-        armor: 1,
-    };
+        damage: stats[1],
+        armor: stats[2],
+    }));
+}
+
+fn is_winner (player: &Modifier, boss: &Modifier, boss_hp: u32) -> bool {
+    let mut player_hp: u32 = 100;
+    let mut boss_hp: u32 = boss_hp;
 
     loop {
-        attack(player, &boss, &mut boss_hp);
+        attack(player, boss, &mut boss_hp);
         if boss_hp == 0 {
             return true;
         }
-        attack(&boss, player, &mut player_hp);
+        attack(boss, player, &mut player_hp);
         if player_hp == 0 {
             return false;
         }
@@ -82,7 +97,9 @@ fn is_winner (player: &Modifier) -> bool {
 }
 
 
-fn main() {
+// The least gold that wins and the most gold that loses
+fn costs(filename: &str) -> Result<(u32, u32), String> {
+    let (boss_hp, boss) = load(filename)?;
     let mut min_cost_to_win: u32 = u32::MAX;
     let mut max_cost_to_lose: u32 = 0;
     for wi in 0 .. WEAPONS.len() {
@@ -93,7 +110,7 @@ fn main() {
                 let r1 = RINGS[r1i] + a;
                 for r2i in r1i .. RINGS.len() {
                     let r2 = RINGS[r2i] + r1;
-                    if is_winner (&r2) {
+                    if is_winner (&r2, &boss, boss_hp) {
                         min_cost_to_win = u32::min(r2.cost, min_cost_to_win);
                     } else {
                         max_cost_to_lose = u32::max(r2.cost, max_cost_to_lose);
@@ -102,6 +119,31 @@ fn main() {
             }
         }
     }
-    println!("{}", min_cost_to_win);
-    println!("{}", max_cost_to_lose);
+    return Ok((min_cost_to_win, max_cost_to_lose));
+}
+
+fn part1(filename: &str) -> Result<u32, String> {
+    return Ok(costs(filename)?.0);
+}
+
+fn part2(filename: &str) -> Result<u32, String> {
+    return Ok(costs(filename)?.1);
+}
+
+pub struct Solution;
+
+impl aoc::Puzzle for Solution {
+    fn part1(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part1(filename)?.to_string());
+    }
+
+    fn part2(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part2(filename)?.to_string());
+    }
+}
+
+fn main() -> Result<(), String> {
+    println!("{}", part1("input")?);
+    println!("{}", part2("input")?);
+    return Ok(());
 }
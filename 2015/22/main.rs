@@ -1,4 +1,6 @@
 
+use std::fs;
+
 const SHIELD_ARMOR: i32 = 7;
 const MAGIC_MISSILE_COST: i32 = 53;
 const DRAIN_COST: i32 = 73;
@@ -248,12 +250,53 @@ fn test_part1_search() {
                                     POISON_COST + MAGIC_MISSILE_COST);
 }
 
-fn main() {
-    let mut least_spent = search(51, 9, 50, 500, false);
-    assert!(least_spent < i32::MAX);
-    println!("{}", least_spent);
+// The boss's hit points and damage, from lines like "Hit Points: 51"
+fn load(filename: &str) -> Result<(i32, i32), String> {
+    let text = fs::read_to_string(filename).map_err(|e| format!("{}: {}", filename, e))?;
+    let mut stats: Vec<i32> = Vec::new();
+    for line in text.lines() {
+        let value = line.split_once(':').and_then(|(_, value)| value.trim().parse().ok())
+            .ok_or(format!("{}: '{}' should be like 'Hit Points: 51'", filename, line))?;
+        stats.push(value);
+    }
+    if stats.len() != 2 {
+        return Err(format!("{}: there should be hit points and damage", filename));
+    }
+    return Ok((stats[0], stats[1]));
+}
+
+// The least mana spent to win, if the boss can be beaten at all
+fn least_spent(filename: &str, hard: bool) -> Result<i32, String> {
+    let (boss_hp, boss_damage) = load(filename)?;
+    let least_spent = search(boss_hp, boss_damage, 50, 500, hard);
+    if least_spent == i32::MAX {
+        return Err("the boss cannot be beaten".to_string());
+    }
+    return Ok(least_spent);
+}
+
+fn part1(filename: &str) -> Result<i32, String> {
+    return least_spent(filename, false);
+}
+
+fn part2(filename: &str) -> Result<i32, String> {
+    return least_spent(filename, true);
+}
+
+pub struct Solution;
+
+impl aoc::Puzzle for Solution {
+    fn part1(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part1(filename)?.to_string());
+    }
+
+    fn part2(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part2(filename)?.to_string());
+    }
+}
 
-    least_spent = search(51, 9, 50, 500, true);
-    assert!(least_spent < i32::MAX);
-    println!("{}", least_spent);
+fn main() -> Result<(), String> {
+    println!("{}", part1("input")?);
+    println!("{}", part2("input")?);
+    return Ok(());
 }
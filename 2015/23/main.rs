@@ -74,9 +74,9 @@ fn execute_one(inst: &Instruction, state: &mut State) {
 
 
 
-fn main() {
+fn load(filename: &str) -> Vec<Instruction> {
     // read program
-    let file = File::open("input").unwrap();
+    let file = File::open(filename).unwrap();
     let lines = io::BufReader::new(file).lines();
     let mut program: Vec<Instruction> = Vec::new();
     for line in lines {
@@ -98,23 +98,43 @@ fn main() {
             });
         }
     }
+    return program;
+}
 
-    // part 1
-    let initial_state = State {
+// Run the program with register a set to `a`, and return register b
+fn run(program: &[Instruction], a: usize) -> usize {
+    let mut state = State {
         regs: [0, 0],
         pc: 0,
     };
-    let mut state = initial_state;
+    state.regs[parse_register("a")] = a;
     while let Some(inst) = program.get(state.pc) {
         execute_one(&inst, &mut state);
     }
-    println!("{}", state.regs[parse_register("b")]);
+    return state.regs[parse_register("b")];
+}
 
-    // part 2
-    state = initial_state;
-    state.regs[parse_register("a")] = 1;
-    while let Some(inst) = program.get(state.pc) {
-        execute_one(&inst, &mut state);
+fn part1(filename: &str) -> usize {
+    return run(&load(filename), 0);
+}
+
+fn part2(filename: &str) -> usize {
+    return run(&load(filename), 1);
+}
+
+pub struct Solution;
+
+impl aoc::Puzzle for Solution {
+    fn part1(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part1(filename).to_string());
+    }
+
+    fn part2(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part2(filename).to_string());
     }
-    println!("{}", state.regs[parse_register("b")]);
+}
+
+fn main() {
+    println!("{}", part1("input"));
+    println!("{}", part2("input"));
 }
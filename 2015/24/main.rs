@@ -114,9 +114,9 @@ fn do_select(state: &mut State) {
 }
 
 
-fn solver(num_compartments: usize) {
+fn solver(filename: &str, num_compartments: usize) -> f64 {
     // read input
-    let file = File::open("input").unwrap();
+    let file = File::open(filename).unwrap();
     let lines = io::BufReader::new(file).lines();
     let mut weight: Vec<usize> = Vec::new();
     for line in lines {
@@ -150,10 +150,30 @@ fn solver(num_compartments: usize) {
     };
     do_select(&mut state);
 
-    println!("{}", state.best.compartment_1_qe);
+    return state.best.compartment_1_qe;
+}
+
+fn part1(filename: &str) -> f64 {
+    return solver(filename, 3);
+}
+
+fn part2(filename: &str) -> f64 {
+    return solver(filename, 4);
+}
+
+pub struct Solution;
+
+impl aoc::Puzzle for Solution {
+    fn part1(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part1(filename).to_string());
+    }
+
+    fn part2(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part2(filename).to_string());
+    }
 }
 
 fn main() {
-    solver(3);
-    solver(4);
+    println!("{}", part1("input"));
+    println!("{}", part2("input"));
 }
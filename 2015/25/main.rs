@@ -1,14 +1,5 @@
 
-use std::fs::File;
-use std::io::{self, BufRead};
-
-// input:
-// To continue, please consult the code grid in the manual.  
-// Enter the code at row 2947, column 3029.
-//
-
-const ROW: u64 = 2947;
-const COLUMN: u64 = 3029;
+use std::fs;
 
 const FIRST_CODE: u64 = 20151125;
 const MULTIPLY_BY: u64 = 252533;
@@ -27,7 +18,7 @@ fn row_column_to_linear(row: u64, col: u64) -> u64 {
 
 fn compute_value(linear: u64) -> u64 {
     let mut value = FIRST_CODE;
-    for i in 1 .. linear {
+    for _ in 1 .. linear {
         value = (value * MULTIPLY_BY) % MODULO_BY;
     }
     return value;
@@ -52,7 +43,36 @@ fn test_linear() {
     assert_eq!(compute_value(3), 18749137);
 }
 
-fn main() {
-    println!("{}", compute_value(row_column_to_linear(ROW, COLUMN)));
+// The input is a sentence like "Enter the code at row 2947, column 3029."
+fn load(filename: &str) -> Result<(u64, u64), String> {
+    let text = fs::read_to_string(filename).map_err(|e| format!("{}: {}", filename, e))?;
+    let numbers: Vec<u64> = text.split(|c: char| !c.is_ascii_digit())
+        .filter_map(|word| word.parse().ok()).collect();
+    if numbers.len() != 2 || numbers.contains(&0) {
+        return Err(format!("{}: there should be a row and a column, counting from 1", filename));
+    }
+    return Ok((numbers[0], numbers[1]));
+}
+
+fn part1(filename: &str) -> Result<u64, String> {
+    let (row, column) = load(filename)?;
+    return Ok(compute_value(row_column_to_linear(row, column)));
+}
+
+pub struct Solution;
+
+impl aoc::Puzzle for Solution {
+    fn part1(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part1(filename)?.to_string());
+    }
+
+    fn part2(self: &Self, _filename: &str) -> Result<String, String> {
+        return Err("there is no part 2 on day 25".to_string());
+    }
+}
+
+fn main() -> Result<(), String> {
+    println!("{}", part1("input")?);
+    return Ok(());
 }
 
@@ -0,0 +1,229 @@
+// Maps of asteroids, as seen from a monitoring station on one of them.
+// Directions from the station are compared exactly, using integers only:
+// first by which half of the sweep they are in, then by the sign of their
+// cross product. Asteroids in the same direction block each other, so the
+// number of different directions is the number of asteroids visible.
+
+use std::fs;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+
+pub type Word = i64;
+
+#[derive(Hash, Eq, PartialEq, Ord, PartialOrd, Copy, Clone, Debug)]
+pub struct Asteroid {
+    pub x: Word,
+    pub y: Word,
+}
+
+impl raster::Position for Asteroid {
+    fn position(self: &Self) -> (i64, i64) {
+        return (self.x, self.y);
+    }
+}
+
+// The direction of a vector from the station, with y increasing downwards.
+// Directions are ordered clockwise from straight up, as the laser turns;
+// vectors pointing the same way are equal whatever their length.
+#[derive(Copy, Clone, Debug)]
+pub struct Direction {
+    dx: Word,
+    dy: Word,
+}
+
+impl Direction {
+    // 0 from straight up round to just before straight down, 1 for the rest
+    fn half(self: &Self) -> u8 {
+        return if self.dx > 0 || (self.dx == 0 && self.dy < 0) { 0 } else { 1 };
+    }
+}
+
+impl Ord for Direction {
+    fn cmp(self: &Self, other: &Self) -> Ordering {
+        // within a half, a positive cross product means `other` is further round
+        let cross = (self.dx as i128) * (other.dy as i128) - (self.dy as i128) * (other.dx as i128);
+        return self.half().cmp(&other.half()).then(0.cmp(&cross));
+    }
+}
+
+impl PartialOrd for Direction {
+    fn partial_cmp(self: &Self, other: &Self) -> Option<Ordering> {
+        return Some(self.cmp(other));
+    }
+}
+
+impl PartialEq for Direction {
+    fn eq(self: &Self, other: &Self) -> bool {
+        return self.cmp(other) == Ordering::Equal;
+    }
+}
+
+impl Eq for Direction {}
+
+pub struct Map {
+    // in order of y, then x
+    pub asteroids: Vec<Asteroid>,
+}
+
+impl Map {
+    // '#' is an asteroid; the map can be any size
+    pub fn parse(text: &str) -> Self {
+        let mut asteroids: Vec<Asteroid> = Vec::new();
+        for (y, line) in text.lines().enumerate() {
+            for (x, ch) in line.chars().enumerate() {
+                if ch == '#' {
+                    asteroids.push(Asteroid { x: x as Word, y: y as Word });
+                }
+            }
+        }
+        asteroids.sort_by_key(|a| (a.y, a.x));
+        return Map { asteroids: asteroids };
+    }
+
+    pub fn load(filename: &str) -> Result<Self, String> {
+        let text = fs::read_to_string(filename).map_err(|e| format!("{}: {}", filename, e))?;
+        return Ok(Map::parse(&text));
+    }
+
+    // Every other asteroid, with its direction from `station`, in the
+    // order they would be vaporised on the first pass and then by distance
+    fn targets(self: &Self, station: Asteroid) -> Vec<(Direction, Asteroid)> {
+        let mut targets: Vec<(Direction, Asteroid)> = self.asteroids.iter()
+            .filter(|a| **a != station)
+            .map(|a| (Direction { dx: a.x - station.x, dy: a.y - station.y }, *a))
+            .collect();
+        targets.sort_by(|(d1, a1), (d2, a2)| {
+            let distance1 = (d1.dx.abs(), d1.dy.abs());
+            let distance2 = (d2.dx.abs(), d2.dy.abs());
+            return d1.cmp(d2).then(distance1.cmp(&distance2)).then(a1.cmp(a2));
+        });
+        return targets;
+    }
+
+    // The number of other asteroids visible from `station`
+    pub fn visible_from(self: &Self, station: Asteroid) -> usize {
+        let targets = self.targets(station);
+        let mut count = 0;
+        for (i, (direction, _)) in targets.iter().enumerate() {
+            if i == 0 || targets[i - 1].0 != *direction {
+                count += 1;
+            }
+        }
+        return count;
+    }
+
+    // The number of asteroids visible from each asteroid
+    pub fn visibility(self: &Self) -> HashMap<Asteroid, usize> {
+        return self.asteroids.iter().map(|a| (*a, self.visible_from(*a))).collect();
+    }
+
+    // The asteroid that can see the most others, and how many it sees.
+    // A tie goes to the first in order of y, then x.
+    pub fn best_station(self: &Self) -> Option<(Asteroid, usize)> {
+        let mut best: Option<(Asteroid, usize)> = None;
+        for station in &self.asteroids {
+            let count = self.visible_from(*station);
+            if best.is_none() || count > best.unwrap().1 {
+                best = Some((*station, count));
+            }
+        }
+        return best;
+    }
+
+    // The other asteroids in the order that a laser at `station` destroys
+    // them: it starts pointing straight up, turns clockwise, and destroys
+    // the nearest asteroid in each direction as it passes
+    pub fn vaporise(self: &Self, station: Asteroid) -> Vaporisation {
+        let mut directions: VecDeque<VecDeque<Asteroid>> = VecDeque::new();
+        let mut previous: Option<Direction> = None;
+        for (direction, asteroid) in self.targets(station) {
+            if previous != Some(direction) {
+                directions.push_back(VecDeque::new());
+                previous = Some(direction);
+            }
+            directions.back_mut().unwrap().push_back(asteroid);
+        }
+        return Vaporisation { directions: directions };
+    }
+}
+
+pub struct Vaporisation {
+    // directions still to be passed, each with its asteroids nearest first;
+    // a direction goes to the back after the laser passes it
+    directions: VecDeque<VecDeque<Asteroid>>,
+}
+
+impl Iterator for Vaporisation {
+    type Item = Asteroid;
+
+    fn next(self: &mut Self) -> Option<Asteroid> {
+        let mut direction = self.directions.pop_front()?;
+        let asteroid = direction.pop_front();
+        if !direction.is_empty() {
+            self.directions.push_back(direction);
+        }
+        return asteroid;
+    }
+}
+
+// The number visible from each asteroid, from dark blue for the fewest
+// to yellow for the most, with black where there is no asteroid
+pub fn heatmap(map: &Map, scale: usize) -> raster::Image {
+    let counts = map.visibility();
+    let low = counts.values().min().copied().unwrap_or(0);
+    let high = counts.values().max().copied().unwrap_or(0);
+    return raster::render(&counts, |count| match count {
+        None => raster::BLACK,
+        Some(count) => {
+            let t = ((count - low) * 255 / usize::max(1, high - low)) as u8;
+            [t, t, 128 - t / 2]
+        },
+    }, scale);
+}
+
+#[test]
+fn test_directions() {
+    let d = |dx: Word, dy: Word| Direction { dx: dx, dy: dy };
+    let clockwise = [d(0, -1), d(1, -3), d(1, -1), d(1, 0), d(2, 1), d(0, 1), d(-1, 1),
+                     d(-1, 0), d(-5, -1), d(-1, -1)];
+    for i in 1 .. clockwise.len() {
+        assert!(clockwise[i - 1] < clockwise[i]);
+    }
+    assert_eq!(d(3, -3), d(1, -1));
+    assert_eq!(d(0, 7), d(0, 1));
+    assert!(d(2, 0) != d(-2, 0));
+
+    // too close together to tell apart with floating point angles
+    let a = d(1_000_000_000, 999_999_999);
+    let b = d(999_999_999, 999_999_998);
+    assert!((a.dy as f64).atan2(a.dx as f64) == (b.dy as f64).atan2(b.dx as f64));
+    assert!(b < a);
+}
+
+#[test]
+fn test_large_map() {
+    // coordinates well beyond the range of an i8
+    let mut map = Map { asteroids: vec![Asteroid { x: 0, y: 0 }, Asteroid { x: 200, y: 100 },
+                                         Asteroid { x: 400, y: 200 }, Asteroid { x: 1000, y: 3 }] };
+    map.asteroids.sort_by_key(|a| (a.y, a.x));
+    assert_eq!(map.visible_from(Asteroid { x: 0, y: 0 }), 2);
+    assert_eq!(map.visible_from(Asteroid { x: 200, y: 100 }), 3);
+    assert_eq!(map.best_station(), Some((Asteroid { x: 1000, y: 3 }, 3)));
+    let order: Vec<Asteroid> = map.vaporise(Asteroid { x: 0, y: 0 }).collect();
+    assert_eq!(order, vec![Asteroid { x: 1000, y: 3 }, Asteroid { x: 200, y: 100 },
+                           Asteroid { x: 400, y: 200 }]);
+}
+
+#[test]
+fn test_heatmap() {
+    let map = Map::parse(".#..#\n.....\n#####\n....#\n...##");
+    let counts = map.visibility();
+    assert_eq!(counts[&Asteroid { x: 3, y: 4 }], 8);
+    assert_eq!(counts[&Asteroid { x: 4, y: 2 }], 5);
+    let image = heatmap(&map, 1);
+    assert_eq!((image.width, image.height), (5, 5));
+    assert_eq!(image.pixels[0], raster::BLACK);
+    assert_eq!(image.pixels[4 * 5 + 3], [255, 255, 1]);
+    assert_eq!(image.pixels[2 * 5 + 4], [0, 0, 128]);
+}
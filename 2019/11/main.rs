@@ -1,156 +1,10 @@
 
-use std::fs::File;
-use std::io::{self, BufRead};
 use std::collections::HashMap;
+use std::env;
 
-
-type Word = i64;
-type Memory = HashMap<Word, Word>;
-type InputOutput = Vec<Word>;
-
-struct MachineState {
-    memory: Memory,
-    input: InputOutput,
-    output: InputOutput,
-    pc: Word,
-    relative_base: Word,
-}
-
-fn load_from_input(filename: &str) -> MachineState {
-    let file = File::open(filename).unwrap();
-    let line = io::BufReader::new(file).lines().next().expect("line").unwrap();
-    return load(line.as_str());
-}
-
-fn load(line: &str) -> MachineState {
-    let mut memory: Memory = HashMap::new();
-    let mut index: Word = 0;
-    for code in line.split(",") {
-        let trimmed = code.trim();
-        let parsed: Word = trimmed.parse().expect("number");
-        memory.insert(index, parsed);
-        index += 1;
-    }
-    return MachineState {
-        memory: memory,
-        input: Vec::new(),
-        output: Vec::new(),
-        pc: 0,
-        relative_base: 0,
-    };
-}
-
-fn get_parameter_mode(opcode: Word, index: Word) -> Word {
-    assert!(index >= 1);
-    let mut copy = opcode / 100;
-    for _ in 1 .. index {
-        copy = copy / 10;
-    }
-    return copy % 10;
-}
-
-fn load_memory(ms: &mut MachineState, address: Word) -> Word {
-    return *ms.memory.get(&address).unwrap_or(&0);
-}
-
-fn store_memory(ms: &mut MachineState, address: Word, value: Word) {
-    ms.memory.insert(address, value);
-}
-
-fn load_parameter(ms: &mut MachineState, index: Word) -> Word {
-    assert!(index >= 1);
-    let opcode = load_memory(ms, ms.pc);
-    let parameter = load_memory(ms, ms.pc + index);
-
-    return match get_parameter_mode(opcode, index) {
-        0 => load_memory(ms, parameter),
-        1 => parameter,
-        2 => load_memory(ms, parameter + ms.relative_base),
-        _ => panic!(),
-    }
-}
-
-fn store_parameter(ms: &mut MachineState, index: Word, value: Word) {
-    assert!(index >= 1);
-    let opcode = load_memory(ms, ms.pc);
-    let parameter = load_memory(ms, ms.pc + index);
-
-    match get_parameter_mode(opcode, index) {
-        0 => store_memory(ms, parameter, value),
-        1 => panic!(),
-        2 => store_memory(ms, parameter + ms.relative_base, value),
-        _ => panic!(),
-    }
-}
-
-fn run(ms: &mut MachineState) -> Option<Word> {
-    loop {
-        let opcode = load_memory(ms, ms.pc);
-        let a = load_parameter(ms, 1);
-        let b = load_parameter(ms, 2);
-        match opcode % 100 {
-            1 => {
-                store_parameter(ms, 3, a + b);
-                ms.pc += 4;
-            },
-            2 => {
-                store_parameter(ms, 3, a * b);
-                ms.pc += 4;
-            },
-            3 => {
-                if ms.input.is_empty() {
-                    return None;
-                }
-                let v = ms.input.pop().unwrap_or(0);
-                store_parameter(ms, 1, v);
-                ms.pc += 2;
-            },
-            4 => {
-                ms.pc += 2;
-                ms.output.push(a);
-            },
-            5 => {
-                ms.pc += 3;
-                if a != 0 {
-                    ms.pc = b;
-                }
-            },
-            6 => {
-                ms.pc += 3;
-                if a == 0 {
-                    ms.pc = b;
-                }
-            },
-            7 => {
-                if a < b {
-                    store_parameter(ms, 3, 1);
-                } else {
-                    store_parameter(ms, 3, 0);
-                }
-                ms.pc += 4;
-            },
-            8 => {
-                if a == b {
-                    store_parameter(ms, 3, 1);
-                } else {
-                    store_parameter(ms, 3, 0);
-                }
-                ms.pc += 4;
-            },
-            9 => {
-                ms.relative_base += a;
-                ms.pc += 2;
-            },
-            99 => {
-                return Some(load_memory(ms, 0));
-            },
-            _ => {
-                println!("illegal instruction {} at {}", opcode, ms.pc);
-                panic!();
-            }
-        }
-    }
-}
+extern crate intcode;
+use intcode::{Word, MachineState, load_from_input, run};
+use raster::{Position, Palette, BLACK, WHITE, render};
 
 #[derive(Eq, PartialEq, Hash, Copy, Clone)]
 struct Location {
@@ -158,22 +12,28 @@ struct Location {
     y: Word,
 }
 
+impl Position for Location {
+    fn position(self: &Self) -> (i64, i64) {
+        return (self.x, self.y);
+    }
+}
+
 type Painting = HashMap<Location, Word>;
 
-fn paint(initial: Word) -> Painting {
-    let mut ms: MachineState = load_from_input("input");
+fn paint(filename: &str, initial: Word) -> Painting {
+    let mut ms: MachineState = load_from_input(filename);
     let mut painting: Painting = HashMap::new();
     let mut loc = Location { x: 0, y: 0 };
     let mut dir = 0;
 
     painting.insert(loc, initial);
     loop {
-        ms.input.push(*painting.get(&loc).unwrap_or(&0));
-        let rc = run(&mut ms);
-        let second_out = ms.output.pop(); 
-        let first_out = ms.output.pop(); 
-        assert!(!first_out.is_none());
-        assert!(!second_out.is_none());
+        ms.push_input(*painting.get(&loc).unwrap_or(&0));
+        let rc = run(&mut ms).unwrap();
+        let first_out = ms.pop_output();
+        let second_out = ms.pop_output();
+        assert!(first_out.is_some());
+        assert!(second_out.is_some());
         assert!((first_out.unwrap() == 0) || (first_out.unwrap() == 1));
         painting.insert(loc, first_out.unwrap());
         match second_out.unwrap() {
@@ -197,12 +57,16 @@ fn paint(initial: Word) -> Painting {
     return painting;
 }
 
-fn part1() {
-    println!("{}", paint(0).len());
+fn part1(filename: &str) -> usize {
+    return paint(filename, 0).len();
 }
 
-fn part2() {
-    let painting = paint(1);
+fn part2(filename: &str, image: Option<&String>) -> Result<String, String> {
+    let painting = paint(filename, 1);
+    if let Some(filename) = image {
+        let palette = Palette::new([(1, WHITE)], BLACK);
+        render(&painting, |v| palette.colour(v), 4).save(filename)?;
+    }
     let mut x1 = Word::MAX;
     let mut y1 = Word::MAX;
     let mut x2 = Word::MIN;
@@ -213,21 +77,47 @@ fn part2() {
         y1 = Word::min(y1, loc.y);
         y2 = Word::max(y2, loc.y);
     }
+    let mut grid: Vec<Vec<bool>> = Vec::new();
     for y in y1 .. y2 + 1 {
-        for x in x1 .. x2 + 1 {
-            let loc = Location { x: x, y: y };
-            if *painting.get(&loc).unwrap_or(&0) == 1 {
-                print!("#");
-            } else {
-                print!(" ");
-            }
-        }
-        println!();
+        grid.push((x1 .. x2 + 1).map(|x| {
+            *painting.get(&Location { x: x, y: y }).unwrap_or(&0) == 1
+        }).collect());
     }
+    return ocr::recognise(&grid);
 }
 
-fn main() {
-    part1();
-    part2();
+#[test]
+fn test_part1() {
+    assert_eq!(part1("input"), 2469);
+}
+
+#[test]
+fn test_part2() {
+    assert_eq!(part2("input", None), Ok("KLCZAEGU".to_string()));
+}
+
+pub struct Solution;
+
+impl aoc::Puzzle for Solution {
+    fn part1(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part1(filename).to_string());
+    }
+
+    fn part2(self: &Self, filename: &str) -> Result<String, String> {
+        return part2(filename, None);
+    }
+}
+
+fn main() -> Result<(), String> {
+    let args: Vec<String> = env::args().collect();
+    let image = match args.len() {
+        1 => None,
+        3 if args[1] == "--image" => Some(&args[2]),
+        _ => { return Err("Optionally, give --image <file> to save the part 2 \
+                           registration identifier as a .pbm, .pgm or .ppm file".to_string()); },
+    };
+    println!("{}", part1("input"));
+    println!("{}", part2("input", image)?);
+    return Ok(());
 }
 
@@ -1,163 +1,296 @@
+use std::env;
+use std::fs;
+use std::thread;
 
-use std::fs::File;
-use std::io::{self, BufRead};
-use std::iter::FromIterator;
 
-
-const NUM_DIMENSIONS: usize = 3;
-const NUM_PLANETS: usize = 4;
-
-type Word = i16;
+type Word = i64;
 type Time = u64;
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
 struct Dimension {
     position: Word,
     velocity: Word,
 }
 
-type Planet = [Dimension; NUM_DIMENSIONS];
-type System = [Planet; NUM_PLANETS];
-
-fn load_values(filename: &str) -> System {
-    let file = File::open(filename).unwrap();
-    let mut system: System = [[Dimension {
-        position: 0,
-        velocity: 0,
-    }; NUM_DIMENSIONS]; NUM_PLANETS];
-    let mut p1: usize = 0;
-
-    for line in io::BufReader::new(file).lines() {
-        if let Ok(line_string) = line {
-            let cleaned = line_string.replace("=", " ")
-                                     .replace(",", " ")
-                                     .replace(">", " ");
-            let fields = Vec::from_iter(cleaned.split_ascii_whitespace());
-            assert!(fields.len() >= 6);
-            assert!(p1 < NUM_PLANETS);
-            for d in 0 .. NUM_DIMENSIONS {
-                system[p1][d].position =
-                    fields.get((d * 2) + 1).unwrap().parse().expect("x");
+// One dimension of every body; the dimensions don't affect each other
+type Axis = Vec<Dimension>;
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+struct System {
+    // names of the dimensions, e.g. x, y, z
+    names: Vec<String>,
+    // axes[d][b] is dimension d of body b
+    axes: Vec<Axis>,
+}
+
+impl System {
+    // One body per line, like <x=-1, y=0, z=2>; every body must have the
+    // same dimensions, in the same order
+    fn parse(text: &str) -> Result<Self, String> {
+        let mut names: Vec<String> = Vec::new();
+        let mut axes: Vec<Axis> = Vec::new();
+        let mut bodies = 0;
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let fields = line.trim_start_matches('<').trim_end_matches('>');
+            let mut body: Vec<(String, Word)> = Vec::new();
+            for field in fields.split(',') {
+                let (name, value) = field.split_once('=')
+                    .ok_or(format!("'{}' should be like <x=1, y=2>", line))?;
+                let value: Word = value.trim().parse()
+                    .map_err(|_| format!("'{}' is not a number", value.trim()))?;
+                body.push((name.trim().to_string(), value));
+            }
+            if bodies == 0 {
+                names = body.iter().map(|(name, _)| name.clone()).collect();
+                axes = vec![Vec::new(); names.len()];
+            }
+            if body.iter().map(|(name, _)| name).ne(names.iter()) {
+                return Err(format!("'{}' should have the dimensions {}", line, names.join(", ")));
+            }
+            for (d, (_, position)) in body.iter().enumerate() {
+                axes[d].push(Dimension { position: *position, velocity: 0 });
+            }
+            bodies += 1;
+        }
+        if bodies == 0 {
+            return Err("there are no bodies".to_string());
+        }
+        return Ok(System { names: names, axes: axes });
+    }
+
+    fn load(filename: &str) -> Result<Self, String> {
+        let text = fs::read_to_string(filename).map_err(|e| format!("{}: {}", filename, e))?;
+        return Self::parse(&text).map_err(|e| format!("{}: {}", filename, e));
+    }
+
+    fn bodies(self: &Self) -> usize {
+        return self.axes[0].len();
+    }
+
+    fn step(self: &mut Self) {
+        for axis in self.axes.iter_mut() {
+            simulate(axis);
+        }
+    }
+
+    // total energy: for each body, the sum of the absolute positions
+    // multiplied by the sum of the absolute velocities
+    fn energy(self: &Self) -> Word {
+        let mut total: Word = 0;
+        for b in 0 .. self.bodies() {
+            let mut energy1: Word = 0;
+            let mut energy2: Word = 0;
+            for axis in &self.axes {
+                energy1 += Word::abs(axis[b].position);
+                energy2 += Word::abs(axis[b].velocity);
             }
-            p1 += 1;
+            total += energy1 * energy2;
         }
+        return total;
     }
-    return system;
 }
 
-fn simulate(system: &mut System, d: usize) {
+fn simulate(axis: &mut Axis) {
     // Gravity
-    for p1 in 0 .. NUM_PLANETS {
-        let pos1 = system[p1][d].position;
-        for p2 in 0 .. NUM_PLANETS {
-            let pos2 = system[p2][d].position;
+    for p1 in 0 .. axis.len() {
+        let pos1 = axis[p1].position;
+        for p2 in 0 .. axis.len() {
+            let pos2 = axis[p2].position;
             if pos1 < pos2 {
-                system[p1][d].velocity += 1;
+                axis[p1].velocity += 1;
             } else if pos1 > pos2 {
-                system[p1][d].velocity -= 1;
+                axis[p1].velocity -= 1;
             }
         }
     }
     // Position
-    for p1 in 0 .. NUM_PLANETS {
-        system[p1][d].position +=
-            system[p1][d].velocity;
+    for dimension in axis.iter_mut() {
+        dimension.position += dimension.velocity;
     }
 }
 
-fn part1(filename: &str, num_steps: Time) -> Word {
-    let mut system = load_values(filename);
-
-    // For each time step
-    for _ in 0 .. num_steps {
-        // For each dimension
-        for d in 0 .. NUM_DIMENSIONS {
-            simulate(&mut system, d);
+// The total energy and the position of every body after each of the first
+// `num_steps` steps, starting with the initial state, as CSV
+fn to_csv(initial: &System, num_steps: Time) -> String {
+    let mut system = initial.clone();
+    let mut header: Vec<String> = vec!["step".to_string(), "energy".to_string()];
+    for b in 0 .. system.bodies() {
+        for name in &system.names {
+            header.push(format!("{}{}", name, b));
         }
     }
-    // total energy
-    let mut total: Word = 0;
-    for p1 in 0 .. NUM_PLANETS {
-        let mut energy1: Word = 0;
-        let mut energy2: Word = 0;
-        for d in 0 .. NUM_DIMENSIONS {
-            energy1 += Word::abs(system[p1][d].position);
-            energy2 += Word::abs(system[p1][d].velocity);
+    let mut out = header.join(",");
+    out.push('\n');
+    for step in 0 ..= num_steps {
+        if step > 0 {
+            system.step();
         }
-        total += energy1 * energy2;
+        let mut row: Vec<String> = vec![step.to_string(), system.energy().to_string()];
+        for b in 0 .. system.bodies() {
+            for axis in &system.axes {
+                row.push(axis[b].position.to_string());
+            }
+        }
+        out.push_str(&row.join(","));
+        out.push('\n');
+    }
+    return out;
+}
+
+fn part1(filename: &str, num_steps: Time) -> Result<Word, String> {
+    let mut system = System::load(filename)?;
+
+    // For each time step
+    for _ in 0 .. num_steps {
+        system.step();
     }
-    return total;
+    return Ok(system.energy());
 }
 
 #[test]
 fn test_part1() {
-    assert_eq!(part1("test", 10), 179);
-    assert_eq!(part1("test2", 100), 1940);
+    assert_eq!(part1("test", 10), Ok(179));
+    assert_eq!(part1("test2", 100), Ok(1940));
 }
 
-fn get_period(initial: &System, d: usize) -> Time {
+fn get_period(initial: &Axis) -> Time {
     // copy of initial state
-    let mut system: System = initial.clone();
+    let mut axis: Axis = initial.clone();
 
-    // For each time step
+    // For each time step, until it returns to the initial state
     let mut num_steps: Time = 0;
-    let mut accept: bool = false;
-    while !accept {
-        // Simulate this dimension only
-        simulate(&mut system, d);
-
-        // Returned to initial state?
-        accept = true;
-        for p1 in 0 .. NUM_PLANETS {
-            if (system[p1][d].position != initial[p1][d].position)
-            || (system[p1][d].velocity != initial[p1][d].velocity) {
-                accept = false;
-                break;
-            }
-        }
+    loop {
+        simulate(&mut axis);
         num_steps += 1;
+        if axis == *initial {
+            return num_steps;
+        }
     }
-    return num_steps;
 }
 
-fn greatest_common_divisor(a: Time, b: Time) -> Time {
+// The period of each dimension, each worked out in its own thread
+fn get_periods(system: &System) -> Vec<Time> {
+    return thread::scope(|scope| {
+        let workers: Vec<_> = system.axes.iter().map(|axis| {
+            scope.spawn(move || get_period(axis))
+        }).collect();
+        return workers.into_iter().map(|w| w.join().unwrap()).collect();
+    });
+}
+
+fn greatest_common_divisor(a: u128, b: u128) -> u128 {
     let mut copy_a = a;
     let mut copy_b = b;
-    loop {
-        copy_a %= copy_b;
-        if copy_a == 0 {
-            return copy_b;
-        }
-        copy_b %= copy_a;
-        if copy_b == 0 {
-            return copy_a;
-        }
+    while copy_b != 0 {
+        (copy_a, copy_b) = (copy_b, copy_a % copy_b);
+    }
+    return copy_a;
+}
+
+// None if the result doesn't fit
+fn least_common_multiple(a: u128, b: u128) -> Option<u128> {
+    if a == 0 || b == 0 {
+        return Some(0);
     }
+    return (a / greatest_common_divisor(a, b)).checked_mul(b);
 }
 
-fn least_common_multiple(a: Time, b: Time) -> Time {
-    return (a * b) / greatest_common_divisor(a, b);
+#[test]
+fn test_least_common_multiple() {
+    assert_eq!(least_common_multiple(4, 6), Some(12));
+    assert_eq!(least_common_multiple(18, 2772), Some(2772));
+    assert_eq!(least_common_multiple(u64::MAX as u128, u64::MAX as u128), Some(u64::MAX as u128));
+    assert_eq!(least_common_multiple(u64::MAX as u128, u64::MAX as u128 - 1),
+               Some(u64::MAX as u128 * (u64::MAX as u128 - 1)));
+    assert_eq!(least_common_multiple(u128::MAX, 2), None);
 }
 
-fn part2(filename: &str) -> Time {
-    let system = load_values(filename);
+fn part2(filename: &str) -> Result<u128, String> {
+    let system = System::load(filename)?;
 
     // Calculate period for each dimension individually
-    let px = get_period(&system, 0);
-    let py = get_period(&system, 1);
-    let pz = get_period(&system, 2);
-    return least_common_multiple(px, least_common_multiple(py, pz));
+    let mut period: u128 = 1;
+    for p in get_periods(&system) {
+        period = least_common_multiple(period, p as u128)
+            .ok_or("the period is too large for a u128")?;
+    }
+    return Ok(period);
 }
 
 #[test]
 fn test_part2() {
-    assert_eq!(part2("test"), 2772);
-    assert_eq!(part2("test2"), 4686774924);
+    assert_eq!(part2("test"), Ok(2772));
+    assert_eq!(part2("test2"), Ok(4686774924));
+}
+
+#[test]
+fn test_system() {
+    // five bodies in two dimensions
+    let system = System::parse("<a=-1, b=1>\n<a=1, b=-3>\n<a=-1, b=-5>\n<a=0, b=3>\n<a=0, b=-1>\n").unwrap();
+    assert_eq!(system.names, vec!["a", "b"]);
+    assert_eq!(system.bodies(), 5);
+    assert_eq!(get_periods(&system), vec![4, 6]);
+    for (axis, period) in system.axes.iter().zip(get_periods(&system)) {
+        let mut copy = axis.clone();
+        for _ in 0 .. period {
+            simulate(&mut copy);
+        }
+        assert_eq!(&copy, axis);
+    }
+
+    assert_eq!(System::parse("<x=1, y=2>\n<x=1, z=2>").unwrap_err(),
+               "'<x=1, z=2>' should have the dimensions x, y");
+    assert_eq!(System::parse("<x=1, y=2>\n<x=1>").unwrap_err(),
+               "'<x=1>' should have the dimensions x, y");
+    assert_eq!(System::parse("<x=1, y=q>").unwrap_err(), "'q' is not a number");
+    assert_eq!(System::parse("<x=1, y 2>").unwrap_err(), "'<x=1, y 2>' should be like <x=1, y=2>");
+    assert_eq!(System::parse("\n").unwrap_err(), "there are no bodies");
+}
+
+#[test]
+fn test_csv() {
+    let system = System::parse("<x=0>\n<x=3>").unwrap();
+    assert_eq!(to_csv(&system, 3), "step,energy,x0,x1\n0,0,0,3\n1,3,1,2\n2,6,3,0\n3,5,4,-1\n");
+    let csv = to_csv(&System::load("test").unwrap(), 10);
+    assert_eq!(csv.lines().next(), Some("step,energy,x0,y0,z0,x1,y1,z1,x2,y2,z2,x3,y3,z3"));
+    assert_eq!(csv.lines().last(), Some("10,179,2,1,-3,1,-8,0,3,-6,1,2,0,4"));
 }
 
-fn main() {
-    println!("{}", part1("input", 1000));
-    println!("{}", part2("input"));
+#[test]
+fn test_answers() {
+    assert_eq!(part1("input", 1000), Ok(12070));
+    assert_eq!(part2("input"), Ok(500903629351944));
 }
 
+pub struct Solution;
+
+impl aoc::Puzzle for Solution {
+    fn part1(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part1(filename, 1000)?.to_string());
+    }
+
+    fn part2(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part2(filename)?.to_string());
+    }
+}
 
+fn main() -> Result<(), String> {
+    let args: Vec<String> = env::args().collect();
+    let filename = match args.len() {
+        1 => None,
+        3 if args[1] == "--csv" => Some(&args[2]),
+        _ => { return Err("Optionally, give --csv <file> to save the energy and positions \
+                           for each of the first 1000 steps".to_string()); },
+    };
+    println!("{}", part1("input", 1000)?);
+    println!("{}", part2("input")?);
+    if let Some(filename) = filename {
+        let csv = to_csv(&System::load("input")?, 1000);
+        fs::write(filename, csv).map_err(|e| format!("{}: {}", filename, e))?;
+    }
+    return Ok(());
+}
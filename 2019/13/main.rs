@@ -1,157 +1,19 @@
 
-use std::fs::File;
-use std::io::{self, BufRead};
 use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::thread;
+use std::time::Duration;
 
-const DEBUG: bool = false;
-
-type Word = i32;
-type Memory = HashMap<Word, Word>;
-type InputOutput = Vec<Word>;
-
-struct MachineState {
-    memory: Memory,
-    input: InputOutput,
-    output: InputOutput,
-    pc: Word,
-    relative_base: Word,
-}
-
-fn load_from_input(filename: &str) -> MachineState {
-    let file = File::open(filename).unwrap();
-    let line = io::BufReader::new(file).lines().next().expect("line").unwrap();
-    return load(line.as_str());
-}
-
-fn load(line: &str) -> MachineState {
-    let mut memory: Memory = HashMap::new();
-    let mut index: Word = 0;
-    for code in line.split(",") {
-        let trimmed = code.trim();
-        let parsed: Word = trimmed.parse().expect("number");
-        memory.insert(index, parsed);
-        index += 1;
-    }
-    return MachineState {
-        memory: memory,
-        input: Vec::new(),
-        output: Vec::new(),
-        pc: 0,
-        relative_base: 0,
-    };
-}
-
-fn get_parameter_mode(opcode: Word, index: Word) -> Word {
-    assert!(index >= 1);
-    let mut copy = opcode / 100;
-    for _ in 1 .. index {
-        copy = copy / 10;
-    }
-    return copy % 10;
-}
+extern crate intcode;
+use intcode::{Word, MachineState, Fault, load_from_input, run};
+use intcode::adapter::receive_tuples;
 
-fn load_memory(ms: &mut MachineState, address: Word) -> Word {
-    return *ms.memory.get(&address).unwrap_or(&0);
-}
-
-fn store_memory(ms: &mut MachineState, address: Word, value: Word) {
-    ms.memory.insert(address, value);
-}
-
-fn load_parameter(ms: &mut MachineState, index: Word) -> Word {
-    assert!(index >= 1);
-    let opcode = load_memory(ms, ms.pc);
-    let parameter = load_memory(ms, ms.pc + index);
-
-    return match get_parameter_mode(opcode, index) {
-        0 => load_memory(ms, parameter),
-        1 => parameter,
-        2 => load_memory(ms, parameter + ms.relative_base),
-        _ => panic!(),
-    }
-}
-
-fn store_parameter(ms: &mut MachineState, index: Word, value: Word) {
-    assert!(index >= 1);
-    let opcode = load_memory(ms, ms.pc);
-    let parameter = load_memory(ms, ms.pc + index);
-
-    match get_parameter_mode(opcode, index) {
-        0 => store_memory(ms, parameter, value),
-        1 => panic!(),
-        2 => store_memory(ms, parameter + ms.relative_base, value),
-        _ => panic!(),
-    }
-}
+mod terminal;
+use terminal::{Terminal, Key};
+use raster::{Position, Palette, BLACK, render};
 
-fn run(ms: &mut MachineState) -> Option<Word> {
-    loop {
-        let opcode = load_memory(ms, ms.pc);
-        let a = load_parameter(ms, 1);
-        let b = load_parameter(ms, 2);
-        match opcode % 100 {
-            1 => {
-                store_parameter(ms, 3, a + b);
-                ms.pc += 4;
-            },
-            2 => {
-                store_parameter(ms, 3, a * b);
-                ms.pc += 4;
-            },
-            3 => {
-                if ms.input.is_empty() {
-                    return None;
-                }
-                let v = ms.input.pop().unwrap_or(0);
-                store_parameter(ms, 1, v);
-                ms.pc += 2;
-            },
-            4 => {
-                ms.pc += 2;
-                ms.output.push(a);
-            },
-            5 => {
-                ms.pc += 3;
-                if a != 0 {
-                    ms.pc = b;
-                }
-            },
-            6 => {
-                ms.pc += 3;
-                if a == 0 {
-                    ms.pc = b;
-                }
-            },
-            7 => {
-                if a < b {
-                    store_parameter(ms, 3, 1);
-                } else {
-                    store_parameter(ms, 3, 0);
-                }
-                ms.pc += 4;
-            },
-            8 => {
-                if a == b {
-                    store_parameter(ms, 3, 1);
-                } else {
-                    store_parameter(ms, 3, 0);
-                }
-                ms.pc += 4;
-            },
-            9 => {
-                ms.relative_base += a;
-                ms.pc += 2;
-            },
-            99 => {
-                return Some(load_memory(ms, 0));
-            },
-            _ => {
-                println!("illegal instruction {} at {}", opcode, ms.pc);
-                panic!();
-            }
-        }
-    }
-}
+const DEBUG: bool = false;
 
 #[derive(Eq, PartialEq, Hash, Copy, Clone)]
 struct Location {
@@ -159,6 +21,12 @@ struct Location {
     y: Word,
 }
 
+impl Position for Location {
+    fn position(self: &Self) -> (i64, i64) {
+        return (self.x, self.y);
+    }
+}
+
 type Screen = HashMap<Location, Word>;
 
 const EMPTY: Word = 0;
@@ -168,51 +36,68 @@ const PADDLE: Word = 3;
 const BALL: Word = 4;
 
 
-fn print_screen(screen: &Screen) {
+// Frame delay when the bot is playing or a game is replayed
+const FRAME_TIME: Duration = Duration::from_millis(20);
+
+fn render_screen(screen: &Screen, newline: &str) -> String {
     let mut right: Word = 0;
     let mut bottom: Word = 0;
     for k in screen.keys() {
         right = Word::max(k.x, right);
         bottom = Word::max(k.y, bottom);
     }
+    let mut out = String::new();
     for y in 0 .. bottom + 1 {
         for x in 0 .. right + 1 {
             let loc = Location { x: x, y: y };
             let v = *screen.get(&loc).unwrap_or(&0);
-            match v {
-                EMPTY =>  { print!(" "); },
-                WALL =>   { print!("W"); },
-                BLOCK =>  { print!("b"); },
-                PADDLE => { print!("p"); },
-                BALL =>   { print!("0"); },
-                _ =>      { print!("?"); },
-            }
+            out.push(match v {
+                EMPTY =>  ' ',
+                WALL =>   'W',
+                BLOCK =>  'b',
+                PADDLE => 'p',
+                BALL =>   '0',
+                _ =>      '?',
+            });
         }
-        println!();
+        out.push_str(newline);
     }
+    return out;
+}
+
+fn print_screen(screen: &Screen) {
+    print!("{}", render_screen(screen, "\n"));
+}
+
+fn save_screen(screen: &Screen, filename: &str) -> Result<(), String> {
+    let palette = Palette::new([(WALL, [128, 128, 128]), (BLOCK, [224, 128, 32]),
+                                (PADDLE, [64, 128, 255]), (BALL, [255, 255, 255])], BLACK);
+    // the score is not part of the picture
+    let mut picture = screen.clone();
+    picture.retain(|loc, _| loc.x >= 0 && loc.y >= 0);
+    return render(&picture, |v| palette.colour(v), 8).save(filename);
 }
 
 fn update_screen(screen: &mut Screen, ms: &mut MachineState) {
-    assert_eq!(ms.output.len() % 3, 0);
-    for i in 0 .. (ms.output.len() / 3) {
-        let j = i * 3;
-        let loc = Location {
-            x: *ms.output.get(j + 0).unwrap(),
-            y: *ms.output.get(j + 1).unwrap(),
-        };
-        screen.insert(loc, *ms.output.get(j + 2).unwrap());
+    for [x, y, tile] in receive_tuples::<3>(ms) {
+        screen.insert(Location { x: x, y: y }, tile);
     }
-    ms.output.clear();
+    assert!(ms.output.is_empty());
 }
 
-fn part1() -> usize {
-    let mut ms: MachineState = load_from_input("input");
+fn initial_screen(filename: &str) -> Screen {
+    let mut ms: MachineState = load_from_input(filename);
 
-    let rc = run(&mut ms);
+    let rc = run(&mut ms).unwrap();
     assert!(rc.is_some());
 
     let mut screen: Screen = HashMap::new();
     update_screen(&mut screen, &mut ms);
+    return screen;
+}
+
+fn part1(filename: &str) -> usize {
+    let screen = initial_screen(filename);
 
     let mut count = 0;
     for v in screen.values() {
@@ -238,43 +123,204 @@ fn find_item(screen: &Screen, item: Word) -> Location {
     return found.unwrap();
 }
 
-fn part2() -> Word {
-    let mut ms: MachineState = load_from_input("input");
-    let mut screen: Screen = HashMap::new();
+// The game in free play mode
+struct Game {
+    ms: MachineState,
+    screen: Screen,
+}
+
+impl Game {
+    fn new(filename: &str) -> Self {
+        let mut ms: MachineState = load_from_input(filename);
+        // free play:
+        ms.memory.insert(0, 2);
+        return Game {
+            ms: ms,
+            screen: HashMap::new(),
+        };
+    }
 
-    // free play:
-    ms.memory.insert(0, 2);
+    // Run to the next frame; returns true when the game is over
+    fn advance(self: &mut Self) -> Result<bool, Fault> {
+        let rc = run(&mut self.ms)?;
+        assert!(self.ms.input.is_empty());
+        update_screen(&mut self.screen, &mut self.ms);
+        return Ok(rc.is_some());
+    }
+
+    fn joystick(self: &mut Self, position: Word) {
+        assert!((-1 ..= 1).contains(&position));
+        self.ms.push_input(position);
+    }
 
-    while run(&mut ms).is_none() {
-        assert!(ms.input.is_empty());
-        update_screen(&mut screen, &mut ms);
+    fn score(self: &Self) -> Word {
+        return *self.screen.get(&Location { x: -1, y: 0 }).unwrap_or(&0);
+    }
 
-        let ball = find_item(&screen, BALL);
-        let paddle = find_item(&screen, PADDLE);
+    fn blocks(self: &Self) -> usize {
+        return self.screen.values().filter(|v| **v == BLOCK).count();
+    }
 
+    fn bot(self: &Self) -> Word {
+        let ball = find_item(&self.screen, BALL);
+        let paddle = find_item(&self.screen, PADDLE);
         if paddle.x > ball.x {
             // move left
-            ms.input.push(-1);
+            return -1;
         } else if paddle.x < ball.x {
             // move right
-            ms.input.push(1);
+            return 1;
         } else {
             // don't move
-            ms.input.push(0);
+            return 0;
         }
     }
 
-    // final update
-    update_screen(&mut screen, &mut ms);
+    fn draw(self: &Self, status: &str) {
+        // in raw mode, a newline doesn't return to the start of the line
+        print!("\x1b[H\x1b[2J{}\r\nscore {}  blocks {}  {}\r\n",
+               render_screen(&self.screen, "\r\n"), self.score(), self.blocks(), status);
+    }
+}
+
+// Let the bot play, returning the score and the joystick moves
+fn part2(filename: &str) -> Result<(Word, Vec<Word>), Fault> {
+    let mut game = Game::new(filename);
+    let mut moves: Vec<Word> = Vec::new();
+    while !game.advance()? {
+        let position = game.bot();
+        game.joystick(position);
+        moves.push(position);
+    }
     if DEBUG {
-        print_screen(&screen);
+        print_screen(&game.screen);
     }
-    return *screen.get(&Location { x: -1, y: 0 }).unwrap_or(&0);
+    return Ok((game.score(), moves));
 }
 
+// Play in the terminal with the arrow keys; 'b' switches between human
+// and bot control, and 'q' quits. The moves are saved to `record`.
+fn play(record: Option<&String>) -> Result<(), String> {
+    let mut game = Game::new("input");
+    let mut moves: Vec<Word> = Vec::new();
+    let mut bot = false;
+    let mut quit = false;
+    {
+        let terminal = Terminal::new()?;
+        while !quit && !game.advance().map_err(|f| f.to_string())? {
+            game.draw(if bot { "bot playing: b for control, q to quit" }
+                      else { "arrow keys to move: b for the bot, q to quit" });
+            let mut position: Option<Word> = None;
+            while position.is_none() && !quit {
+                let key = if bot { terminal.poll() } else { Some(terminal.wait()) };
+                match key {
+                    Some(Key::Left) if !bot => { position = Some(-1); },
+                    Some(Key::Right) if !bot => { position = Some(1); },
+                    Some(Key::Stay) if !bot => { position = Some(0); },
+                    Some(Key::Toggle) => { bot = !bot; },
+                    Some(Key::Quit) => { quit = true; },
+                    Some(_) => {},
+                    None => {
+                        thread::sleep(FRAME_TIME);
+                        position = Some(game.bot());
+                    },
+                }
+            }
+            if let Some(position) = position {
+                game.joystick(position);
+                moves.push(position);
+            }
+        }
+        game.draw(if quit { "quit" } else { "game over" });
+    }
+    if let Some(record) = record {
+        save_moves(record, &moves)?;
+    }
+    return Ok(());
+}
+
+fn save_moves(filename: &str, moves: &[Word]) -> Result<(), String> {
+    let text: Vec<String> = moves.iter().map(|m| m.to_string()).collect();
+    return fs::write(filename, text.join("\n") + "\n")
+            .map_err(|e| format!("{}: {}", filename, e));
+}
+
+fn load_moves(filename: &str) -> Result<Vec<Word>, String> {
+    let text = fs::read_to_string(filename).map_err(|e| format!("{}: {}", filename, e))?;
+    let mut moves: Vec<Word> = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        match line.trim().parse() {
+            Ok(position) if (-1 ..= 1).contains(&position) => { moves.push(position); },
+            _ => { return Err(format!("{}: line {}: expected -1, 0 or 1", filename, number + 1)); },
+        }
+    }
+    return Ok(moves);
+}
+
+// Play the recorded moves again, returning the final score. The game is
+// deterministic, so the result is always the same.
+fn replay(moves: &[Word], show: bool) -> Result<Word, String> {
+    let mut game = Game::new("input");
+    let mut next = moves.iter();
+    while !game.advance().map_err(|f| f.to_string())? {
+        if show {
+            game.draw("replay");
+            thread::sleep(FRAME_TIME);
+        }
+        match next.next() {
+            Some(position) => { game.joystick(*position); },
+            None => { return Err("the recording ended before the game".to_string()); },
+        }
+    }
+    if show {
+        game.draw("replay: game over");
+    }
+    if next.next().is_some() {
+        return Err("the game ended before the recording".to_string());
+    }
+    return Ok(game.score());
+}
+
+pub struct Solution;
+
+impl aoc::Puzzle for Solution {
+    fn part1(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part1(filename).to_string());
+    }
 
-fn main() {
-    println!("{}", part1());
-    println!("{}", part2());
+    fn part2(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part2(filename).map_err(|f| f.to_string())?.0.to_string());
+    }
 }
 
+fn main() -> Result<(), String> {
+    let args: Vec<String> = env::args().collect();
+    let usage = "With no parameters, print the answers. Otherwise:\n\
+                 play [file]   play the game, recording the moves in file\n\
+                 replay <file> replay moves recorded earlier\n\
+                 image <file>  save the screen before the game starts (.pbm, .pgm or .ppm)";
+    match args.get(1).map(|a| a.as_str()) {
+        None => {
+            println!("{}", part1("input"));
+            println!("{}", part2("input").map_err(|f| f.to_string())?.0);
+        },
+        Some("play") if args.len() <= 3 => {
+            play(args.get(2))?;
+        },
+        Some("replay") if args.len() == 3 => {
+            replay(&load_moves(&args[2])?, true)?;
+        },
+        Some("image") if args.len() == 3 => {
+            save_screen(&initial_screen("input"), &args[2])?;
+        },
+        _ => { return Err(usage.to_string()); },
+    }
+    return Ok(());
+}
+
+#[test]
+fn test_record_replay() {
+    let (score, moves) = part2("input").unwrap();
+    assert_eq!(replay(&moves, false), Ok(score));
+    assert!(replay(&moves[.. moves.len() - 1], false).is_err());
+}
@@ -0,0 +1,106 @@
+// Keyboard input for the interactive game. The terminal is put into raw
+// mode with stty so that keys arrive as they are pressed; a thread reads
+// them and passes them on, so the game can check for keys without waiting.
+
+use std::io::{self, Read};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread;
+
+#[derive(Eq, PartialEq, Copy, Clone)]
+pub enum Key {
+    Left,
+    Right,
+    Stay,
+    Toggle,
+    Quit,
+}
+
+pub struct Terminal {
+    keys: Receiver<Key>,
+    saved: String,
+}
+
+fn stty(args: &[&str]) -> Result<String, String> {
+    let output = Command::new("stty").args(args).stdin(Stdio::inherit()).output()
+                    .map_err(|e| format!("stty: {}", e))?;
+    if !output.status.success() {
+        return Err("stty failed: is stdin a terminal?".to_string());
+    }
+    return Ok(String::from_utf8_lossy(&output.stdout).trim().to_string());
+}
+
+fn read_keys(keys: Sender<Key>) {
+    let mut escape: Vec<u8> = Vec::new();
+    let mut buffer = [0; 16];
+    let mut stdin = io::stdin();
+    loop {
+        let count = match stdin.read(&mut buffer) {
+            Ok(0) | Err(_) => { break; },
+            Ok(count) => count,
+        };
+        for byte in &buffer[.. count] {
+            if !key_byte(*byte, &mut escape, &keys) {
+                return;
+            }
+        }
+    }
+}
+
+// Handle one byte of input; returns false if the game has finished
+fn key_byte(byte: u8, escape: &mut Vec<u8>, keys: &Sender<Key>) -> bool {
+    // arrow keys are ESC [ A to ESC [ D
+    if !escape.is_empty() || byte == 0x1b {
+        escape.push(byte);
+        let key = match escape[..] {
+            [0x1b] | [0x1b, b'['] => { return true; },
+            [0x1b, b'[', b'D'] => Some(Key::Left),
+            [0x1b, b'[', b'C'] => Some(Key::Right),
+            [0x1b, b'[', b'B'] => Some(Key::Stay),
+            _ => None,
+        };
+        escape.clear();
+        return match key {
+            Some(key) => keys.send(key).is_ok(),
+            None => true,
+        };
+    }
+    let key = match byte {
+        b'a' | b'h' => Key::Left,
+        b'd' | b'l' => Key::Right,
+        b' ' | b's' | b'j' => Key::Stay,
+        b'b' => Key::Toggle,
+        b'q' | 3 => Key::Quit,
+        _ => { return true; },
+    };
+    return keys.send(key).is_ok();
+}
+
+impl Terminal {
+    pub fn new() -> Result<Self, String> {
+        let saved = stty(&["-g"])?;
+        stty(&["raw", "-echo"])?;
+        let (sender, receiver) = channel();
+        thread::spawn(move || read_keys(sender));
+        return Ok(Self {
+            keys: receiver,
+            saved: saved,
+        });
+    }
+
+    /// Wait for the next key. If stdin is closed, that means quit.
+    pub fn wait(self: &Self) -> Key {
+        return self.keys.recv().unwrap_or(Key::Quit);
+    }
+
+    /// The next key, if one has been pressed.
+    pub fn poll(self: &Self) -> Option<Key> {
+        return self.keys.try_recv().ok();
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(self: &mut Self) {
+        let _ = stty(&[&self.saved]);
+    }
+}
@@ -0,0 +1,289 @@
+// The nanofactory's reactions, as a graph of chemicals. Each chemical
+// except ORE is made by exactly one reaction, so working through the
+// chemicals in topological order (each before everything it is made from)
+// gives the exact amount of each that is needed: by the time a chemical
+// is reached, everything that consumes it has already been counted, and
+// it only needs to be made once, in whole batches.
+
+use std::fs;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+
+pub type Quantity = u64;
+pub type Chemical = usize;
+
+pub const ORE: &str = "ORE";
+pub const FUEL: &str = "FUEL";
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Term {
+    pub quantity: Quantity,
+    pub chemical: Chemical,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Reaction {
+    pub inputs: Vec<Term>,
+    // quantity made by each batch
+    pub output: Quantity,
+}
+
+#[derive(Debug)]
+pub struct Graph {
+    pub names: Vec<String>,
+    // reactions[c] makes chemical c; None for ORE
+    reactions: Vec<Option<Reaction>>,
+    // every chemical comes before the chemicals it is made from
+    order: Vec<Chemical>,
+    ore: Chemical,
+    fuel: Chemical,
+}
+
+// How much of one chemical is needed and how much is made,
+// which is more if it comes in batches
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Batch {
+    pub chemical: Chemical,
+    pub needed: Quantity,
+    pub made: Quantity,
+}
+
+impl Batch {
+    pub fn wasted(self: &Self) -> Quantity {
+        return self.made - self.needed;
+    }
+}
+
+impl Graph {
+    // One reaction per line, like 7 A, 1 B => 1 C
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut names: Vec<String> = Vec::new();
+        let mut ids: HashMap<String, Chemical> = HashMap::new();
+        let mut reactions: Vec<Option<Reaction>> = Vec::new();
+        let mut get_id = |name: &str, reactions: &mut Vec<Option<Reaction>>| -> Chemical {
+            if let Some(id) = ids.get(name) {
+                return *id;
+            }
+            ids.insert(name.to_string(), names.len());
+            names.push(name.to_string());
+            reactions.push(None);
+            return names.len() - 1;
+        };
+        let parse_term = |term: &str| -> Result<(Quantity, String), String> {
+            let fields: Vec<&str> = term.split_whitespace().collect();
+            let quantity: Option<Quantity> = fields.first().and_then(|q| q.parse().ok());
+            return match (fields.len(), quantity) {
+                (2, Some(q)) if q > 0 => Ok((q, fields[1].to_string())),
+                _ => Err(format!("'{}' should be a positive quantity and a chemical", term.trim())),
+            };
+        };
+
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (inputs, output) = line.split_once("=>")
+                .ok_or(format!("'{}' should be like 7 A, 1 B => 1 C", line))?;
+            let (output, name) = parse_term(output)?;
+            let id = get_id(&name, &mut reactions);
+            if name == ORE {
+                return Err(format!("'{}' makes {}, which can only be mined", line, ORE));
+            }
+            if reactions[id].is_some() {
+                return Err(format!("{} is made by more than one reaction", name));
+            }
+            let mut terms: Vec<Term> = Vec::new();
+            for input in inputs.split(',') {
+                let (quantity, name) = parse_term(input)?;
+                terms.push(Term { quantity: quantity, chemical: get_id(&name, &mut reactions) });
+            }
+            reactions[id] = Some(Reaction { inputs: terms, output: output });
+        }
+
+        let ore = get_id(ORE, &mut reactions);
+        let fuel = get_id(FUEL, &mut reactions);
+        for (id, reaction) in reactions.iter().enumerate() {
+            if reaction.is_none() && id != ore {
+                return Err(format!("no reaction makes {}", names[id]));
+            }
+        }
+        let order = Self::topological_order(&names, &reactions)?;
+        return Ok(Graph { names: names, reactions: reactions, order: order, ore: ore, fuel: fuel });
+    }
+
+    pub fn load(filename: &str) -> Result<Self, String> {
+        let text = fs::read_to_string(filename).map_err(|e| format!("{}: {}", filename, e))?;
+        return Self::parse(&text).map_err(|e| format!("{}: {}", filename, e));
+    }
+
+    // Kahn's algorithm, taking a chemical once everything that consumes it
+    // has been taken
+    fn topological_order(names: &[String], reactions: &[Option<Reaction>]) -> Result<Vec<Chemical>, String> {
+        let mut consumers: Vec<Vec<Chemical>> = vec![Vec::new(); names.len()];
+        for (id, reaction) in reactions.iter().enumerate() {
+            for input in reaction.iter().flat_map(|r| r.inputs.iter()) {
+                consumers[input.chemical].push(id);
+            }
+        }
+        let mut waiting: Vec<usize> = consumers.iter().map(|c| c.len()).collect();
+        let mut ready: VecDeque<Chemical> = (0 .. names.len()).filter(|c| waiting[*c] == 0).collect();
+        let mut order: Vec<Chemical> = Vec::new();
+        while let Some(id) = ready.pop_front() {
+            order.push(id);
+            for input in reactions[id].iter().flat_map(|r| r.inputs.iter()) {
+                waiting[input.chemical] -= 1;
+                if waiting[input.chemical] == 0 {
+                    ready.push_back(input.chemical);
+                }
+            }
+        }
+        if order.len() == names.len() {
+            return Ok(order);
+        }
+
+        // Every chemical left over still has a consumer that is left over,
+        // so following consumers from any of them must come round in a loop
+        let mut path: Vec<Chemical> = vec![(0 .. names.len()).find(|c| waiting[*c] > 0).unwrap()];
+        loop {
+            let last = *path.last().unwrap();
+            let next = *consumers[last].iter().find(|c| waiting[**c] > 0).unwrap();
+            if let Some(start) = path.iter().position(|c| *c == next) {
+                let mut cycle: Vec<&str> = path[start ..].iter().rev().map(|c| names[*c].as_str()).collect();
+                cycle.push(cycle[0]);
+                return Err(format!("the reactions go round in a cycle: {}", cycle.join(" <= ")));
+            }
+            path.push(next);
+        }
+    }
+
+    // Everything that goes into making `fuel` FUEL, in topological order,
+    // so ORE comes last
+    pub fn batches(self: &Self, fuel: Quantity) -> Result<Vec<Batch>, String> {
+        let too_much = |c: Chemical| format!("making {} {} needs too much {} to count", fuel, FUEL, self.names[c]);
+        let mut needed: Vec<Quantity> = vec![0; self.names.len()];
+        needed[self.fuel] = fuel;
+        let mut batches: Vec<Batch> = Vec::new();
+        for &id in &self.order {
+            let made = match &self.reactions[id] {
+                None => needed[id],
+                Some(reaction) => {
+                    let repeats = needed[id].div_ceil(reaction.output);
+                    for input in &reaction.inputs {
+                        needed[input.chemical] = input.quantity.checked_mul(repeats)
+                            .and_then(|q| q.checked_add(needed[input.chemical]))
+                            .ok_or_else(|| too_much(input.chemical))?;
+                    }
+                    repeats.checked_mul(reaction.output).ok_or_else(|| too_much(id))?
+                },
+            };
+            batches.push(Batch { chemical: id, needed: needed[id], made: made });
+        }
+        return Ok(batches);
+    }
+
+    // The exact amount of ORE needed to make `fuel` FUEL
+    pub fn ore_for_fuel(self: &Self, fuel: Quantity) -> Result<Quantity, String> {
+        let batches = self.batches(fuel)?;
+        return Ok(batches.iter().find(|b| b.chemical == self.ore).unwrap().needed);
+    }
+
+    fn affordable(self: &Self, fuel: Quantity, ore: Quantity) -> bool {
+        return matches!(self.ore_for_fuel(fuel), Ok(needed) if needed <= ore);
+    }
+
+    // The most FUEL that can be made from `ore` ORE. The ORE needed never
+    // goes down as the FUEL goes up, so a bound can be found by doubling
+    // and then narrowed down by bisection.
+    pub fn max_fuel(self: &Self, ore: Quantity) -> Quantity {
+        // Leftovers from one FUEL can only help with the next, so this much
+        // is certainly affordable
+        let mut low: Quantity = ore / self.ore_for_fuel(1).unwrap_or(Quantity::MAX);
+        let mut high: Quantity = low.saturating_add(1);
+        while self.affordable(high, ore) {
+            low = high;
+            if high == Quantity::MAX {
+                return low;
+            }
+            high = high.saturating_mul(2);
+        }
+        while high - low > 1 {
+            let middle = low + (high - low) / 2;
+            if self.affordable(middle, ore) {
+                low = middle;
+            } else {
+                high = middle;
+            }
+        }
+        return low;
+    }
+
+    // A table of how much of each chemical is made, needed and wasted
+    // in making `fuel` FUEL
+    pub fn bill_of_materials(self: &Self, fuel: Quantity) -> Result<String, String> {
+        let mut out = format!("{:>12} {:>12} {:>12}  chemical\n", "made", "needed", "wasted");
+        for batch in self.batches(fuel)? {
+            if batch.made > 0 {
+                out.push_str(&format!("{:>12} {:>12} {:>12}  {}\n", batch.made, batch.needed,
+                                      batch.wasted(), self.names[batch.chemical]));
+            }
+        }
+        return Ok(out);
+    }
+}
+
+#[test]
+fn test_order() {
+    let graph = Graph::parse("2 ORE => 3 B\n1 ORE, 2 B => 1 A\n3 A, 1 B => 1 FUEL\n").unwrap();
+    let names: Vec<&str> = graph.order.iter().map(|c| graph.names[*c].as_str()).collect();
+    assert_eq!(names, vec!["FUEL", "A", "B", "ORE"]);
+    assert_eq!(graph.reactions[graph.fuel].as_ref().unwrap().inputs.len(), 2);
+    assert!(graph.reactions[graph.ore].is_none());
+
+    // 3 A needs 6 B, plus 1 more for the FUEL; that is 3 batches, with 2 spare
+    let batches = graph.batches(1).unwrap();
+    let b = batches.iter().find(|b| graph.names[b.chemical] == "B").unwrap();
+    assert_eq!((b.needed, b.made, b.wasted()), (7, 9, 2));
+    assert_eq!(graph.ore_for_fuel(1), Ok(3 + 6));
+    assert_eq!(graph.ore_for_fuel(0), Ok(0));
+    assert_eq!(graph.max_fuel(8), 0);
+    assert_eq!(graph.max_fuel(9), 1);
+}
+
+#[test]
+fn test_errors() {
+    assert_eq!(Graph::parse("1 ORE => 1 A\n1 B => 1 FUEL").unwrap_err(), "no reaction makes B");
+    assert_eq!(Graph::parse("1 ORE => 1 A").unwrap_err(), "no reaction makes FUEL");
+    assert_eq!(Graph::parse("1 ORE => 1 A\n1 ORE => 2 A").unwrap_err(),
+               "A is made by more than one reaction");
+    assert_eq!(Graph::parse("1 A => 1 ORE").unwrap_err(), "'1 A => 1 ORE' makes ORE, which can only be mined");
+    assert_eq!(Graph::parse("1 ORE => 1 A 1 FUEL").unwrap_err(),
+               "'1 A 1 FUEL' should be a positive quantity and a chemical");
+    assert_eq!(Graph::parse("0 ORE => 1 FUEL").unwrap_err(),
+               "'0 ORE' should be a positive quantity and a chemical");
+    assert_eq!(Graph::parse("1 ORE, 1 FUEL").unwrap_err(), "'1 ORE, 1 FUEL' should be like 7 A, 1 B => 1 C");
+    assert_eq!(Graph::parse("1 C => 1 FUEL\n1 B => 1 A\n1 ORE, 1 A => 1 B\n1 B => 1 C").unwrap_err(),
+               "the reactions go round in a cycle: B <= A <= B");
+    assert_eq!(Graph::parse("1 FUEL => 1 FUEL").unwrap_err(),
+               "the reactions go round in a cycle: FUEL <= FUEL");
+
+    let graph = Graph::parse("1000000000000 ORE => 1 FUEL").unwrap();
+    assert!(graph.ore_for_fuel(u64::MAX).is_err());
+    assert_eq!(graph.max_fuel(u64::MAX), 18446744);
+    // as much FUEL as ORE, which is as much as can be counted
+    let graph = Graph::parse("1 ORE => 1 FUEL").unwrap();
+    assert_eq!(graph.max_fuel(u64::MAX), u64::MAX);
+    let graph = Graph::parse("1 ORE => 2 FUEL").unwrap();
+    assert_eq!(graph.max_fuel(u64::MAX), u64::MAX);
+}
+
+#[test]
+fn test_bill_of_materials() {
+    let graph = Graph::parse("2 ORE => 3 B\n1 ORE, 2 B => 1 A\n3 A, 1 B => 1 FUEL\n").unwrap();
+    assert_eq!(graph.bill_of_materials(1).unwrap(),
+               "        made       needed       wasted  chemical\n\
+                \x20          1            1            0  FUEL\n\
+                \x20          3            3            0  A\n\
+                \x20          9            7            2  B\n\
+                \x20          9            9            0  ORE\n");
+}
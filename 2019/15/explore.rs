@@ -0,0 +1,144 @@
+// Mapping an unknown grid by trying moves. The only way to learn what is
+// at a place is to try to move there, which an Oracle does. Exploring
+// clones the oracle at each place reached and tries the four moves from
+// there, so nothing ever has to walk back along a route.
+
+use std::collections::HashMap;
+use intcode::Word;
+
+#[derive(Eq, PartialEq, Hash, Copy, Clone, Debug)]
+pub struct Location {
+    pub x: Word,
+    pub y: Word,
+}
+
+#[derive(Eq, PartialEq, Hash, Copy, Clone, Debug)]
+pub enum Here {
+    Hallway,
+    Wall,
+    Oxygen,
+    Unexplored,
+    Start,
+}
+
+pub type Maze = HashMap<Location, Here>;
+
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub enum Direction {
+    North, South, East, West,
+}
+
+pub const DIRECTIONS: [Direction; 4] =
+    [Direction::North, Direction::South, Direction::West, Direction::East];
+
+impl Location {
+    pub fn step(self: &Self, dir: Direction) -> Location {
+        let (dx, dy) = match dir {
+            Direction::North => (0, -1),
+            Direction::South => (0, 1),
+            Direction::West => (-1, 0),
+            Direction::East => (1, 0),
+        };
+        return Location {
+            x: self.x + dx,
+            y: self.y + dy,
+        };
+    }
+}
+
+pub trait Oracle: Clone {
+    // Try to move one step: returns Wall if the move was blocked, or what
+    // is at the new location if the move was made
+    fn try_move(self: &mut Self, dir: Direction) -> Result<Here, String>;
+}
+
+// Map everything reachable from `start`, where the oracle is
+pub fn explore(oracle: impl Oracle, start: Location) -> Result<Maze, String> {
+    let mut maze: Maze = Maze::new();
+    let mut stack = vec![(start, oracle)];
+    maze.insert(start, Here::Start);
+
+    while let Some((from_loc, oracle)) = stack.pop() {
+        for dir in DIRECTIONS {
+            let to_loc = from_loc.step(dir);
+            if *maze.get(&to_loc).unwrap_or(&Here::Unexplored) != Here::Unexplored {
+                // Already explored
+                continue;
+            }
+            let mut fork = oracle.clone();
+            let here = fork.try_move(dir)?;
+            match here {
+                Here::Wall => {},
+                Here::Hallway | Here::Oxygen => {
+                    stack.push((to_loc, fork));
+                },
+                Here::Unexplored | Here::Start => {
+                    return Err("the oracle can't report Unexplored or Start".to_string());
+                },
+            }
+            maze.insert(to_loc, here);
+        }
+    }
+    return Ok(maze);
+}
+
+// A maze drawn with '#' for walls, 'o' for oxygen and 'S' for the start
+#[cfg(test)]
+#[derive(Clone)]
+struct TestMaze<'a> {
+    rows: &'a [&'a str],
+    at: Location,
+}
+
+#[cfg(test)]
+impl Oracle for TestMaze<'_> {
+    fn try_move(self: &mut Self, dir: Direction) -> Result<Here, String> {
+        let to_loc = self.at.step(dir);
+        let c = self.rows.get(to_loc.y as usize)
+                    .and_then(|row| row.chars().nth(to_loc.x as usize)).ok_or("outside")?;
+        if c == '#' {
+            return Ok(Here::Wall);
+        }
+        self.at = to_loc;
+        return Ok(if c == 'o' { Here::Oxygen } else { Here::Hallway });
+    }
+}
+
+#[test]
+fn test_explore() {
+    let rows = [
+        "#########",
+        "#S..#...#",
+        "##.##.#.#",
+        "#.....#o#",
+        "###.#####",
+        "#...#...#",
+        "#########",
+    ];
+    let start = Location { x: 1, y: 1 };
+    let maze = explore(TestMaze { rows: &rows, at: start }, start).unwrap();
+
+    // every reachable place is found, with the walls next to them;
+    // the room on the right can't be reached
+    let mut found: Vec<String> = Vec::new();
+    for y in 0 .. rows.len() as Word {
+        found.push((0 .. rows[0].len() as Word).map(|x| {
+            match maze.get(&Location { x: x, y: y }) {
+                Some(Here::Wall) => '#',
+                Some(Here::Hallway) => '.',
+                Some(Here::Oxygen) => 'o',
+                Some(Here::Start) => 'S',
+                Some(Here::Unexplored) | None => '?',
+            }
+        }).collect());
+    }
+    assert_eq!(found, [
+        "?###?###?",
+        "#S..#...#",
+        "?#.##.#.#",
+        "#.....#o#",
+        "?##.##?#?",
+        "#...#????",
+        "?###?????",
+    ]);
+}
@@ -1,186 +1,27 @@
 
-use std::fs::File;
-use std::io::{self, BufRead};
 use std::collections::HashMap;
+use std::env;
 use std::collections::BinaryHeap;
 use std::cmp::Ordering;
 
+extern crate intcode;
+use intcode::{Word, MachineState, load_from_input};
+use intcode::adapter::request;
 
-type Word = i32;
-type Memory = HashMap<Word, Word>;
-type InputOutput = Vec<Word>;
+mod explore;
+use explore::{Location, Here, Maze, Direction, Oracle, explore};
+use raster::{Position, Palette, WHITE, render};
 
-struct MachineState {
-    memory: Memory,
-    input: InputOutput,
-    output: InputOutput,
-    pc: Word,
-    relative_base: Word,
-}
-
-fn load_from_input(filename: &str) -> MachineState {
-    let file = File::open(filename).unwrap();
-    let line = io::BufReader::new(file).lines().next().expect("line").unwrap();
-    return load(line.as_str());
-}
-
-fn load(line: &str) -> MachineState {
-    let mut memory: Memory = HashMap::new();
-    let mut index: Word = 0;
-    for code in line.split(",") {
-        let trimmed = code.trim();
-        let parsed: Word = trimmed.parse().expect("number");
-        memory.insert(index, parsed);
-        index += 1;
-    }
-    return MachineState {
-        memory: memory,
-        input: Vec::new(),
-        output: Vec::new(),
-        pc: 0,
-        relative_base: 0,
-    };
-}
-
-fn get_parameter_mode(opcode: Word, index: Word) -> Word {
-    assert!(index >= 1);
-    let mut copy = opcode / 100;
-    for _ in 1 .. index {
-        copy = copy / 10;
-    }
-    return copy % 10;
-}
-
-fn load_memory(ms: &mut MachineState, address: Word) -> Word {
-    return *ms.memory.get(&address).unwrap_or(&0);
-}
-
-fn store_memory(ms: &mut MachineState, address: Word, value: Word) {
-    ms.memory.insert(address, value);
-}
-
-fn load_parameter(ms: &mut MachineState, index: Word) -> Word {
-    assert!(index >= 1);
-    let opcode = load_memory(ms, ms.pc);
-    let parameter = load_memory(ms, ms.pc + index);
-
-    return match get_parameter_mode(opcode, index) {
-        0 => load_memory(ms, parameter),
-        1 => parameter,
-        2 => load_memory(ms, parameter + ms.relative_base),
-        _ => panic!(),
-    }
-}
-
-fn store_parameter(ms: &mut MachineState, index: Word, value: Word) {
-    assert!(index >= 1);
-    let opcode = load_memory(ms, ms.pc);
-    let parameter = load_memory(ms, ms.pc + index);
-
-    match get_parameter_mode(opcode, index) {
-        0 => store_memory(ms, parameter, value),
-        1 => panic!(),
-        2 => store_memory(ms, parameter + ms.relative_base, value),
-        _ => panic!(),
+impl Position for Location {
+    fn position(self: &Self) -> (i64, i64) {
+        return (self.x, self.y);
     }
 }
 
-fn run(ms: &mut MachineState) -> Option<Word> {
-    loop {
-        let opcode = load_memory(ms, ms.pc);
-        let a = load_parameter(ms, 1);
-        let b = load_parameter(ms, 2);
-        match opcode % 100 {
-            1 => {
-                store_parameter(ms, 3, a + b);
-                ms.pc += 4;
-            },
-            2 => {
-                store_parameter(ms, 3, a * b);
-                ms.pc += 4;
-            },
-            3 => {
-                if ms.input.is_empty() {
-                    return None;
-                }
-                let v = ms.input.pop().unwrap_or(0);
-                store_parameter(ms, 1, v);
-                ms.pc += 2;
-            },
-            4 => {
-                ms.pc += 2;
-                ms.output.push(a);
-            },
-            5 => {
-                ms.pc += 3;
-                if a != 0 {
-                    ms.pc = b;
-                }
-            },
-            6 => {
-                ms.pc += 3;
-                if a == 0 {
-                    ms.pc = b;
-                }
-            },
-            7 => {
-                if a < b {
-                    store_parameter(ms, 3, 1);
-                } else {
-                    store_parameter(ms, 3, 0);
-                }
-                ms.pc += 4;
-            },
-            8 => {
-                if a == b {
-                    store_parameter(ms, 3, 1);
-                } else {
-                    store_parameter(ms, 3, 0);
-                }
-                ms.pc += 4;
-            },
-            9 => {
-                ms.relative_base += a;
-                ms.pc += 2;
-            },
-            99 => {
-                return Some(load_memory(ms, 0));
-            },
-            _ => {
-                println!("illegal instruction {} at {}", opcode, ms.pc);
-                panic!();
-            }
-        }
-    }
-}
-
-#[derive(Eq, PartialEq, Hash, Copy, Clone)]
-struct Location {
-    x: Word,
-    y: Word,
-}
-
-#[derive(Eq, PartialEq, Copy, Clone)]
-enum Here {
-    Hallway,
-    Wall,
-    Oxygen,
-    Unexplored,
-    Start,
-}
-
-type Maze = HashMap<Location, Here>;
-
-#[derive(Eq, PartialEq, Copy, Clone)]
-enum Direction {
-    North, South, East, West, Invalid,
-}
-
 #[derive(Copy, Clone)]
 struct Waypoint {
     from_loc: Location,
     to_loc: Location,
-    dir: Direction,
     distance: Word,
 }
 
@@ -246,7 +87,6 @@ fn shortest_path(maze: &Maze, start: &Location, finish: Option<&Location>) -> Ha
         from_loc: *start,
         to_loc: *start,
         distance: 0,
-        dir: Direction::Invalid,
     });
     let mut done: HashMap<Location, Waypoint> = HashMap::new();
 
@@ -259,7 +99,7 @@ fn shortest_path(maze: &Maze, start: &Location, finish: Option<&Location>) -> Ha
         }
 
         // Shortest path found to this location
-        done.insert(way.to_loc, way.clone());
+        done.insert(way.to_loc, way);
 
         if finish.is_some() && (way.to_loc == *finish.unwrap()) {
             // Found the shortest path
@@ -267,7 +107,7 @@ fn shortest_path(maze: &Maze, start: &Location, finish: Option<&Location>) -> Ha
         }
 
         // Look for other paths
-        let mut add_path = |dx: Word, dy: Word, dir: Direction| {
+        let mut add_path = |dx: Word, dy: Word| {
             let to_loc = Location {
                 x: way.to_loc.x + dx,
                 y: way.to_loc.y + dy,
@@ -284,14 +124,13 @@ fn shortest_path(maze: &Maze, start: &Location, finish: Option<&Location>) -> Ha
             todo.push(Waypoint {
                 from_loc: way.to_loc,
                 to_loc: to_loc,
-                dir: dir,
                 distance: way.distance + 1,
             });
         };
-        add_path(-1, 0, Direction::West);
-        add_path( 1, 0, Direction::East);
-        add_path(0, -1, Direction::North);
-        add_path(0,  1, Direction::South);
+        add_path(-1, 0);
+        add_path( 1, 0);
+        add_path(0, -1);
+        add_path(0,  1);
     }
     return done;
 }
@@ -305,7 +144,7 @@ fn make_route(maze: &Maze, start: &Location, finish: &Location) -> Vec<Waypoint>
     let mut loc = *finish;
 
     while loc != *start {
-        // Push the direction for way.from_loc to way.to_loc
+        // Push the step from way.from_loc to way.to_loc
         let way: Waypoint = *done.get(&loc).unwrap();
         loc = way.from_loc;
         route.push(way);
@@ -314,119 +153,94 @@ fn make_route(maze: &Maze, start: &Location, finish: &Location) -> Vec<Waypoint>
     return route;
 }
 
-fn main() {
-    let mut ms: MachineState = load_from_input("input");
-    let mut maze: Maze = Maze::new();
-    let mut stack: Vec<Location> = Vec::new();
-    let mut droid = Location { x: 0, y: 0 };
-    let mut oxygen: Option<Location> = None;
+// The repair droid, driven by the Intcode program
+#[derive(Clone)]
+struct Droid {
+    ms: MachineState,
+}
 
-    maze.insert(Location { x: 0, y: 0 }, Here::Start);
-    stack.push(Location { x: 1, y: 0 });
-    stack.push(Location { x: -1, y: 0 });
-    stack.push(Location { x: 0, y: 1 });
-    stack.push(Location { x: 0, y: -1 });
+impl Oracle for Droid {
+    fn try_move(self: &mut Self, dir: Direction) -> Result<Here, String> {
+        let command = match dir {
+            Direction::North => 1,
+            Direction::South => 2,
+            Direction::West => 3,
+            Direction::East => 4,
+        };
+        let status = request(&mut self.ms, command).map_err(|f| f.to_string())?;
+        return match status {
+            Some(0) => Ok(Here::Wall),
+            Some(1) => Ok(Here::Hallway),
+            Some(2) => Ok(Here::Oxygen),
+            Some(other) => Err(format!("unexpected status {}", other)),
+            None => Err("the droid stopped responding".to_string()),
+        };
+    }
+}
 
-    while !stack.is_empty() {
-        let next_unexplored: Location = stack.pop().unwrap();
+fn save_maze(maze: &Maze, filename: &str) -> Result<(), String> {
+    let palette = Palette::new([(Here::Wall, [64, 64, 64]), (Here::Oxygen, [0, 96, 255]),
+                                (Here::Start, [0, 192, 0]), (Here::Hallway, WHITE)],
+                               [160, 160, 160]);
+    return render(maze, |v| palette.colour(v), 4).save(filename);
+}
 
-        if *maze.get(&next_unexplored).unwrap_or(&Here::Unexplored) != Here::Unexplored {
-            // Already explored
-            continue;
-        }
-        assert!(next_unexplored != droid);
-
-        // Find a route to the unexplored place and go there
-        let route = make_route(&maze, &droid, &next_unexplored);
-        assert!(!route.is_empty());
-        let mut result: Word = -1;
-        for i in 0 .. route.len() {
-            assert!(ms.input.is_empty());
-            let mv: &Waypoint = route.get(i).unwrap();
-
-            ms.input.push(match mv.dir {
-                Direction::North => 1,
-                Direction::South => 2,
-                Direction::West => 3,
-                Direction::East => 4,
-                _ => { panic!(); },
-            });
-            let rc = run(&mut ms);
-            assert!(rc.is_none());
-            assert!(ms.output.len() == 1);
-            result = ms.output.pop().unwrap();
-            match result {
-                0 => {
-                    // Hit a wall - this must be the final move
-                    assert!(i == (route.len() - 1));
-                },
-                1 | 2 => {
-                    // Some space here
-                },
-                _ => {
-                    panic!();
-                },
-            }
-        }
-        assert!(route.last().unwrap().to_loc == next_unexplored);
-        match result {
-            0 => {
-                // Unexplored space is a wall - droid didn't move
-                maze.insert(next_unexplored, Here::Wall);
-                droid = route.last().unwrap().from_loc;
-            },
-            1 => {
-                // Unexplored space is hallway
-                maze.insert(next_unexplored, Here::Hallway);
-                droid = next_unexplored;
-            },
-            2 => {
-                // Unexplored space is an oxygen generator
-                maze.insert(next_unexplored, Here::Oxygen);
-                droid = next_unexplored;
-                oxygen = Some(next_unexplored);
-            },
-            _ => {
-                panic!();
-            },
-        }
-        match result {
-            0 => {},
-            1 | 2 => {
-                // Explore further
-                let mut explore = |dx: Word, dy: Word| {
-                    let to_loc = Location {
-                        x: droid.x + dx,
-                        y: droid.y + dy,
-                    };
-                    if *maze.get(&to_loc).unwrap_or(&Here::Unexplored) == Here::Unexplored {
-                        stack.push(to_loc);
-                    }
-                };
-                explore( 1, 0);
-                explore(-1, 0);
-                explore(0,  1);
-                explore(0, -1);
-            },
-            _ => {
-                panic!();
-            },
-        }
-    }
-    // Maze is now fully explored!
-    print_maze(&maze);
+// Explore the whole maze, and find the oxygen system
+fn load_maze(filename: &str) -> Result<(Maze, Location), String> {
+    let droid = Droid { ms: load_from_input(filename) };
+    let maze = explore(droid, Location { x: 0, y: 0 })?;
+    let oxygen: Option<Location> = maze.iter()
+        .find(|(_, here)| **here == Here::Oxygen).map(|(loc, _)| *loc);
+    let oxygen = oxygen.ok_or("there is no oxygen system")?;
+    return Ok((maze, oxygen));
+}
 
-    // Here is the result for part 1
-    assert!(oxygen.is_some());
-    let oxy_route = make_route(&maze, &Location { x: 0, y: 0 }, &oxygen.unwrap());
-    println!("{}", oxy_route.len());
+// The length of the route from the start to the oxygen system
+fn part1(maze: &Maze, oxygen: &Location) -> usize {
+    return make_route(maze, &Location { x: 0, y: 0 }, oxygen).len();
+}
 
-    // Result for part 2 is the distance of the furthest point from the oxygen generator
-    let done = shortest_path(&maze, &oxygen.unwrap(), None);
+// The distance of the furthest point from the oxygen system
+fn part2(maze: &Maze, oxygen: &Location) -> Word {
+    let done = shortest_path(maze, oxygen, None);
     let mut furthest: Word = 0;
     for way in done.values() {
         furthest = Word::max(furthest, way.distance);
     }
-    println!("{}", furthest);
+    return furthest;
+}
+
+pub struct Solution;
+
+impl aoc::Puzzle for Solution {
+    fn part1(self: &Self, filename: &str) -> Result<String, String> {
+        let (maze, oxygen) = load_maze(filename)?;
+        return Ok(part1(&maze, &oxygen).to_string());
+    }
+
+    fn part2(self: &Self, filename: &str) -> Result<String, String> {
+        let (maze, oxygen) = load_maze(filename)?;
+        return Ok(part2(&maze, &oxygen).to_string());
+    }
+}
+
+fn main() -> Result<(), String> {
+    let args: Vec<String> = env::args().collect();
+    let image = match args.len() {
+        1 => None,
+        3 if args[1] == "--image" => Some(&args[2]),
+        _ => { return Err("Optionally, give --image <file> to save the maze \
+                           as a .pbm, .pgm or .ppm file".to_string()); },
+    };
+    let (maze, oxygen) = load_maze("input")?;
+
+    // Maze is now fully explored!
+    print_maze(&maze);
+    if let Some(filename) = image {
+        save_maze(&maze, filename)?;
+    }
+    println!("{}", part1(&maze, &oxygen));
+    println!("{}", part2(&maze, &oxygen));
+    return Ok(());
 }
 
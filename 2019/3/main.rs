@@ -1,74 +1,306 @@
+use std::env;
+use std::fs;
+use std::collections::BTreeSet;
 
-use std::fs::File;
-use std::io::{self, BufRead};
-use std::collections::HashMap;
 
-
-#[derive(Hash, Eq, PartialEq, Copy, Clone)]
+#[derive(Hash, Eq, PartialEq, Ord, PartialOrd, Copy, Clone, Debug)]
 struct Point {
     x: i32,
     y: i32,
 }
 
+const ORIGIN: Point = Point { x: 0, y: 0 };
+
 fn distance(point: &Point) -> i32 {
     return point.x.abs() + point.y.abs();
 }
 
-fn trace_line(steps: &str) -> HashMap<Point, u32> {
+// A straight part of a wire, from `start` to `end` inclusive;
+// `steps` is the length of the wire before `start`
+#[derive(Debug)]
+struct Segment {
+    start: Point,
+    end: Point,
+    steps: u32,
+}
 
-    let mut location = Point { x: 0, y: 0 };
-    let mut line: HashMap<Point, u32> = HashMap::new();
-    let mut total_count: u32 = 0;
+impl Segment {
+    fn contains(self: &Self, point: &Point) -> bool {
+        return i32::min(self.start.x, self.end.x) <= point.x
+            && point.x <= i32::max(self.start.x, self.end.x)
+            && i32::min(self.start.y, self.end.y) <= point.y
+            && point.y <= i32::max(self.start.y, self.end.y);
+    }
 
-    for step in steps.split(",") {
-        let direction: u8 = step[0..1].as_bytes()[0];
-        let mut count: i32 = step[1..].parse().expect("count");
-
-        while count > 0 {
-            match direction {
-                b'R' => location.x += 1,
-                b'L' => location.x -= 1,
-                b'U' => location.y -= 1,
-                b'D' => location.y += 1,
-                _ => panic!(),
-            }
-            count -= 1;
-            total_count += 1;
-            if !line.contains_key(&location) {
-                line.insert(location, total_count);
+    // Steps along the wire to `point`, if it is on this segment
+    fn steps_to(self: &Self, point: &Point) -> Option<u32> {
+        if !self.contains(point) {
+            return None;
+        }
+        let along = (point.x - self.start.x).abs() + (point.y - self.start.y).abs();
+        return Some(self.steps + along as u32);
+    }
+
+    // Points on both segments: a horizontal and a vertical segment meet
+    // at one point at most, but two segments along the same line can
+    // share several
+    fn meet(self: &Self, other: &Segment) -> Vec<Point> {
+        let x1 = i32::max(i32::min(self.start.x, self.end.x), i32::min(other.start.x, other.end.x));
+        let x2 = i32::min(i32::max(self.start.x, self.end.x), i32::max(other.start.x, other.end.x));
+        let y1 = i32::max(i32::min(self.start.y, self.end.y), i32::min(other.start.y, other.end.y));
+        let y2 = i32::min(i32::max(self.start.y, self.end.y), i32::max(other.start.y, other.end.y));
+        let mut points: Vec<Point> = Vec::new();
+        for x in x1 ..= x2 {
+            for y in y1 ..= y2 {
+                points.push(Point { x: x, y: y });
             }
         }
+        return points;
+    }
+}
+
+struct Wire {
+    segments: Vec<Segment>,
+}
+
+impl Wire {
+    // Steps along the wire to the first time it reaches `point`
+    fn steps_to(self: &Self, point: &Point) -> Option<u32> {
+        return self.segments.iter().find_map(|segment| segment.steps_to(point));
+    }
+}
+
+fn parse_wire(steps: &str) -> Result<Wire, String> {
+    let mut location = ORIGIN;
+    let mut total_count: u32 = 0;
+    let mut segments: Vec<Segment> = Vec::new();
+
+    for step in steps.trim().split(",") {
+        let mut chars = step.chars();
+        let (dx, dy) = match chars.next() {
+            Some('R') => (1, 0),
+            Some('L') => (-1, 0),
+            Some('U') => (0, -1),
+            Some('D') => (0, 1),
+            _ => { return Err(format!("'{}' has no direction", step)); },
+        };
+        let count: u32 = chars.as_str().parse().map_err(|_| format!("'{}' has no count", step))?;
+        let along = |d: i32, from: i32| i32::try_from(count).ok()?.checked_mul(d)?.checked_add(from);
+        let end = match (along(dx, location.x), along(dy, location.y)) {
+            (Some(x), Some(y)) => Point { x: x, y: y },
+            _ => { return Err(format!("'{}' goes too far", step)); },
+        };
+        segments.push(Segment { start: location, end: end, steps: total_count });
+        location = end;
+        total_count = total_count.checked_add(count)
+                                 .ok_or(format!("the wire is too long at '{}'", step))?;
+    }
+    return Ok(Wire { segments: segments });
+}
+
+// A point other than the origin where two or more wires meet
+#[derive(Debug, Eq, PartialEq)]
+struct Crossing {
+    point: Point,
+    // steps along each wire to its first visit, or None for wires that
+    // don't pass through this point
+    steps: Vec<Option<u32>>,
+}
+
+impl Crossing {
+    fn delay(self: &Self) -> u32 {
+        return self.steps.iter().flatten().sum();
     }
-    return line;
-}
-
-fn main() {
-    // read input
-    let file = File::open("input").unwrap();
-    let mut lines = io::BufReader::new(file).lines();
-    let line1 = lines.next().expect("line 1").unwrap();
-    let line2 = lines.next().expect("line 2").unwrap();
-    let line1_map = trace_line(line1.trim());
-    let line2_map = trace_line(line2.trim());
-
-    // part 1: find closest point (Manhattan distance from 0,0)
-    let mut best_distance = i32::MAX;
-    for point in line1_map.keys() {
-        if line2_map.contains_key(&point) {
-            let d = distance(&point);
-            best_distance = i32::min(d, best_distance);
+}
+
+// Every crossing of any two of the wires, ordered by x, then y
+fn crossings(wires: &[Wire]) -> Vec<Crossing> {
+    let mut points: BTreeSet<Point> = BTreeSet::new();
+    for (i, wire1) in wires.iter().enumerate() {
+        for wire2 in &wires[i + 1 ..] {
+            for segment1 in &wire1.segments {
+                for segment2 in &wire2.segments {
+                    points.extend(segment1.meet(segment2));
+                }
+            }
         }
     }
-    println!("{}", best_distance);
-
-    // part 2: find closest point (propagation delay)
-    let mut best_delay = u32::MAX;
-    for point in line1_map.keys() {
-        if let Some (d2) = line2_map.get(&point) {
-            let d1 = line1_map.get(&point).unwrap();
-            best_delay = u32::min(d1 + d2, best_delay);
+    points.remove(&ORIGIN);
+    return points.into_iter().map(|point| Crossing {
+        point: point,
+        steps: wires.iter().map(|wire| wire.steps_to(&point)).collect(),
+    }).collect();
+}
+
+const COLOURS: [&str; 6] = ["#d62728", "#1f77b4", "#2ca02c", "#9467bd", "#ff7f0e", "#8c564b"];
+
+// Draw the wires in different colours, with a circle around each crossing
+// and a dot at the origin. Hovering over a crossing shows its steps.
+fn to_svg(wires: &[Wire], crossings: &[Crossing]) -> String {
+    let mut x1 = 0;
+    let mut y1 = 0;
+    let mut x2 = 0;
+    let mut y2 = 0;
+    for segment in wires.iter().flat_map(|wire| wire.segments.iter()) {
+        x1 = i32::min(x1, segment.end.x);
+        y1 = i32::min(y1, segment.end.y);
+        x2 = i32::max(x2, segment.end.x);
+        y2 = i32::max(y2, segment.end.y);
+    }
+    let size = i32::max(1, i32::max(x2 - x1, y2 - y1));
+    let stroke = size / 500 + 1;
+    let margin = stroke * 5;
+
+    let mut out = format!("<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\">\n",
+                          x1 - margin, y1 - margin, x2 - x1 + 2 * margin, y2 - y1 + 2 * margin);
+    out.push_str(&format!("<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"white\"/>\n",
+                          x1 - margin, y1 - margin, x2 - x1 + 2 * margin, y2 - y1 + 2 * margin));
+    for (i, wire) in wires.iter().enumerate() {
+        let mut points: Vec<String> = vec![format!("{},{}", ORIGIN.x, ORIGIN.y)];
+        for segment in &wire.segments {
+            points.push(format!("{},{}", segment.end.x, segment.end.y));
         }
+        out.push_str(&format!("<polyline fill=\"none\" stroke=\"{}\" stroke-width=\"{}\" points=\"{}\"/>\n",
+                              COLOURS[i % COLOURS.len()], stroke, points.join(" ")));
+    }
+    for crossing in crossings {
+        let steps: Vec<String> = crossing.steps.iter().map(|s| match s {
+            Some(s) => s.to_string(),
+            None => "-".to_string(),
+        }).collect();
+        out.push_str(&format!("<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"none\" stroke=\"black\" \
+                               stroke-width=\"{}\"><title>{},{}: {}</title></circle>\n",
+                              crossing.point.x, crossing.point.y, stroke * 4, stroke,
+                              crossing.point.x, crossing.point.y, steps.join(" ")));
+    }
+    out.push_str(&format!("<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"black\"/>\n",
+                          ORIGIN.x, ORIGIN.y, stroke * 3));
+    out.push_str("</svg>\n");
+    return out;
+}
+
+fn read_wires(text: &str) -> Result<Vec<Wire>, String> {
+    return text.lines().filter(|line| !line.trim().is_empty()).map(parse_wire).collect();
+}
+
+// read input: one wire per line
+fn load(filename: &str) -> Result<Vec<Wire>, String> {
+    let text = fs::read_to_string(filename).map_err(|e| format!("{}: {}", filename, e))?;
+    return read_wires(&text);
+}
+
+// part 1: find closest crossing (Manhattan distance from 0,0)
+fn part1(crossings: &[Crossing]) -> Option<i32> {
+    return crossings.iter().map(|crossing| distance(&crossing.point)).min();
+}
+
+// part 2: find closest crossing (propagation delay)
+fn part2(crossings: &[Crossing]) -> Option<u32> {
+    return crossings.iter().map(|crossing| crossing.delay()).min();
+}
+
+#[test]
+fn test_examples() {
+    let examples = [
+        ("R8,U5,L5,D3\nU7,R6,D4,L4", 6, 30),
+        ("R75,D30,R83,U83,L12,D49,R71,U7,L72\n\
+          U62,R66,U55,R34,D71,R55,D58,R83", 159, 610),
+        ("R98,U47,R26,D63,R33,U87,L62,D20,R33,U53,R51\n\
+          U98,R91,D20,R16,D67,R40,U7,R15,U6,R7", 135, 410),
+    ];
+    for (text, closest, delay) in examples {
+        let c = crossings(&read_wires(text).unwrap());
+        assert_eq!(part1(&c), Some(closest));
+        assert_eq!(part2(&c), Some(delay));
+    }
+
+    let c = crossings(&read_wires("R8,U5,L5,D3\nU7,R6,D4,L4").unwrap());
+    assert_eq!(c, vec![
+        Crossing { point: Point { x: 3, y: -3 }, steps: vec![Some(20), Some(20)] },
+        Crossing { point: Point { x: 6, y: -5 }, steps: vec![Some(15), Some(15)] },
+    ]);
+}
+
+#[test]
+fn test_crossings() {
+    // the first wire goes back over itself, and the second runs along it
+    // for a while; the third only crosses the second
+    let wires = read_wires("R4,L2,U3\nU1,R1,D1,R5\nD1,R6,U3").unwrap();
+    assert_eq!(crossings(&wires), vec![
+        Crossing { point: Point { x: 1, y: 0 }, steps: vec![Some(1), Some(3), None] },
+        Crossing { point: Point { x: 2, y: 0 }, steps: vec![Some(2), Some(4), None] },
+        Crossing { point: Point { x: 3, y: 0 }, steps: vec![Some(3), Some(5), None] },
+        Crossing { point: Point { x: 4, y: 0 }, steps: vec![Some(4), Some(6), None] },
+        Crossing { point: Point { x: 6, y: 0 }, steps: vec![None, Some(8), Some(8)] },
+    ]);
+    assert!(crossings(&read_wires("R4\nL4").unwrap()).is_empty());
+    assert_eq!(part1(&[]), None);
+
+    assert_eq!(read_wires("R4,X2").err(), Some("'X2' has no direction".to_string()));
+    assert_eq!(read_wires("R4,U").err(), Some("'U' has no count".to_string()));
+    assert_eq!(read_wires("R4000000000,R4000000000").err(),
+               Some("'R4000000000' goes too far".to_string()));
+    assert_eq!(read_wires("R2000000000,R2000000000").err(),
+               Some("'R2000000000' goes too far".to_string()));
+    assert_eq!(read_wires("R2000000000,L2000000000,R2000000000").err(),
+               Some("the wire is too long at 'R2000000000'".to_string()));
+}
+
+#[test]
+fn test_svg() {
+    let wires = read_wires("R8,U5,L5,D3\nU7,R6,D4,L4").unwrap();
+    let svg = to_svg(&wires, &crossings(&wires));
+    assert_eq!(svg, "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"-5 -12 18 17\">\n\
+                     <rect x=\"-5\" y=\"-12\" width=\"18\" height=\"17\" fill=\"white\"/>\n\
+                     <polyline fill=\"none\" stroke=\"#d62728\" stroke-width=\"1\" \
+                     points=\"0,0 8,0 8,-5 3,-5 3,-2\"/>\n\
+                     <polyline fill=\"none\" stroke=\"#1f77b4\" stroke-width=\"1\" \
+                     points=\"0,0 0,-7 6,-7 6,-3 2,-3\"/>\n\
+                     <circle cx=\"3\" cy=\"-3\" r=\"4\" fill=\"none\" stroke=\"black\" \
+                     stroke-width=\"1\"><title>3,-3: 20 20</title></circle>\n\
+                     <circle cx=\"6\" cy=\"-5\" r=\"4\" fill=\"none\" stroke=\"black\" \
+                     stroke-width=\"1\"><title>6,-5: 15 15</title></circle>\n\
+                     <circle cx=\"0\" cy=\"0\" r=\"3\" fill=\"black\"/>\n\
+                     </svg>\n");
+}
+
+#[test]
+fn test_answers() {
+    let c = crossings(&load("input").unwrap());
+    assert_eq!(part1(&c), Some(1211));
+    assert_eq!(part2(&c), Some(101386));
+}
+
+pub struct Solution;
+
+impl aoc::Puzzle for Solution {
+    fn part1(self: &Self, filename: &str) -> Result<String, String> {
+        let c = crossings(&load(filename)?);
+        return Ok(part1(&c).ok_or("the wires don't cross")?.to_string());
+    }
+
+    fn part2(self: &Self, filename: &str) -> Result<String, String> {
+        let c = crossings(&load(filename)?);
+        return Ok(part2(&c).ok_or("the wires don't cross")?.to_string());
+    }
+}
+
+fn main() -> Result<(), String> {
+    let args: Vec<String> = env::args().collect();
+    let svg = match args.len() {
+        1 => None,
+        3 if args[1] == "--svg" => Some(&args[2]),
+        _ => { return Err("Optionally, give --svg <file> to save the wires \
+                           and their crossings as an SVG image".to_string()); },
+    };
+
+    let wires = load("input")?;
+    let c = crossings(&wires);
+    println!("{}", part1(&c).ok_or("the wires don't cross")?);
+    println!("{}", part2(&c).ok_or("the wires don't cross")?);
+
+    if let Some(filename) = svg {
+        fs::write(filename, to_svg(&wires, &c)).map_err(|e| format!("{}: {}", filename, e))?;
     }
-    println!("{}", best_delay);
+    return Ok(());
 }
-   
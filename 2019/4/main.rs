@@ -1,97 +1,77 @@
+extern crate password;
+use std::fs;
+use password::{Rule, Rules, NonDecreasing, Run};
 
-const START: &str = "145852";
-const END: &str = "616942";
-
-fn advance(password: &mut Vec<u8>, start: usize) {
-    for clear in start + 1 .. password.len() {
-        password[clear] = b'0';
-    }
-    let mut index = start;
-    loop {
-        password[index] += 1;
-        if password[index] <= b'9' {
-            break; // no carry
-        }
-        password[index] = b'0';
-        if index == 0 {
-            break; // overflow
-        }
-        index -= 1;
-    }
+// Going from left to right, the digits never decrease; they only ever increase
+// or stay the same (like 111123 or 135679). Two adjacent digits are the same
+// (like 22 in 122345); in part 2, the two adjacent matching digits are not part
+// of a larger group of matching digits.
+fn rules(part2: bool) -> Rules {
+    let pair: Box<dyn Rule> = match part2 {
+        false => Box::new(Run::at_least(2)),
+        true => Box::new(Run::exactly(2)),
+    };
+    return Rules::new(b'0' ..= b'9', vec![Box::new(NonDecreasing), pair]);
 }
 
+fn count_valid_in_range(start: &str, end: &str, part2: bool) -> Result<u128, String> {
+    return rules(part2).count(start, end);
+}
 
-
-// Two adjacent digits are the same (like 22 in 122345).
-fn first_invalid_by_rule_3(password: &Vec<u8>, part2: bool) -> usize {
-    for index in 1 .. password.len() {
-        if password[index - 1] == password[index - 0] {
-            // rule is met
-            let mut ok = true;
-            if part2 {
-                // OR IS IT? only if this is not part of a larger sequence
-                let before_ok = (index <= 1) || (password[index - 2] != password[index - 1]);
-                let after_ok = (index >= password.len() - 1)
-                        || (password[index + 0] != password[index + 1]);
-                ok = before_ok && after_ok;
-            }
-            if ok {
-                return password.len();
-            }
-        }
-    }
-    // at least the last character is wrong
-    return password.len() - 1;
+// The input is a range like 145852-616942
+fn load(filename: &str) -> Result<(String, String), String> {
+    let text = fs::read_to_string(filename).map_err(|e| format!("{}: {}", filename, e))?;
+    let (start, end) = text.trim().split_once('-')
+        .ok_or(format!("{}: the input should be a range like 145852-616942", filename))?;
+    return Ok((start.to_string(), end.to_string()));
 }
 
-// Going from left to right, the digits never decrease; they only ever increase
-// or stay the same (like 111123 or 135679).
-fn first_invalid_by_rule_4(password: &Vec<u8>) -> usize {
-    for index in 1 .. password.len() {
-        if password[index - 1] > password[index - 0] {
-            return index;
-        }
-    }
-    // rule is met
-    return password.len();
+fn part1(filename: &str) -> Result<u128, String> {
+    let (start, end) = load(filename)?;
+    return count_valid_in_range(&start, &end, false);
 }
 
-// search for the next password and count validity
-fn count_valid_in_range(start: &str, end: &str, part2: bool) -> usize {
-    let mut password: Vec<u8> = Vec::from(start.as_bytes());
-    let end_as_vec: Vec<u8> = Vec::from(end.as_bytes());
-    let final_index = password.len() - 1;
-    let mut count_valid: usize = 0;
+fn part2(filename: &str) -> Result<u128, String> {
+    let (start, end) = load(filename)?;
+    return count_valid_in_range(&start, &end, true);
+}
 
-    while password <= end_as_vec {
-        // move past anything invalid
-        let invalid3 = first_invalid_by_rule_3(&password, part2);
-        let invalid4 = first_invalid_by_rule_4(&password);
-        let invalid = usize::min(invalid3, invalid4);
-        if invalid > final_index {
-            // valid
-            count_valid += 1;
-            advance(&mut password, final_index);
-        } else {
-            // not valid
-            advance(&mut password, invalid);
-        }
-    }
-    return count_valid;
+#[test]
+fn test_part_1() {
+    assert!(rules(false).is_valid("111111"));
+    assert!(!rules(false).is_valid("223450"));
+    assert!(!rules(false).is_valid("123789"));
 }
 
 #[test]
 fn test_part_2() {
-    let mut password: Vec<u8> = Vec::from("112233".as_bytes());
-    assert_eq!(first_invalid_by_rule_3(&password, true), password.len());
-    password = Vec::from("123444".as_bytes());
-    assert_eq!(first_invalid_by_rule_3(&password, true), password.len() - 1);
-    password = Vec::from("111122".as_bytes());
-    assert_eq!(first_invalid_by_rule_3(&password, true), password.len());
+    assert!(rules(true).is_valid("112233"));
+    assert!(!rules(true).is_valid("123444"));
+    assert!(rules(true).is_valid("111122"));
+}
+
+#[test]
+fn test_answers() {
+    assert_eq!(part1("input"), Ok(1767));
+    assert_eq!(part2("input"), Ok(1192));
+    // far more digits than could be tried one by one
+    assert_eq!(count_valid_in_range(&"1".repeat(20), &"9".repeat(20), false), Ok(3108105));
 }
 
-fn main() {
-    println!("{}", count_valid_in_range(START, END, false));
-    println!("{}", count_valid_in_range(START, END, true));
+pub struct Solution;
+
+impl aoc::Puzzle for Solution {
+    fn part1(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part1(filename)?.to_string());
+    }
+
+    fn part2(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part2(filename)?.to_string());
+    }
 }
 
+fn main() -> Result<(), String> {
+    println!("{}", part1("input")?);
+    println!("{}", part2("input")?);
+    return Ok(());
+}
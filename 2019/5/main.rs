@@ -1,175 +1,61 @@
 
-use std::fs::File;
-use std::io::{self, BufRead};
-use std::collections::HashMap;
+extern crate intcode;
+use intcode::{Word, MachineState, load_from_input, run};
+#[cfg(test)]
+use intcode::load;
 
 
-type Memory = HashMap<i32, i32>;
-type InputOutput = Vec<i32>;
-
-fn load_from_input() -> Memory {
-    let file = File::open("input").unwrap();
-    let line = io::BufReader::new(file).lines().next().expect("line").unwrap();
-    return load(line.as_str());
+#[test]
+fn test_old_program() {
+    assert_eq!(run(&mut load("1,9,10,3,2,3,11,0,99,30,40,50")), Ok(Some(3500)));
+    assert_eq!(run(&mut load("1,0,0,0,99")), Ok(Some(2)));
+    assert_eq!(run(&mut load("2,3,0,3,99")), Ok(Some(2)));
+    assert_eq!(run(&mut load("2,4,4,0,99,0")), Ok(Some(9801)));
+    assert_eq!(run(&mut load("1,1,1,4,99,5,6,0,99")), Ok(Some(30)));
 }
 
-fn load(line: &str) -> Memory {
-    let mut memory: Memory = HashMap::new();
-    let mut index: i32 = 0;
-    for code in line.split(",") {
-        let trimmed = code.trim();
-        let parsed: i32 = trimmed.parse().expect("number");
-        memory.insert(index, parsed);
-        index += 1;
-    }
-    return memory;
+#[test]
+fn test_io_program() {
+    let mut ms = load("3,0,4,0,99");
+    ms.push_input(123);
+    assert_eq!(run(&mut ms), Ok(Some(123)));
+    assert!(ms.input.is_empty());
+    assert!(ms.output.len() == 1);
+    assert!(ms.pop_output().unwrap() == 123);
 }
 
-fn get_parameter_mode(opcode: i32, index: i32) -> i32 {
-    assert!(index >= 1);
-    let mut copy = opcode / 100;
-    for _ in 1 .. index {
-        copy = copy / 10;
-    }
-    return copy % 10;
+fn part1(filename: &str) -> Word {
+    let mut ms: MachineState = load_from_input(filename);
+    ms.push_input(1);
+    run(&mut ms).unwrap();
+    assert!(!ms.output.is_empty());
+    return ms.output.pop_back().unwrap();
 }
 
-fn load_memory(memory: &mut Memory, address: i32) -> i32 {
-    return *memory.get(&address).unwrap_or(&0);
+fn part2(filename: &str) -> Word {
+    let mut ms: MachineState = load_from_input(filename);
+    ms.push_input(5);
+    run(&mut ms).unwrap();
+    assert!(!ms.output.is_empty());
+    return ms.output.pop_back().unwrap();
 }
 
-fn load_parameter(memory: &mut Memory, pc: i32, index: i32) -> i32 {
-    assert!(index >= 1);
-    let opcode = load_memory(memory, pc);
-    let parameter = load_memory(memory, pc + index);
 
-    return match get_parameter_mode(opcode, index) {
-        0 => load_memory(memory, parameter),
-        1 => parameter,
-        _ => panic!(),
-    }
-}
+pub struct Solution;
 
-fn run(memory: &mut Memory, input: &mut InputOutput,
-       output: &mut InputOutput) -> i32 {
-    let mut pc: i32 = 0;
-    loop {
-        let opcode = *memory.get(&pc).unwrap_or(&0);
-        let a = load_parameter(memory, pc, 1);
-        let b = load_parameter(memory, pc, 2);
-        match opcode % 100 {
-            1 => {
-                let r = load_memory(memory, pc + 3);
-                pc += 4;
-                memory.insert(r, a + b);
-            },
-            2 => {
-                let r = load_memory(memory, pc + 3);
-                pc += 4;
-                memory.insert(r, a * b);
-            },
-            3 => {
-                let r = load_memory(memory, pc + 1);
-                pc += 2;
-                memory.insert(r, input.pop().unwrap_or(0));
-            },
-            4 => {
-                pc += 2;
-                output.push(a);
-            },
-            5 => {
-                pc += 3;
-                if a != 0 {
-                    pc = b;
-                }
-            },
-            6 => {
-                pc += 3;
-                if a == 0 {
-                    pc = b;
-                }
-            },
-            7 => {
-                let r = load_memory(memory, pc + 3);
-                if a < b {
-                    memory.insert(r, 1);
-                } else {
-                    memory.insert(r, 0);
-                }
-                pc += 4;
-            },
-            8 => {
-                let r = load_memory(memory, pc + 3);
-                if a == b {
-                    memory.insert(r, 1);
-                } else {
-                    memory.insert(r, 0);
-                }
-                pc += 4;
-            },
-            99 => {
-                return load_memory(memory, 0);
-            },
-            _ => {
-                panic!();
-            }
-        }
+impl aoc::Puzzle for Solution {
+    fn part1(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part1(filename).to_string());
     }
-}
 
-#[test]
-fn test_old_program() {
-    let mut n1: InputOutput = Vec::new();
-    let mut n2: InputOutput = Vec::new();
-    assert_eq!(run(&mut load("1,9,10,3,2,3,11,0,99,30,40,50"),
-                   &mut n1, &mut n2), 3500);
-    assert_eq!(run(&mut load("1,0,0,0,99"),
-                   &mut n1, &mut n2), 2);
-    assert_eq!(run(&mut load("2,3,0,3,99"),
-                   &mut n1, &mut n2), 2);
-    assert_eq!(run(&mut load("2,4,4,0,99,0"),
-                   &mut n1, &mut n2), 9801);
-    assert_eq!(run(&mut load("1,1,1,4,99,5,6,0,99"),
-                   &mut n1, &mut n2), 30);
-}
-
-#[test]
-fn test_io_program() {
-    let mut n1: InputOutput = Vec::new();
-    let mut n2: InputOutput = Vec::new();
-    n1.push(123);
-    assert_eq!(run(&mut load("3,0,4,0,99"),
-                   &mut n1, &mut n2), 123);
-    assert!(n1.is_empty());
-    assert!(n2.len() == 1);
-    assert!(n2.pop().unwrap() == 123);
-}
-
-fn part1() -> i32 {
-    let mut memory = load_from_input();
-    let mut input: InputOutput = Vec::new();
-    let mut output: InputOutput = Vec::new();
-    input.push(1);
-    run(&mut memory, &mut input, &mut output);
-    assert!(!output.is_empty());
-    return output.pop().unwrap();
-}
-
-fn part2() -> i32 {
-    let mut memory = load_from_input();
-    let mut input: InputOutput = Vec::new();
-    let mut output: InputOutput = Vec::new();
-    input.push(5);
-    run(&mut memory, &mut input, &mut output);
-    assert!(!output.is_empty());
-    return output.pop().unwrap();
+    fn part2(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part2(filename).to_string());
+    }
 }
 
-
 fn main() {
-    let p1 = part1();
+    let p1 = part1("input");
     println!("{}", p1);
-    let p2 = part2();
+    let p2 = part2("input");
     println!("{}", p2);
 }
-
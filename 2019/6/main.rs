@@ -1,128 +1,60 @@
+use std::env;
+use std::fs;
 
-use std::fs::File;
-use std::io::{self, BufRead};
-use std::collections::HashMap;
-use std::rc::Rc;
-use std::cell::RefCell;
+extern crate orbit;
+use orbit::Space;
 
-struct Object {
-    parent: Option<Rc<RefCell<Object>>>,
-    flag: bool,
-}
 
-struct Space {
-    object: HashMap<String, Rc<RefCell<Object>>>,
+fn load_from_input(filename: &str) -> Result<Space, String> {
+    let text = fs::read_to_string(filename).map_err(|e| format!("{}: {}", filename, e))?;
+    return Space::parse(&text);
 }
 
-impl Space {
-    fn new() -> Self {
-        return Self {
-            object: HashMap::new(),
-        };
-    }
-
-    fn create(self: &mut Self, name: &str) {
-        if !self.object.contains_key(name) {
-            self.object.insert(name.to_string(), Rc::new(RefCell::new(Object {
-                parent: None,
-                flag: false,
-            })));
-        }
-    }
-
-    fn get(self: &Self, name: &str) -> Option<Rc<RefCell<Object>>> {
-        return self.object.get(name).cloned();
-    }
-
-    fn parent(self: &Self, satellite: Rc<RefCell<Object>>) -> Option<Rc<RefCell<Object>>> {
-        if let Some(planet) = &satellite.borrow().parent {
-            return Some(planet.clone());
-        }
-        return None;
-    }
-
-    fn add_link(self: &mut Self,
-                planet_name: &str, satellite_name: &str) {
-        
-        self.create(planet_name);
-        self.create(satellite_name);
-        if let Some(item1) = self.get(satellite_name) {
-            let mut item2 = item1.borrow_mut();
-            item2.parent = Some(self.get(planet_name).unwrap().clone());
-        }
-    }
+fn part1(space: &Space) -> usize {
+    return space.count_transitive();
+}
 
-    fn count_transitive(self: &mut Self) -> u32 {
-        let mut count = 0;
-        for satellite_name in self.object.keys() {
-            if let Some(mut item1) = self.get(satellite_name) {
-                while let Some(item2) = self.parent(item1) {
-                    item1 = item2;
-                    count += 1;
-                }
-            }
-        }
-        return count;
-    }
+// orbital transfers needed to move from the object YOU orbit to the one SAN orbits
+fn part2(space: &Space) -> Option<usize> {
+    let path = space.path(space.parent("YOU")?, space.parent("SAN")?)?;
+    return Some(path.len() - 1);
+}
 
-    fn flip_flag(self: &mut Self, satellite_name: &str) -> u32 {
-        let mut count = 0;
-        if let Some(mut item1) = self.get(satellite_name) {
-            while let Some(item2) = self.parent(item1) {
-                {
-                    let mut item3 = item2.borrow_mut();
-                    if !item3.flag {
-                        count += 1;
-                        item3.flag = true;
-                    } else {
-                        item3.flag = false;
-                    }
-                }
-                item1 = item2;
-            }
-        }
-        return count;
-    }
+#[test]
+fn test() {
+    let space = load_from_input("input").unwrap();
+    assert_eq!(139597, part1(&space));
+    assert_eq!(Some(286), part2(&space));
 }
 
-fn load_from_input() -> Space {
-    let file = File::open("input").unwrap();
-    let mut space = Space::new();
-    for line in io::BufReader::new(file).lines() {
-        if let Ok(line_string) = line {
-            let mut pair = line_string.trim().split(')');
-            let planet_name = pair.next().unwrap();
-            let satellite_name = pair.next().unwrap();
+pub struct Solution;
 
-            space.add_link(planet_name, satellite_name);
-        }
+impl aoc::Puzzle for Solution {
+    fn part1(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part1(&load_from_input(filename)?).to_string());
     }
-    return space;
-}
 
-fn part1() -> u32 {
-    let mut space = load_from_input();
-    return space.count_transitive();
+    fn part2(self: &Self, filename: &str) -> Result<String, String> {
+        let space = load_from_input(filename)?;
+        return Ok(part2(&space).ok_or("YOU and SAN should both orbit something")?.to_string());
+    }
 }
 
-fn part2() -> u32 {
-    let mut space = load_from_input();
-    let you_to_root = space.flip_flag("YOU");
-    let santa_to_meeting = space.flip_flag("SAN");
-    let meeting_to_root = space.flip_flag("YOU");
-    let you_to_meeting = you_to_root - meeting_to_root;
-    let you_to_santa = you_to_meeting + santa_to_meeting;
-    return you_to_santa;
-}
+fn main() -> Result<(), String> {
+    let args: Vec<String> = env::args().collect();
+    let dot = match args.len() {
+        1 => None,
+        3 if args[1] == "--dot" => Some(&args[2]),
+        _ => { return Err("Optionally, give --dot <file> to save the orbit map \
+                           in Graphviz format".to_string()); },
+    };
 
-#[test]
-fn test() {
-    assert_eq!(139597, part1());
-    assert_eq!(286, part2());
-}
+    let space = load_from_input("input")?;
+    println!("{}", part1(&space));
+    println!("{}", part2(&space).ok_or("YOU and SAN should both orbit something")?);
 
-fn main() {
-    println!("{}", part1());
-    println!("{}", part2());
+    if let Some(filename) = dot {
+        fs::write(filename, space.to_dot()).map_err(|e| format!("{}: {}", filename, e))?;
+    }
+    return Ok(());
 }
-
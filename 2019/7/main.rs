@@ -1,287 +1,215 @@
+extern crate intcode;
+use intcode::{Word, Memory, MachineState, load_from_input};
+use intcode::network::{Outcome, chain, ring};
 
-use std::fs::File;
-use std::io::{self, BufRead};
-use std::collections::HashMap;
+use std::thread;
 
 
-type Memory = HashMap<i32, i32>;
-type InputOutput = Vec<i32>;
-
-fn load_from_input() -> Memory {
-    let file = File::open("input").unwrap();
-    let line = io::BufReader::new(file).lines().next().expect("line").unwrap();
-    return load(line.as_str());
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+enum Topology {
+    // each amplifier's output goes to the next one, and the last one's
+    // output is the thrust
+    Series,
+    // as Series, but the last amplifier's output is fed back to the first
+    // until they have all halted
+    Feedback,
 }
 
-fn load(line: &str) -> Memory {
-    let mut memory: Memory = HashMap::new();
-    let mut index: i32 = 0;
-    for code in line.split(",") {
-        let trimmed = code.trim();
-        let parsed: i32 = trimmed.parse().expect("number");
-        memory.insert(index, parsed);
-        index += 1;
-    }
-    return memory;
-}
-
-fn get_parameter_mode(opcode: i32, index: i32) -> i32 {
-    assert!(index >= 1);
-    let mut copy = opcode / 100;
-    for _ in 1 .. index {
-        copy = copy / 10;
-    }
-    return copy % 10;
+// A search for the phase settings giving the most thrust: the amplifiers
+// each get a different setting from `phases`
+struct Search {
+    memory: Memory,
+    amplifiers: usize,
+    phases: Vec<Word>,
+    topology: Topology,
 }
 
-fn load_memory(memory: &mut Memory, address: i32) -> i32 {
-    return *memory.get(&address).unwrap_or(&0);
+#[derive(Eq, PartialEq, Debug)]
+struct Best {
+    thrust: Word,
+    phases: Vec<Word>,
 }
 
-fn load_parameter(memory: &mut Memory, pc: i32, index: i32) -> i32 {
-    assert!(index >= 1);
-    let opcode = load_memory(memory, pc);
-    let parameter = load_memory(memory, pc + index);
-
-    return match get_parameter_mode(opcode, index) {
-        0 => load_memory(memory, parameter),
-        1 => parameter,
-        _ => panic!(),
+// Every way of choosing `count` different items from `items`, in order
+fn permutations(items: &[Word], count: usize) -> Vec<Vec<Word>> {
+    if count == 0 {
+        return vec![Vec::new()];
     }
-}
-
-fn run(memory: &mut Memory, input: &mut InputOutput,
-       output: &mut InputOutput, pc: &mut i32) -> Option<i32> {
-    loop {
-        let opcode = *memory.get(&pc).unwrap_or(&0);
-        let a = load_parameter(memory, *pc, 1);
-        let b = load_parameter(memory, *pc, 2);
-        match opcode % 100 {
-            1 => {
-                let r = load_memory(memory, *pc + 3);
-                *pc += 4;
-                memory.insert(r, a + b);
-            },
-            2 => {
-                let r = load_memory(memory, *pc + 3);
-                *pc += 4;
-                memory.insert(r, a * b);
-            },
-            3 => {
-                if input.is_empty() {
-                    return None;
-                }
-                let r = load_memory(memory, *pc + 1);
-                *pc += 2;
-                memory.insert(r, input.pop().unwrap_or(0));
-            },
-            4 => {
-                *pc += 2;
-                output.push(a);
-            },
-            5 => {
-                *pc += 3;
-                if a != 0 {
-                    *pc = b;
-                }
-            },
-            6 => {
-                *pc += 3;
-                if a == 0 {
-                    *pc = b;
-                }
-            },
-            7 => {
-                let r = load_memory(memory, *pc + 3);
-                if a < b {
-                    memory.insert(r, 1);
-                } else {
-                    memory.insert(r, 0);
-                }
-                *pc += 4;
-            },
-            8 => {
-                let r = load_memory(memory, *pc + 3);
-                if a == b {
-                    memory.insert(r, 1);
-                } else {
-                    memory.insert(r, 0);
-                }
-                *pc += 4;
-            },
-            99 => {
-                return Some(load_memory(memory, 0));
-            },
-            _ => {
-                println!("illegal instruction {} at {}", opcode, *pc);
-                panic!();
-            }
+    let mut result: Vec<Vec<Word>> = Vec::new();
+    for i in 0 .. items.len() {
+        let mut rest = items.to_vec();
+        let first = rest.remove(i);
+        for tail in permutations(&rest, count - 1) {
+            let mut p = vec![first];
+            p.extend(tail);
+            result.push(p);
         }
     }
+    return result;
 }
 
-
-const NUM_PHASE_SETTINGS: usize = 5;
-const NUM_AMPLIFIERS: u8 = 5;
-
-struct Part1State {
-    input: InputOutput,
-    output: InputOutput,
-    initial_memory: Memory,
-    carry: i32,
-    max_thrust: i32,
-    phases_used: [bool; NUM_PHASE_SETTINGS],
-    depth: u8,
+// Keep whichever has more thrust; the earlier one wins a tie
+fn better(best: Option<Best>, candidate: Best) -> Option<Best> {
+    return match best {
+        Some(best) if best.thrust >= candidate.thrust => Some(best),
+        _ => Some(candidate),
+    };
 }
 
-fn part1_solve(state: &mut Part1State) {
-
-    if state.depth >= NUM_AMPLIFIERS {
-        state.max_thrust = i32::max(state.max_thrust, state.carry);
-        return;
+impl Search {
+    fn new(memory: Memory, amplifiers: usize, phases: &[Word], topology: Topology) -> Self {
+        return Search {
+            memory: memory,
+            amplifiers: amplifiers,
+            phases: phases.to_vec(),
+            topology: topology,
+        };
     }
 
-    for i in 0 .. NUM_PHASE_SETTINGS {
-        if state.phases_used[i] {
-            continue;
+    // The thrust for one assignment of phase settings to the amplifiers,
+    // which must all halt having given exactly one output
+    fn thrust(self: &Self, phases: &[Word]) -> Result<Word, String> {
+        // each amplifier reads its phase, then signals from the one before it
+        let mut amps: Vec<MachineState> = Vec::new();
+        for phase in phases {
+            let mut ms = MachineState::new(self.memory.clone());
+            ms.push_input(*phase);
+            amps.push(ms);
+        }
+        let (mut network, output) = match self.topology {
+            Topology::Series => (chain(amps), "out"),
+            Topology::Feedback => (ring(amps), "0"),
+        };
+        network.send("0", 0);
+        let context = |problem: String| format!("phases {:?}: {}", phases, problem);
+        if let Outcome::Deadlock(waiting) = network.run().map_err(|e| context(e.to_string()))? {
+            return Err(context(format!("deadlock waiting for input at amplifiers {}",
+                                       waiting.join(", "))));
         }
-        assert!(state.output.is_empty());
-        assert!(state.input.is_empty());
-        state.input.push(state.carry);
-        state.input.push(i as i32);
 
-        let mut memory = state.initial_memory.clone();
-        let mut pc: i32 = 0;
-        let rc = run(&mut memory, &mut state.input,
-                     &mut state.output, &mut pc);
-        assert!(rc.is_some());
+        let thrust = network.receive(output).ok_or(context("there is no thrust".to_string()))?;
+        if network.receive(output).is_some() {
+            return Err(context("there is more than one thrust".to_string()));
+        }
+        return Ok(thrust);
+    }
+
+    // Try every permutation, shared out between up to `threads` threads.
+    // Returns None if there are more amplifiers than phase settings.
+    fn run(self: &Self, threads: usize) -> Result<Option<Best>, String> {
+        let candidates = permutations(&self.phases, self.amplifiers);
+        let size = usize::max(1, candidates.len().div_ceil(usize::max(1, threads)));
+        let results: Vec<Result<Option<Best>, String>> = thread::scope(|scope| {
+            let workers: Vec<_> = candidates.chunks(size).map(|part| {
+                scope.spawn(move || {
+                    let mut best: Option<Best> = None;
+                    for phases in part {
+                        let thrust = self.thrust(phases)?;
+                        best = better(best, Best { thrust: thrust, phases: phases.clone() });
+                    }
+                    return Ok(best);
+                })
+            }).collect();
+            return workers.into_iter().map(|w| {
+                w.join().unwrap_or(Err("a search thread panicked".to_string()))
+            }).collect();
+        });
 
-        let saved = state.carry;
-        state.phases_used[i] = true;
-        state.carry = state.output.pop().unwrap();
-        state.depth += 1;
-        part1_solve(state);
-        state.depth -= 1;
-        state.carry = saved;
-        state.phases_used[i] = false;
+        // the chunks are in order, so ties still go to the first permutation
+        let mut best: Option<Best> = None;
+        for result in results {
+            if let Some(candidate) = result? {
+                best = better(best, candidate);
+            }
+        }
+        return Ok(best);
     }
 }
 
-fn part1() {
-    let mut state = Part1State {
-        input: Vec::new(),
-        output: Vec::new(),
-        initial_memory: load_from_input(),
-        carry: 0,
-        max_thrust: -1,
-        phases_used: [false; NUM_PHASE_SETTINGS],
-        depth: 0,
-    };
-    part1_solve(&mut state);
-    println!("{}", state.max_thrust);
+fn threads() -> usize {
+    return thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
 }
 
-struct Part2State {
-    amps: Vec<AmpState>,
-    initial_memory: Memory,
-    phases_used: [bool; NUM_PHASE_SETTINGS],
-    phase_assignment: [i32; NUM_AMPLIFIERS as usize],
-    max_thrust: i32,
-    depth: u8,
+fn part1(filename: &str) -> Result<Best, String> {
+    let search = Search::new(load_from_input(filename).memory, 5, &[0, 1, 2, 3, 4],
+                             Topology::Series);
+    return Ok(search.run(threads())?.unwrap());
 }
 
-struct AmpState {
-    input: InputOutput,
-    output: InputOutput,
-    memory: Memory,
-    pc: i32,
-    number: usize,
+fn part2(filename: &str) -> Result<Best, String> {
+    let search = Search::new(load_from_input(filename).memory, 5, &[5, 6, 7, 8, 9],
+                             Topology::Feedback);
+    return Ok(search.run(threads())?.unwrap());
 }
 
-fn part2_solve(state: &mut Part2State) {
-    // start up each amplifier
-    // run to the point where input is expected
-    for amp in state.amps.iter_mut() {
-        assert!(amp.output.is_empty());
-        assert!(amp.input.is_empty());
-        amp.memory = state.initial_memory.clone();
-        amp.pc = 0;
-        amp.input.push(state.phase_assignment[amp.number]);
-        let rc = run(&mut amp.memory, &mut amp.input,
-                     &mut amp.output, &mut amp.pc);
-        assert!(rc.is_none());
-        assert!(amp.input.is_empty());
-        assert!(amp.output.is_empty());
-    }
-
-    let mut feedback: i32 = 0;
-    let mut terminated: bool = false;
-
-    while !terminated {
-        // iterate to the next amplifier state
-        for amp in state.amps.iter_mut() {
-            amp.input.push(feedback);
-            let rc = run(&mut amp.memory, &mut amp.input,
-                         &mut amp.output, &mut amp.pc);
-            if amp.number == 0 {
-                terminated = rc.is_some();
-            } else {
-                assert_eq!(terminated, rc.is_some());
-            }
-            assert!(amp.input.is_empty());
-            assert_eq!(amp.output.len(), 1);
-            feedback = amp.output.pop().unwrap();
-        }
+#[test]
+fn test_search() {
+    let best = |program: &str, phases: &[Word], topology: Topology, threads: usize| {
+        let search = Search::new(intcode::load(program).memory, phases.len(), phases, topology);
+        return search.run(threads).unwrap();
+    };
+    let program = "3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0";
+    for threads in [1, 4, 500] {
+        assert_eq!(best(program, &[0, 1, 2, 3, 4], Topology::Series, threads),
+                   Some(Best { thrust: 43210, phases: vec![4, 3, 2, 1, 0] }));
     }
+    let program = "3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,\
+                   27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5";
+    assert_eq!(best(program, &[5, 6, 7, 8, 9], Topology::Feedback, 3),
+               Some(Best { thrust: 139629729, phases: vec![9, 8, 7, 6, 5] }));
+
+    // fewer amplifiers than phase settings
+    assert_eq!(permutations(&[1, 2, 3], 2),
+               vec![vec![1, 2], vec![1, 3], vec![2, 1], vec![2, 3], vec![3, 1], vec![3, 2]]);
+    let program = "3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0";
+    let search = Search::new(intcode::load(program).memory, 2, &[1, 2, 3, 4],
+                             Topology::Series);
+    assert_eq!(search.run(2).unwrap(), Some(Best { thrust: 43, phases: vec![4, 3] }));
+    let search = Search::new(intcode::load(program).memory, 3, &[1, 2], Topology::Series);
+    assert_eq!(search.run(2).unwrap(), None);
+}
 
-    state.max_thrust = i32::max(state.max_thrust, feedback);
+#[test]
+fn test_errors() {
+    let error = |program: &str, topology: Topology| {
+        let search = Search::new(intcode::load(program).memory, 2, &[1, 2], topology);
+        return search.run(2).unwrap_err();
+    };
+    // the second amplifier never gets a signal
+    assert_eq!(error("3,0,3,0,99", Topology::Series),
+               "phases [1, 2]: deadlock waiting for input at amplifiers 1");
+    // the first amplifier wants another signal before it passes one on
+    assert_eq!(error("3,0,3,0,3,0,99", Topology::Feedback),
+               "phases [1, 2]: deadlock waiting for input at amplifiers 0, 1");
+    assert_eq!(error("3,0,99", Topology::Series), "phases [1, 2]: there is no thrust");
+    assert_eq!(error("3,0,3,0,4,0,4,0,99", Topology::Series),
+               "phases [1, 2]: there is more than one thrust");
+    assert_eq!(error("3,0,42", Topology::Series),
+               "phases [1, 2]: machine 0: unknown opcode 42 at 2");
 }
 
-fn part2_assign_phases(state: &mut Part2State) {
+#[test]
+fn test_answers() {
+    assert_eq!(part1("input").unwrap().thrust, 21860);
+    assert_eq!(part2("input").unwrap().thrust, 2645740);
+}
 
-    if state.depth >= NUM_AMPLIFIERS {
-        part2_solve(state);
-        return;
-    }
+pub struct Solution;
 
-    for i in 0 .. NUM_PHASE_SETTINGS {
-        if state.phases_used[i] {
-            continue;
-        }
-        state.phases_used[i] = true;
-        state.phase_assignment[state.depth as usize] = 5 + i as i32;
-        state.depth += 1;
-        part2_assign_phases(state);
-        state.depth -= 1;
-        state.phase_assignment[state.depth as usize] = -1;
-        state.phases_used[i] = false;
+impl aoc::Puzzle for Solution {
+    fn part1(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part1(filename)?.thrust.to_string());
     }
-}
 
-fn part2() {
-    let mut state = Part2State {
-        amps: Vec::new(),
-        initial_memory: load_from_input(),
-        max_thrust: -1,
-        phases_used: [false; NUM_PHASE_SETTINGS],
-        phase_assignment: [-1; NUM_AMPLIFIERS as usize],
-        depth: 0,
-    };
-    for i in 0 .. NUM_AMPLIFIERS {
-        state.amps.push(AmpState {
-            input: Vec::new(),
-            output: Vec::new(),
-            memory: HashMap::new(),
-            pc: 0,
-            number: i as usize,
-        });
+    fn part2(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part2(filename)?.thrust.to_string());
     }
-    part2_assign_phases(&mut state);
-    println!("{}", state.max_thrust);
 }
 
-fn main() {
-    part1();
-    part2();
+fn main() -> Result<(), String> {
+    let best = part1("input")?;
+    println!("{}", best.thrust);
+    let best = part2("input")?;
+    println!("{}", best.thrust);
+    return Ok(());
 }
-
@@ -1,98 +1,96 @@
+use std::env;
+use std::fs;
 
-use std::fs::File;
-use std::io::{self, BufRead};
+mod sif;
+use sif::{Image, BLACK_PIXEL, WHITE_PIXEL, TRANSPARENT};
 
 
 const WIDTH: usize = 25;
 const HEIGHT: usize = 6;
 
-struct Layer {
-    pixel: [[u8; WIDTH]; HEIGHT],
+fn load(filename: &str) -> Result<Image, String> {
+    let text = fs::read_to_string(filename).map_err(|e| format!("{}: {}", filename, e))?;
+    return Image::decode(&text, WIDTH, HEIGHT);
 }
 
-fn load() -> Vec<Layer> {
-    let file = File::open("input").unwrap();
-    let line = io::BufReader::new(file).lines().next().expect("line").unwrap();
-    let bytes = line.as_bytes();
+// On the layer with the fewest 0 digits, the number of 1 digits
+// multiplied by the number of 2 digits
+fn part1(image: &Image) -> usize {
+    let count = |layer: &Vec<u8>, digit: u8| layer.iter().filter(|p| **p == digit).count();
+    let layer = image.layers.iter().min_by_key(|layer| count(layer, BLACK_PIXEL)).unwrap();
+    return count(layer, WHITE_PIXEL) * count(layer, TRANSPARENT);
+}
 
-    assert_eq!(bytes.len() % (WIDTH * HEIGHT), 0);
-    let num_layers = bytes.len() / (WIDTH * HEIGHT);
+fn part2(image: &Image) -> Result<String, String> {
+    return ocr::recognise(&image.white());
+}
 
-    let mut layers: Vec<Layer> = Vec::new();
-    let mut index: usize = 0;
-    for _ in 0 .. num_layers {
-        let mut layer = Layer {
-            pixel: [[0; WIDTH]; HEIGHT],
-        };
-        for y in 0 .. HEIGHT {
-            for x in 0 .. WIDTH {
-                layer.pixel[y][x] = bytes[index];
-                index += 1;
-            }
-        }
-        layers.push(layer);
-    }
-    assert_eq!(index, bytes.len());
-    return layers;
+#[test]
+fn test_part1() {
+    assert_eq!(part1(&load("input").unwrap()), 1206);
 }
 
-fn part1() {
-    let layers = load();
-    let mut result: usize = 0;
-    let mut least_zeroes: usize = usize::MAX;
+#[test]
+fn test_part2() {
+    assert_eq!(part2(&load("input").unwrap()), Ok("EJRGP".to_string()));
+}
 
-    for layer in layers {
-        let mut zeroes: usize = 0;
-        let mut ones: usize = 0;
-        let mut twos: usize = 0;
-        for y in 0 .. HEIGHT {
-            for x in 0 .. WIDTH {
-                match layer.pixel[y][x] {
-                    b'0' => zeroes += 1,
-                    b'1' => ones += 1,
-                    b'2' => twos += 1,
-                    _ => {},
-                }
-            }
-        }
-        if zeroes < least_zeroes {
-            least_zeroes = zeroes;
-            result = ones * twos;
-        }
+#[test]
+fn test_built_image() {
+    // "HI" drawn in white over black, split between two layers with the
+    // rest of each transparent
+    let text = "#..#.###\n#..#..#.\n####..#.\n#..#..#.\n#..#..#.\n#..#.###";
+    let mut layer1: Vec<u8> = Vec::new();
+    let mut layer2: Vec<u8> = Vec::new();
+    for (i, c) in text.chars().filter(|c| *c != '\n').enumerate() {
+        let pixel = if c == '#' { WHITE_PIXEL } else { BLACK_PIXEL };
+        let top = i % 8 < 4;
+        layer1.push(if top { pixel } else { TRANSPARENT });
+        layer2.push(if top { TRANSPARENT } else { pixel });
     }
-    println!("{}", result);
+    let image = Image::new(8, 6, vec![layer1, layer2]).unwrap();
+    let image = Image::decode(&image.encode(), 8, 6).unwrap();
+    assert_eq!(part1(&image), 14 * 24);
+    assert_eq!(part2(&image), Ok("HI".to_string()));
 }
 
-fn part2() {
-    let mut layers = load();
-    let mut combined = Layer {
-        pixel: [[0; WIDTH]; HEIGHT],
-    };
-    layers.reverse();
+// Print a .pbm, .pgm or .ppm file as a single layer SIF image
+fn from_image(filename: &str) -> Result<(), String> {
+    let text = fs::read_to_string(filename).map_err(|e| format!("{}: {}", filename, e))?;
+    let picture = raster::Image::decode(&text).map_err(|e| format!("{}: {}", filename, e))?;
+    let image = Image::from_raster(&picture)?;
+    println!("{} {}", image.width, image.height);
+    println!("{}", image.encode());
+    return Ok(());
+}
 
-    for layer in layers {
-        for y in 0 .. HEIGHT {
-            for x in 0 .. WIDTH {
-                match layer.pixel[y][x] {
-                    b'0' | b'1' => combined.pixel[y][x] = layer.pixel[y][x],
-                    _ => {},
-                }
-            }
-        }
+pub struct Solution;
+
+impl aoc::Puzzle for Solution {
+    fn part1(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part1(&load(filename)?).to_string());
     }
-    for y in 0 .. HEIGHT {
-        for x in 0 .. WIDTH {
-            print!("{}", match combined.pixel[y][x] {
-                b'1' => '#',
-                _ => ' ',
-            });
-        }
-        println!();
+
+    fn part2(self: &Self, filename: &str) -> Result<String, String> {
+        return part2(&load(filename)?);
     }
 }
 
-fn main() {
-    part1();
-    part2();
+fn main() -> Result<(), String> {
+    let args: Vec<String> = env::args().collect();
+    let filename = match args.len() {
+        1 => None,
+        3 if args[1] == "--image" => Some(&args[2]),
+        3 if args[1] == "--from-image" => { return from_image(&args[2]); },
+        _ => { return Err("Optionally, give --image <file> to save the decoded image \
+                           as a .pbm, .pgm or .ppm file, or --from-image <file> to \
+                           encode an image as SIF".to_string()); },
+    };
+    let image = load("input")?;
+    println!("{}", part1(&image));
+    println!("{}", part2(&image)?);
+    if let Some(filename) = filename {
+        image.to_raster().save(filename)?;
+    }
+    return Ok(());
 }
-
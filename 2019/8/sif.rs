@@ -0,0 +1,140 @@
+// The Space Image Format. An image is a stack of layers, each `width` by
+// `height` digits, sent one layer after another as a single line of
+// digits, row by row from the top left. In each pixel 0 is black, 1 is
+// white and 2 is transparent; the top (first) layer that is not
+// transparent gives the colour seen.
+
+use raster::{Rgb, BLACK, WHITE, grey};
+
+pub const BLACK_PIXEL: u8 = 0;
+pub const WHITE_PIXEL: u8 = 1;
+pub const TRANSPARENT: u8 = 2;
+
+// Transparent pixels that are still transparent after compositing are
+// drawn mid-grey
+const GREY: Rgb = [128, 128, 128];
+
+// A layer's digits, row by row
+pub type Layer = Vec<u8>;
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub layers: Vec<Layer>,
+}
+
+impl Image {
+    // Check that there is at least one layer, they are all the right size,
+    // and every pixel is a digit
+    pub fn new(width: usize, height: usize, layers: Vec<Layer>) -> Result<Self, String> {
+        if width == 0 || height == 0 {
+            return Err(format!("a {}x{} image has no pixels", width, height));
+        }
+        let size = width.checked_mul(height)
+            .ok_or(format!("a {}x{} image is too large", width, height))?;
+        if layers.is_empty() {
+            return Err("there are no layers".to_string());
+        }
+        for (i, layer) in layers.iter().enumerate() {
+            if layer.len() != size {
+                return Err(format!("layer {} has {} pixels, not {}", i, layer.len(), size));
+            }
+            if let Some(p) = layer.iter().find(|p| **p > 9) {
+                return Err(format!("layer {} has pixel {}, which is not a digit", i, p));
+            }
+        }
+        return Ok(Image { width: width, height: height, layers: layers });
+    }
+
+    // Read a stream of digits; whitespace at either end is ignored
+    pub fn decode(text: &str, width: usize, height: usize) -> Result<Self, String> {
+        let mut digits: Vec<u8> = Vec::new();
+        for c in text.trim().chars() {
+            digits.push(c.to_digit(10).ok_or(format!("'{}' is not a digit", c))? as u8);
+        }
+        let size = width.saturating_mul(height);
+        if size == 0 || digits.is_empty() || !digits.len().is_multiple_of(size) {
+            return Err(format!("there are {} digits, which is not a whole number of \
+                                {}x{} layers", digits.len(), width, height));
+        }
+        return Image::new(width, height, digits.chunks(size).map(|layer| layer.to_vec()).collect());
+    }
+
+    pub fn encode(self: &Self) -> String {
+        return self.layers.iter().flatten().map(|digit| char::from(b'0' + digit)).collect();
+    }
+
+    // The colour seen at each pixel, looking down through the layers
+    pub fn composite(self: &Self) -> Layer {
+        return (0 .. self.width * self.height).map(|i| {
+            self.layers.iter().map(|layer| layer[i]).find(|p| *p != TRANSPARENT).unwrap_or(TRANSPARENT)
+        }).collect();
+    }
+
+    // The composite as rows of pixels, true for white
+    pub fn white(self: &Self) -> Vec<Vec<bool>> {
+        return self.composite().chunks(self.width).map(|row| {
+            row.iter().map(|p| *p == WHITE_PIXEL).collect()
+        }).collect();
+    }
+
+    // The composite, as black, white and grey
+    pub fn to_raster(self: &Self) -> raster::Image {
+        let pixels: Vec<Rgb> = self.composite().iter().map(|p| match *p {
+            BLACK_PIXEL => BLACK,
+            WHITE_PIXEL => WHITE,
+            _ => GREY,
+        }).collect();
+        return raster::Image { width: self.width, height: self.height, pixels: pixels };
+    }
+
+    // A single layer image: dark pixels are black, light ones are white,
+    // and those in between are transparent
+    pub fn from_raster(image: &raster::Image) -> Result<Self, String> {
+        let layer: Layer = image.pixels.iter().map(|rgb| match grey(*rgb) {
+            0 ..= 63 => BLACK_PIXEL,
+            192 ..= 255 => WHITE_PIXEL,
+            _ => TRANSPARENT,
+        }).collect();
+        return Image::new(image.width, image.height, vec![layer]);
+    }
+}
+
+#[test]
+fn test_decode() {
+    let image = Image::decode("123456789012\n", 3, 2).unwrap();
+    assert_eq!(image.layers, vec![vec![1, 2, 3, 4, 5, 6], vec![7, 8, 9, 0, 1, 2]]);
+    assert_eq!(image.encode(), "123456789012");
+
+    assert_eq!(Image::decode("1234567890123", 3, 2).unwrap_err(),
+               "there are 13 digits, which is not a whole number of 3x2 layers");
+    assert!(Image::decode("", 3, 2).is_err());
+    assert!(Image::decode("1234", 0, 2).is_err());
+    assert_eq!(Image::decode("12x4", 2, 2).unwrap_err(), "'x' is not a digit");
+    assert_eq!(Image::new(2, 1, vec![vec![0, 1], vec![0]]).unwrap_err(),
+               "layer 1 has 1 pixels, not 2");
+    assert_eq!(Image::new(2, 1, vec![vec![0, 1], vec![10, 2]]).unwrap_err(),
+               "layer 1 has pixel 10, which is not a digit");
+    assert!(Image::new(usize::MAX, 2, vec![vec![0]]).is_err());
+}
+
+#[test]
+fn test_composite() {
+    let image = Image::decode("0222112222120000", 2, 2).unwrap();
+    assert_eq!(image.composite(), vec![0, 1, 1, 0]);
+    assert_eq!(image.white(), vec![vec![false, true], vec![true, false]]);
+
+    let image = Image::decode("22101212", 2, 2).unwrap();
+    assert_eq!(image.composite(), vec![1, 2, 1, 0]);
+}
+
+#[test]
+fn test_pgm() {
+    let image = Image::decode("122122020020", 3, 2).unwrap();
+    assert_eq!(image.composite(), vec![1, 2, 0, 1, 2, 0]);
+    let pgm = image.to_raster().encode(raster::Format::Pgm);
+    assert_eq!(pgm, "P2\n3 2\n255\n255 128 0\n255 128 0\n");
+    let decoded = Image::from_raster(&raster::Image::decode(&pgm).unwrap()).unwrap();
+    assert_eq!(decoded.layers, vec![image.composite()]);
+}
@@ -0,0 +1,123 @@
+//! Typed input and output for Intcode programs.
+//!
+//! The machine's queues hold raw words; these functions convert them for
+//! the common protocols:
+//!
+//! - ASCII: text is sent as character codes, usually a line at a time,
+//!   and output is read back as text. Values outside ASCII, such as a
+//!   final score, are passed through as numbers.
+//! - Tuples: output is a sequence of fixed-size records, such as the
+//!   `(x, y, tile)` triples drawn by the 2019/13 arcade game.
+//! - Request/response: each input value produces one output value, like
+//!   the 2019/15 repair droid's move and status.
+
+use crate::{Word, MachineState, Status, Fault, step};
+
+/// Part of the output of an ASCII program.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum Ascii {
+    Text(String),
+    /// A value that is not an ASCII character.
+    Value(Word),
+}
+
+/// Queue the character codes of `text`.
+pub fn send_text(ms: &mut MachineState, text: &str) {
+    for c in text.chars() {
+        ms.push_input(c as Word);
+    }
+}
+
+/// Queue the character codes of `line`, followed by a newline.
+pub fn send_line(ms: &mut MachineState, line: &str) {
+    send_text(ms, line);
+    ms.push_input('\n' as Word);
+}
+
+/// Take all of the output as text, with runs of ASCII characters joined
+/// together and any other values separate.
+pub fn receive_text(ms: &mut MachineState) -> Vec<Ascii> {
+    let mut pieces: Vec<Ascii> = Vec::new();
+    for value in ms.output.drain(..) {
+        if (0 .. 128).contains(&value) {
+            let c = value as u8 as char;
+            if let Some(Ascii::Text(text)) = pieces.last_mut() {
+                text.push(c);
+            } else {
+                pieces.push(Ascii::Text(c.to_string()));
+            }
+        } else {
+            pieces.push(Ascii::Value(value));
+        }
+    }
+    return pieces;
+}
+
+/// Take the output as records of `N` values. An incomplete record is
+/// left in the output queue until the rest of it arrives.
+pub fn receive_tuples<const N: usize>(ms: &mut MachineState) -> Vec<[Word; N]> {
+    let mut tuples: Vec<[Word; N]> = Vec::new();
+    while N > 0 && ms.output.len() >= N {
+        let mut tuple = [0; N];
+        for item in tuple.iter_mut() {
+            *item = ms.output.pop_front().unwrap();
+        }
+        tuples.push(tuple);
+    }
+    return tuples;
+}
+
+/// Send one value and run until the program writes one value in reply.
+/// Returns None if the program halted or needed more input first. Any
+/// output already queued is returned first.
+pub fn request(ms: &mut MachineState, value: Word) -> Result<Option<Word>, Fault> {
+    ms.push_input(value);
+    while ms.output.is_empty() {
+        match step(ms)? {
+            Status::Running => {},
+            Status::Blocked | Status::Halted => { break; },
+        }
+    }
+    return Ok(ms.pop_output());
+}
+
+#[test]
+fn test_ascii() {
+    // echo input with the letters upper-cased; 1000 after each newline
+    let source = "
+        loop:   in      input -> [c]
+                cmp     [c] < 97 -> [t]
+                br      if [t] != 0 goto same
+                add     [c] + -32 -> [c]
+        same:   out     [c] -> output
+                cmp     [c] == 10 -> [t]
+                br      if [t] == 0 goto loop
+                out     1000 -> output
+                br      if 1 != 0 goto loop
+        c:      data    0
+        t:      data    0
+    ";
+    let program = crate::asm::assemble(source).unwrap();
+    let mut ms = crate::load(&crate::asm::program_text(&program));
+    send_line(&mut ms, "hello, world");
+    send_text(&mut ms, "more");
+    assert_eq!(crate::run(&mut ms), Ok(None));
+    assert_eq!(receive_text(&mut ms), vec![Ascii::Text("HELLO, WORLD\n".to_string()),
+                                           Ascii::Value(1000),
+                                           Ascii::Text("MORE".to_string())]);
+    assert_eq!(receive_text(&mut ms), vec![]);
+}
+
+#[test]
+fn test_tuples_and_requests() {
+    let mut ms = crate::load("104,1,104,2,104,3,104,4,104,5,99");
+    assert_eq!(crate::run(&mut ms), Ok(Some(104)));
+    assert_eq!(receive_tuples::<2>(&mut ms), vec![[1, 2], [3, 4]]);
+    assert_eq!(Vec::from(ms.output.clone()), vec![5]);
+
+    // reply with double the input, until 0 is sent
+    let mut ms = crate::load("3,15,1006,15,14,1002,15,2,15,4,15,1105,1,0,99,0");
+    assert_eq!(request(&mut ms, 21), Ok(Some(42)));
+    assert_eq!(request(&mut ms, -4), Ok(Some(-8)));
+    assert_eq!(request(&mut ms, 0), Ok(None));
+}
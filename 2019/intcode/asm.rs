@@ -0,0 +1,359 @@
+//! Assembler for Intcode programs.
+//!
+//! The input syntax is the output of `disasm`, one instruction per line:
+//!
+//! ```text
+//! add     [a] + b -> [sp+c]
+//! mul     [a] * b -> [c]
+//! in      input -> [a]
+//! out     [a] -> output
+//! br      if [a] != 0 goto b
+//! br      if [a] == 0 goto b
+//! cmp     [a] < b -> [c]
+//! cmp     [a] == b -> [c]
+//! adj     sp + a -> sp
+//! halt
+//! ```
+//!
+//! Operands are `[a]` (position mode), `a` (immediate mode) or `[sp+a]`
+//! and `[sp-a]` (relative mode). `a` may be a number, a label, or a label
+//! plus or minus a number.
+//!
+//! A line may start with one or more labels such as `loop:`. A line may
+//! also start with the address and opcode columns written by `disasm`:
+//! the address is checked, and the opcode is used as given, which allows
+//! `?a` operands and unusual mode digits to be reassembled exactly.
+//! `data 1, 2, label` places words directly, and so does `? a`, which is
+//! how `disasm` writes words it can't decode. `#` starts a comment.
+//! `flow::disassemble_reachable` output can also be reassembled.
+
+use std::collections::HashMap;
+use crate::{Word, get_parameter_mode};
+
+enum Value {
+    Number(Word),
+    Label(String, Word),
+}
+
+struct Operand {
+    mode: Option<Word>, // None for "?a": the mode comes from the opcode
+    value: Value,
+}
+
+// Operand patterns for each instruction: A, B and C are parameters 1, 2 and 3
+const INSTRUCTIONS: [(&str, Word, &str); 10] = [
+    ("add", 1, "A + B -> C"),
+    ("mul", 2, "A * B -> C"),
+    ("in", 3, "input -> A"),
+    ("out", 4, "A -> output"),
+    ("br", 5, "if A != 0 goto B"),
+    ("br", 6, "if A == 0 goto B"),
+    ("cmp", 7, "A < B -> C"),
+    ("cmp", 8, "A == B -> C"),
+    ("adj", 9, "sp + A -> sp"),
+    ("halt", 99, ""),
+];
+
+fn is_name(text: &str) -> bool {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {},
+        _ => { return false; },
+    }
+    if !chars.all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return false;
+    }
+    return !["sp", "input", "output", "if", "goto"].contains(&text);
+}
+
+fn parse_number(text: &str) -> Result<Word, String> {
+    return text.parse().map_err(|_| format!("'{}' is not a number", text));
+}
+
+fn parse_value(text: &str) -> Result<Value, String> {
+    if let Ok(number) = text.parse() {
+        return Ok(Value::Number(number));
+    }
+    let split = text.find(['+', '-']).unwrap_or(text.len());
+    let name = &text[.. split];
+    if !is_name(name) {
+        return Err(format!("'{}' is not a number or label", text));
+    }
+    let mut offset = 0;
+    if split < text.len() {
+        offset = parse_number(&text[split ..])?;
+    }
+    return Ok(Value::Label(name.to_string(), offset));
+}
+
+fn parse_operand(text: &str) -> Result<Operand, String> {
+    if let Some(value) = text.strip_prefix('?') {
+        return Ok(Operand { mode: None, value: Value::Number(parse_number(value)?) });
+    }
+    if let Some(inner) = text.strip_prefix('[').and_then(|t| t.strip_suffix(']')) {
+        if inner == "sp" {
+            return Ok(Operand { mode: Some(2), value: Value::Number(0) });
+        }
+        if let Some(offset) = inner.strip_prefix("sp") {
+            if offset.starts_with(['+', '-']) {
+                return Ok(Operand { mode: Some(2), value: Value::Number(parse_number(offset)?) });
+            }
+        }
+        return Ok(Operand { mode: Some(0), value: parse_value(inner)? });
+    }
+    return Ok(Operand { mode: Some(1), value: parse_value(text)? });
+}
+
+// Match the operand tokens against a pattern such as "A + B -> C"
+fn match_pattern(pattern: &str, tokens: &[&str]) -> Option<Vec<String>> {
+    let expect: Vec<&str> = pattern.split_whitespace().collect();
+    if expect.len() != tokens.len() {
+        return None;
+    }
+    let mut operands: Vec<(&str, String)> = Vec::new();
+    for (e, t) in expect.iter().zip(tokens) {
+        if ["A", "B", "C"].contains(e) {
+            operands.push((e, t.to_string()));
+        } else if e != t {
+            return None;
+        }
+    }
+    operands.sort();
+    return Some(operands.into_iter().map(|(_, t)| t).collect());
+}
+
+fn encode_instruction(mnemonic: &str, tokens: &[&str],
+                      explicit_opcode: Option<Word>,
+                      words: &mut Vec<Value>) -> Result<(), String> {
+    for (name, base, pattern) in INSTRUCTIONS {
+        if name != mnemonic {
+            continue;
+        }
+        let texts = match match_pattern(pattern, tokens) {
+            Some(texts) => texts,
+            None => { continue; },
+        };
+        let mut operands: Vec<Operand> = Vec::new();
+        for text in &texts {
+            operands.push(parse_operand(text)?);
+        }
+        // Which parameter is written to?
+        let store_index = match base {
+            1 | 2 | 7 | 8 => 3,
+            3 => 1,
+            _ => 0,
+        };
+        if store_index > 0 && operands[store_index - 1].mode == Some(1) {
+            return Err(format!("can't write to immediate operand '{}'",
+                               texts[store_index - 1]));
+        }
+        let opcode = match explicit_opcode {
+            Some(opcode) => {
+                if opcode % 100 != base {
+                    return Err(format!("opcode {} is not '{}'", opcode, mnemonic));
+                }
+                for (i, operand) in operands.iter().enumerate() {
+                    if let Some(mode) = operand.mode {
+                        if get_parameter_mode(opcode, (i + 1) as Word) != mode {
+                            return Err(format!("opcode {} does not match operand '{}'",
+                                               opcode, texts[i]));
+                        }
+                    }
+                }
+                opcode
+            },
+            None => {
+                let mut opcode = base;
+                let mut scale = 100;
+                for (i, operand) in operands.iter().enumerate() {
+                    match operand.mode {
+                        Some(mode) => { opcode += mode * scale; },
+                        None => {
+                            return Err(format!("operand '{}' needs an explicit opcode",
+                                               texts[i]));
+                        },
+                    }
+                    scale *= 10;
+                }
+                opcode
+            },
+        };
+        words.push(Value::Number(opcode));
+        for operand in operands {
+            words.push(operand.value);
+        }
+        return Ok(());
+    }
+    return Err(format!("can't parse '{} {}'", mnemonic, tokens.join(" ")));
+}
+
+fn assemble_line(line: &str, labels: &mut HashMap<String, Word>,
+                 words: &mut Vec<Value>) -> Result<(), String> {
+    let code = match line.find('#') {
+        Some(i) => &line[.. i],
+        None => line,
+    };
+    let mut tokens: Vec<&str> = code.split_whitespace().collect();
+
+    // labels
+    while !tokens.is_empty() && tokens[0].ends_with(':') {
+        let name = tokens[0].strip_suffix(':').unwrap();
+        if !is_name(name) {
+            return Err(format!("'{}' is not a valid label", name));
+        }
+        if labels.insert(name.to_string(), words.len() as Word).is_some() {
+            return Err(format!("label '{}' is defined more than once", name));
+        }
+        tokens.remove(0);
+    }
+    if tokens.is_empty() {
+        return Ok(());
+    }
+
+    // address and opcode columns
+    let mut explicit_opcode: Option<Word> = None;
+    if let Ok(address) = tokens[0].parse::<Word>() {
+        if address != (words.len() as Word) {
+            return Err(format!("address {} should be {}", address, words.len()));
+        }
+        if tokens.len() < 2 {
+            return Err("opcode missing after address".to_string());
+        }
+        explicit_opcode = Some(parse_number(tokens[1])?);
+        tokens.drain(0 .. 2);
+        if tokens.is_empty() {
+            return Err("mnemonic missing after opcode".to_string());
+        }
+    }
+
+    let mnemonic = tokens[0];
+    let rest = &tokens[1 ..];
+    match mnemonic {
+        "data" => {
+            let texts: Vec<String> = rest.join(" ").split(',')
+                .map(|t| t.trim().to_string()).collect();
+            if let Some(opcode) = explicit_opcode {
+                // as written by disasm for a word that is not code
+                if texts.len() != 1 || parse_number(&texts[0]) != Ok(opcode) {
+                    return Err(format!("data does not match value {}", opcode));
+                }
+            }
+            for text in texts {
+                words.push(parse_value(&text)?);
+            }
+        },
+        "?" => {
+            if rest.len() != 2 || rest[0] != "?" {
+                return Err("expected '? ?' followed by a value".to_string());
+            }
+            let value = parse_number(rest[1])?;
+            if let Some(opcode) = explicit_opcode {
+                if opcode != value {
+                    return Err(format!("opcode {} does not match value {}",
+                                       opcode, value));
+                }
+            }
+            words.push(Value::Number(value));
+        },
+        _ => {
+            encode_instruction(mnemonic, rest, explicit_opcode, words)?;
+        },
+    }
+    return Ok(());
+}
+
+/// Assemble source text into a program.
+/// Errors are reported with the line number where they were found.
+pub fn assemble(source: &str) -> Result<Vec<Word>, String> {
+    let mut labels: HashMap<String, Word> = HashMap::new();
+    let mut words: Vec<Value> = Vec::new();
+    let mut line_numbers: Vec<usize> = Vec::new();
+
+    for (i, line) in source.lines().enumerate() {
+        assemble_line(line, &mut labels, &mut words)
+            .map_err(|e| format!("line {}: {}", i + 1, e))?;
+        line_numbers.resize(words.len(), i + 1);
+    }
+
+    let mut program: Vec<Word> = Vec::new();
+    for (value, line_number) in words.iter().zip(line_numbers) {
+        program.push(match value {
+            Value::Number(number) => *number,
+            Value::Label(name, offset) => match labels.get(name) {
+                Some(address) => address + offset,
+                None => {
+                    return Err(format!("line {}: label '{}' is not defined",
+                                       line_number, name));
+                },
+            },
+        });
+    }
+    return Ok(program);
+}
+
+/// Comma-separated text for a program, as expected by `load`.
+pub fn program_text(program: &[Word]) -> String {
+    let texts: Vec<String> = program.iter().map(|w| w.to_string()).collect();
+    return texts.join(",");
+}
+
+#[test]
+fn test_assemble() {
+    let source = "
+        # count down from the input value
+                adj     sp + stack -> sp
+                in      input -> [count]
+        loop:   out     [count] -> output
+                add     [count] + -1 -> [count]
+                br      if [count] != 0 goto loop
+                add     [sp] + 7 -> [sp+1]
+                out     [sp+1] -> output
+                halt
+        count:  data    0
+        stack:  data    5, stack
+    ";
+    let program = assemble(source).unwrap();
+    assert_eq!(program_text(&program),
+               "109,21,3,20,4,20,1001,20,-1,20,1005,20,4,21201,0,7,1,204,1,99,0,5,21");
+    let mut ms = crate::load(&program_text(&program));
+    ms.push_input(3);
+    assert!(crate::run(&mut ms).unwrap().is_some());
+    assert_eq!(Vec::from(ms.output), vec![3, 2, 1, 12]);
+}
+
+#[test]
+fn test_assemble_errors() {
+    assert_eq!(assemble("br if 1 != 0 goto nowhere").unwrap_err(),
+               "line 1: label 'nowhere' is not defined");
+    assert_eq!(assemble("x: halt\nx: halt").unwrap_err(),
+               "line 2: label 'x' is defined more than once");
+    assert_eq!(assemble("in input -> 5").unwrap_err(),
+               "line 1: can't write to immediate operand '5'");
+    assert_eq!(assemble("0 1 add 1 + 2 -> [3]").unwrap_err(),
+               "line 1: opcode 1 does not match operand '1'");
+    assert_eq!(assemble("halt\n0 99 halt").unwrap_err(),
+               "line 2: address 0 should be 1");
+    assert_eq!(assemble("add ?1 + 2 -> [3]").unwrap_err(),
+               "line 1: operand '?1' needs an explicit opcode");
+    assert_eq!(assemble("jmp 4").unwrap_err(),
+               "line 1: can't parse 'jmp 4'");
+}
+
+#[test]
+fn test_round_trip() {
+    // Disassembling and then reassembling gives back exactly the original
+    for day in ["2", "5", "7", "9", "11", "13", "15"] {
+        let filename = format!("../{}/input", day);
+        let original = std::fs::read_to_string(&filename).unwrap();
+        let ms = crate::load_from_input(&filename);
+        let source = crate::disasm::disassemble_program(&ms, ms.memory.len() as Word);
+        let program = assemble(&source).unwrap();
+        assert_eq!(program_text(&program) + "\n", original);
+    }
+
+    // Including invalid modes, immediate stores and truncated instructions
+    let odd = "1101,1,2,3,399,11104,7,304,5,1,2";
+    let ms = crate::load(odd);
+    let source = crate::disasm::disassemble_program(&ms, 11);
+    assert_eq!(program_text(&assemble(&source).unwrap()), odd);
+}
@@ -0,0 +1,485 @@
+//! Differential testing of the Intcode implementations.
+//!
+//! The reference machine (`step`), `fast::FastMachine`, a `trace::Tracer`
+//! and a machine that is passed through `snapshot` every so often should
+//! all behave in exactly the same way. `compare` runs a program on each of
+//! them side by side and reports the first instruction after which any of
+//! them disagrees with the reference: in what the instruction returned,
+//! the pc, the relative base, the input and output queues, or memory.
+//!
+//! Test cases come from the examples given in the 2019/2, 2019/5 and
+//! 2019/9 puzzles, and from `random_case`, which makes up programs that
+//! modify themselves, move the relative base, and often fault.
+
+use std::fmt;
+use std::io;
+use std::collections::BTreeSet;
+use crate::{Word, Memory, MachineState, Status, Fault, load, step};
+use crate::fast::FastMachine;
+use crate::snapshot::{from_text, to_text};
+use crate::trace::Tracer;
+
+// The whole state is compared after this many instructions; the
+// registers are compared after every instruction
+const FULL_CHECK_INTERVAL: u64 = 256;
+
+// The restored machine goes through a snapshot after this many instructions
+const RESTORE_INTERVAL: u64 = 100;
+
+/// The parts of the state that are compared after every instruction.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Registers {
+    pub pc: Word,
+    pub relative_base: Word,
+    pub input: usize,
+    pub output: usize,
+    pub last_output: Option<Word>,
+}
+
+impl Registers {
+    fn new(ms: &MachineState) -> Self {
+        return Self {
+            pc: ms.pc,
+            relative_base: ms.relative_base,
+            input: ms.input.len(),
+            output: ms.output.len(),
+            last_output: ms.output.back().copied(),
+        };
+    }
+}
+
+/// An implementation of the Intcode machine.
+pub trait Machine {
+    fn step(self: &mut Self) -> Result<Status, Fault>;
+    fn registers(self: &Self) -> Registers;
+    fn state(self: &Self) -> MachineState;
+}
+
+impl Machine for MachineState {
+    fn step(self: &mut Self) -> Result<Status, Fault> {
+        return step(self);
+    }
+    fn registers(self: &Self) -> Registers {
+        return Registers::new(self);
+    }
+    fn state(self: &Self) -> MachineState {
+        return self.clone();
+    }
+}
+
+impl Machine for FastMachine {
+    fn step(self: &mut Self) -> Result<Status, Fault> {
+        return FastMachine::step(self);
+    }
+    fn registers(self: &Self) -> Registers {
+        return Registers {
+            pc: self.pc,
+            relative_base: self.relative_base,
+            input: self.input.len(),
+            output: self.output.len(),
+            last_output: self.output.back().copied(),
+        };
+    }
+    fn state(self: &Self) -> MachineState {
+        return self.to_state();
+    }
+}
+
+struct Traced {
+    tracer: Tracer,
+    ms: MachineState,
+}
+
+impl Machine for Traced {
+    fn step(self: &mut Self) -> Result<Status, Fault> {
+        return self.tracer.step(&mut self.ms);
+    }
+    fn registers(self: &Self) -> Registers {
+        return Registers::new(&self.ms);
+    }
+    fn state(self: &Self) -> MachineState {
+        return self.ms.clone();
+    }
+}
+
+struct Restored {
+    ms: MachineState,
+    steps: u64,
+}
+
+impl Machine for Restored {
+    fn step(self: &mut Self) -> Result<Status, Fault> {
+        if self.steps.is_multiple_of(RESTORE_INTERVAL) {
+            self.ms = from_text(&to_text(&self.ms)).unwrap();
+        }
+        self.steps += 1;
+        return step(&mut self.ms);
+    }
+    fn registers(self: &Self) -> Registers {
+        return Registers::new(&self.ms);
+    }
+    fn state(self: &Self) -> MachineState {
+        return self.ms.clone();
+    }
+}
+
+pub struct Implementation {
+    pub name: &'static str,
+    pub start: fn(&MachineState) -> Box<dyn Machine>,
+}
+
+/// Every implementation, starting with the reference.
+pub fn implementations() -> Vec<Implementation> {
+    return vec![
+        Implementation {
+            name: "reference",
+            start: |ms| Box::new(ms.clone()),
+        },
+        Implementation {
+            name: "fast",
+            start: |ms| Box::new(FastMachine::new(ms)),
+        },
+        Implementation {
+            name: "traced",
+            start: |ms| Box::new(Traced {
+                tracer: Tracer::with_trace(Box::new(io::sink())),
+                ms: ms.clone(),
+            }),
+        },
+        Implementation {
+            name: "restored",
+            start: |ms| Box::new(Restored { ms: ms.clone(), steps: 0 }),
+        },
+    ];
+}
+
+/// A program with its input.
+pub struct Case {
+    pub name: String,
+    pub ms: MachineState,
+}
+
+impl Case {
+    pub fn new(name: &str, program: &str, input: &[Word]) -> Self {
+        let mut ms = load(program);
+        for value in input {
+            ms.push_input(*value);
+        }
+        return Self {
+            name: name.to_string(),
+            ms: ms,
+        };
+    }
+}
+
+/// The example programs from the puzzles, with some inputs for each.
+pub fn examples() -> Vec<Case> {
+    let mut cases: Vec<Case> = Vec::new();
+    // 2019/2
+    for program in ["1,9,10,3,2,3,11,0,99,30,40,50", "1,0,0,0,99", "2,3,0,3,99",
+                    "2,4,4,0,99,0", "1,1,1,4,99,5,6,0,99"] {
+        cases.push(Case::new("2019/2 example", program, &[]));
+    }
+    // 2019/5
+    cases.push(Case::new("2019/5 echo", "3,0,4,0,99", &[123]));
+    cases.push(Case::new("2019/5 modes", "1002,4,3,4,33", &[]));
+    cases.push(Case::new("2019/5 negative", "1101,100,-1,4,0", &[]));
+    let compare = [
+        "3,9,8,9,10,9,4,9,99,-1,8",
+        "3,9,7,9,10,9,4,9,99,-1,8",
+        "3,3,1108,-1,8,3,4,3,99",
+        "3,3,1107,-1,8,3,4,3,99",
+        "3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9",
+        "3,3,1105,-1,9,1101,0,0,12,4,12,99,1",
+        "3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,\
+         1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,\
+         999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99",
+    ];
+    for program in compare {
+        for input in [-3, 0, 7, 8, 9] {
+            cases.push(Case::new("2019/5 comparison", program, &[input]));
+        }
+    }
+    // 2019/9
+    cases.push(Case::new("2019/9 quine",
+                         "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99", &[]));
+    cases.push(Case::new("2019/9 16 digits", "1102,34915192,34915192,7,4,7,99,0", &[]));
+    cases.push(Case::new("2019/9 large", "104,1125899906842624,99", &[]));
+    return cases;
+}
+
+// xorshift64*
+struct Random(u64);
+
+impl Random {
+    fn next(self: &mut Self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        return self.0.wrapping_mul(0x2545F4914F6CDD1D);
+    }
+
+    fn below(self: &mut Self, n: Word) -> Word {
+        return (self.next() % n as u64) as Word;
+    }
+}
+
+/// Many random programs loop forever, so they are only run for this many
+/// instructions.
+pub const RANDOM_LIMIT: u64 = 2000;
+
+/// A random program with some input, which is the same for the same seed.
+/// Most parameters are addresses within the program, so that it modifies
+/// itself; a few are negative, far away, or in an unknown mode. Values that
+/// are multiplied over and over can overflow, which is a fault like any other.
+pub fn random_case(seed: u64) -> Case {
+    let mut random = Random(seed.wrapping_mul(0x9E3779B97F4A7C15) | 1);
+    let length = 20 + random.below(40);
+    let mut program: Vec<Word> = Vec::new();
+    while (program.len() as Word) < length {
+        let operation = match random.below(40) {
+            0 => 99,
+            1 => 10 + random.below(89),
+            n => (n % 9) + 1,
+        };
+        let mut opcode = operation;
+        let mut scale = 100;
+        for _ in 0 .. 3 {
+            let mode = match random.below(20) {
+                0 => 3,
+                1 ..= 6 => 2,
+                7 ..= 12 => 1,
+                _ => 0,
+            };
+            opcode += mode * scale;
+            scale *= 10;
+        }
+        program.push(opcode);
+        for _ in 1 .. crate::disasm::instruction_length(operation) {
+            program.push(match random.below(20) {
+                0 => -random.below(5) - 1,
+                1 => 16_777_000 + random.below(1000),
+                2 => random.below(2000),
+                _ => random.below(length),
+            });
+        }
+    }
+    let text: Vec<String> = program.iter().map(|w| w.to_string()).collect();
+    let input: Vec<Word> = (0 .. random.below(6)).map(|_| random.below(200) - 100).collect();
+    return Case::new(&format!("random {}", seed), &text.join(","), &input);
+}
+
+/// Where an implementation first differed from the reference.
+#[derive(Debug, Eq, PartialEq)]
+pub struct Divergence {
+    pub case: String,
+    pub implementation: &'static str,
+    /// Number of instructions executed, including the one that differed.
+    pub step: u64,
+    pub difference: String,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(self: &Self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(f, "{}: {} differs from the reference after {} steps: {}",
+                      self.case, self.implementation, self.step, self.difference);
+    }
+}
+
+fn without_zeros(memory: &Memory) -> Memory {
+    return memory.iter().filter(|(_, v)| **v != 0).map(|(a, v)| (*a, *v)).collect();
+}
+
+// How `actual` differs from `expected`, if it does
+fn state_difference(actual: &MachineState, expected: &MachineState) -> Option<String> {
+    let actual_registers = Registers::new(actual);
+    let expected_registers = Registers::new(expected);
+    if actual_registers != expected_registers {
+        return Some(format!("{:?}, not {:?}", actual_registers, expected_registers));
+    }
+    if actual.input != expected.input {
+        return Some(format!("input {:?}, not {:?}", actual.input, expected.input));
+    }
+    if actual.output != expected.output {
+        return Some(format!("output {:?}, not {:?}", actual.output, expected.output));
+    }
+    let a = without_zeros(&actual.memory);
+    let e = without_zeros(&expected.memory);
+    let addresses: BTreeSet<Word> = a.keys().chain(e.keys()).copied().collect();
+    for address in addresses {
+        let (x, y) = (a.get(&address).unwrap_or(&0), e.get(&address).unwrap_or(&0));
+        if x != y {
+            return Some(format!("memory[{}] is {}, not {}", address, x, y));
+        }
+    }
+    return None;
+}
+
+fn run_side_by_side(case: &Case, implementations: &[Implementation], limit: u64,
+                    interval: u64) -> Result<u64, Divergence> {
+    let mut machines: Vec<Box<dyn Machine>> =
+        implementations.iter().map(|i| (i.start)(&case.ms)).collect();
+    let diverged = |i: usize, step: u64, difference: String| Divergence {
+        case: case.name.clone(),
+        implementation: implementations[i].name,
+        step: step,
+        difference: difference,
+    };
+    let mut steps: u64 = 0;
+    loop {
+        let finished = steps >= limit;
+        if !finished {
+            steps += 1;
+        }
+        let results: Vec<Result<Status, Fault>> = if finished {
+            Vec::new()
+        } else {
+            machines.iter_mut().map(|m| m.step()).collect()
+        };
+        for i in 1 .. results.len() {
+            if results[i] != results[0] {
+                return Err(diverged(i, steps, format!("{:?}, not {:?}", results[i], results[0])));
+            }
+        }
+        let expected = machines[0].registers();
+        for (i, m) in machines.iter().enumerate().skip(1) {
+            let actual = m.registers();
+            if actual != expected {
+                return Err(diverged(i, steps, format!("{:?}, not {:?}", actual, expected)));
+            }
+        }
+        // a fault, halt or lack of input would be repeated by another step
+        let stopped = finished || results[0] != Ok(Status::Running);
+        if stopped || steps.is_multiple_of(interval) {
+            let expected = machines[0].state();
+            for (i, m) in machines.iter().enumerate().skip(1) {
+                if let Some(difference) = state_difference(&m.state(), &expected) {
+                    return Err(diverged(i, steps, difference));
+                }
+            }
+        }
+        if stopped {
+            return Ok(steps);
+        }
+    }
+}
+
+/// Run `case` on each of `implementations` side by side, for up to `limit`
+/// instructions or until it halts, faults or needs more input. The first
+/// implementation is the reference. Returns the number of instructions
+/// executed if they all agree.
+pub fn compare(case: &Case, implementations: &[Implementation],
+               limit: u64) -> Result<u64, Divergence> {
+    return match run_side_by_side(case, implementations, limit, FULL_CHECK_INTERVAL) {
+        // the whole state is not checked every time, so find exactly where
+        // the difference began
+        Err(d) => Err(run_side_by_side(case, implementations, d.step, 1).unwrap_err()),
+        Ok(steps) => Ok(steps),
+    };
+}
+
+#[test]
+fn test_examples() {
+    for case in examples() {
+        assert!(compare(&case, &implementations(), 1000).is_ok(), "{}", case.name);
+    }
+    // the examples run to the end
+    let case = Case::new("quine", "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99", &[]);
+    assert_eq!(compare(&case, &implementations(), 1000), Ok(81));
+}
+
+#[test]
+fn test_random() {
+    let mut faults = 0;
+    for seed in 0 .. 500 {
+        let case = random_case(seed);
+        if let Err(d) = compare(&case, &implementations(), RANDOM_LIMIT) {
+            panic!("{}", d);
+        }
+        let mut ms = case.ms.clone();
+        for _ in 0 .. RANDOM_LIMIT {
+            match step(&mut ms) {
+                Ok(Status::Running) => {},
+                Ok(_) => { break; },
+                Err(_) => { faults += 1; break; },
+            }
+        }
+    }
+    // the random programs test faults as well as successful execution
+    assert!(faults > 50 && faults < 450, "{} faults", faults);
+    assert_eq!(random_case(7).ms, random_case(7).ms);
+}
+
+#[test]
+fn test_overflow() {
+    // this program multiplies until the result overflows, which every
+    // implementation reports as the same fault
+    let case = random_case(16249);
+    assert!(compare(&case, &implementations(), RANDOM_LIMIT).is_ok());
+    let mut ms = case.ms.clone();
+    let fault = loop {
+        match step(&mut ms) {
+            Ok(Status::Running) => {},
+            Ok(status) => { panic!("{:?}", status); },
+            Err(fault) => { break fault; },
+        }
+    };
+    assert_eq!(fault.cause, crate::Cause::Overflow);
+}
+
+#[test]
+fn test_inputs() {
+    for (day, input) in [("2", 0), ("5", 1), ("5", 5), ("9", 1), ("11", 0), ("13", 0), ("15", 1)] {
+        let mut ms = crate::load_from_input(&format!("../{}/input", day));
+        ms.push_input(input);
+        let case = Case { name: format!("2019/{}", day), ms: ms };
+        if let Err(d) = compare(&case, &implementations(), 1_000_000) {
+            panic!("{}", d);
+        }
+    }
+}
+
+#[test]
+fn test_divergence() {
+    // an implementation that gets "less than" wrong for equal values
+    struct Broken(MachineState);
+    impl Machine for Broken {
+        fn step(self: &mut Self) -> Result<Status, Fault> {
+            let before = self.0.clone();
+            let status = step(&mut self.0)?;
+            let opcode = crate::load_memory(&before, before.pc);
+            if opcode == 1107 && crate::load_memory(&before, before.pc + 1) ==
+                                 crate::load_memory(&before, before.pc + 2) {
+                let address = crate::load_memory(&before, before.pc + 3);
+                crate::store_memory(&mut self.0, address, 1);
+            }
+            return Ok(status);
+        }
+        fn registers(self: &Self) -> Registers {
+            return Registers::new(&self.0);
+        }
+        fn state(self: &Self) -> MachineState {
+            return self.0.clone();
+        }
+    }
+    let mut implementations = implementations();
+    implementations.push(Implementation {
+        name: "broken",
+        start: |ms| Box::new(Broken(ms.clone())),
+    });
+
+    let case = Case::new("less than", "3,3,1107,-1,8,3,4,3,99", &[7]);
+    assert_eq!(compare(&case, &implementations, 1000), Ok(4));
+    // with an input of 8 the comparison is wrong; it is found straight
+    // away, although the output is only written two instructions later
+    let case = Case::new("less than", "3,3,1107,-1,8,3,4,3,99", &[8]);
+    let d = compare(&case, &implementations, 1000).unwrap_err();
+    assert_eq!(d, Divergence {
+        case: "less than".to_string(),
+        implementation: "broken",
+        step: 2,
+        difference: "memory[3] is 1, not 0".to_string(),
+    });
+    assert_eq!(d.to_string(),
+               "less than: broken differs from the reference after 2 steps: memory[3] is 1, not 0");
+}
@@ -0,0 +1,348 @@
+//! Interactive debugger for Intcode programs.
+//!
+//! `Debugger::command` carries out one command, such as `step` or
+//! `break 12`, and returns the text to show. The `intcode_debug` program
+//! reads commands from the terminal; tests can use scripts.
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use crate::{Word, MachineState, Status, Fault, list_words, load_memory, store_memory, step};
+use crate::disasm::{disassemble, instruction_length};
+use crate::snapshot;
+
+pub const HELP: &str = "\
+step [n]            execute n instructions (default 1)
+continue            run until a breakpoint, watchpoint, input or halt
+break [address]     set a breakpoint, or list breakpoints
+delete <address>    remove a breakpoint
+watch [address]     stop when the word at address changes, or list watchpoints
+unwatch <address>   remove a watchpoint
+input <value> ...   queue values for the program to read
+output              show and remove the values written by the program
+info                show pc, relative base, I/O queues, break and watchpoints
+list [address]      disassemble around address (default pc)
+memory <address> [n] show n words of memory (default 8)
+set <address> <value> change a word of memory
+save <file>         save the machine state to a file
+restore <file>      replace the machine state with one saved earlier
+help                show this text
+quit                leave the debugger";
+
+/// Why execution stopped.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Stop {
+    /// The requested number of instructions were executed.
+    Done,
+    Breakpoint(Word),
+    /// Address, old value, new value.
+    Watchpoint(Word, Word, Word),
+    Blocked,
+    Halted,
+    /// The instruction at `pc` can't be executed.
+    Fault(Fault),
+}
+
+pub struct Debugger {
+    pub breakpoints: BTreeSet<Word>,
+    /// Watched addresses, with the value last seen at each.
+    pub watchpoints: BTreeMap<Word, Word>,
+}
+
+fn parse_word(text: Option<&&str>) -> Result<Word, String> {
+    return match text {
+        Some(t) => t.parse().map_err(|_| format!("'{}' is not a number", t)),
+        None => Err("a number is required".to_string()),
+    };
+}
+
+impl Debugger {
+    pub fn new() -> Self {
+        return Self {
+            breakpoints: BTreeSet::new(),
+            watchpoints: BTreeMap::new(),
+        };
+    }
+
+    fn check_watchpoints(self: &mut Self, ms: &MachineState) -> Option<Stop> {
+        for (address, last) in self.watchpoints.iter_mut() {
+            let value = load_memory(ms, *address);
+            if value != *last {
+                let old = *last;
+                *last = value;
+                return Some(Stop::Watchpoint(*address, old, value));
+            }
+        }
+        return None;
+    }
+
+    /// Execute up to `count` instructions, stopping early at a watchpoint,
+    /// or if the program halts, needs input or faults. With `count` zero,
+    /// execute until one of those happens or a breakpoint is reached.
+    pub fn run(self: &mut Self, ms: &mut MachineState, count: u64) -> Stop {
+        let mut executed: u64 = 0;
+        loop {
+            if count != 0 && executed >= count {
+                return Stop::Done;
+            }
+            if count == 0 && executed != 0 && self.breakpoints.contains(&ms.pc) {
+                return Stop::Breakpoint(ms.pc);
+            }
+            match step(ms) {
+                Ok(Status::Running) => {},
+                Ok(Status::Blocked) => { return Stop::Blocked; },
+                Ok(Status::Halted) => { return Stop::Halted; },
+                Err(fault) => { return Stop::Fault(fault); },
+            }
+            executed += 1;
+            if let Some(stop) = self.check_watchpoints(ms) {
+                return stop;
+            }
+        }
+    }
+
+    /// Disassemble about `before` instructions before `address` and `after`
+    /// instructions from `address`, marking the pc and any breakpoints.
+    pub fn list(self: &Self, ms: &MachineState, address: Word,
+                before: usize, after: usize) -> String {
+        // Instruction boundaries are found by decoding from 0, as far as
+        // the size of memory; if address is not on one of them, the listing
+        // just begins at address
+        let mut starts: Vec<Word> = Vec::new();
+        let mut pc = 0;
+        while pc < address && pc < ms.memory.len() as Word {
+            starts.push(pc);
+            disassemble(ms, &mut pc);
+        }
+        if pc != address {
+            starts.clear();
+        }
+        let mut pc = starts[starts.len().saturating_sub(before) ..]
+                        .first().copied().unwrap_or(address);
+        let mut lines: Vec<String> = Vec::new();
+        let mut count = 0;
+        // an instruction that would run past the last address ends it
+        while count < after && pc.checked_add(instruction_length(load_memory(ms, pc))).is_some() {
+            let mut line = String::new();
+            line.push(if pc == ms.pc { '>' } else { ' ' });
+            line.push(if self.breakpoints.contains(&pc) { '*' } else { ' ' });
+            line.push(' ');
+            if pc >= address {
+                count += 1;
+            }
+            line.push_str(&disassemble(ms, &mut pc));
+            lines.push(line);
+        }
+        return lines.join("\n");
+    }
+
+    fn describe(self: &Self, ms: &MachineState, stop: Stop) -> String {
+        let reason = match stop {
+            Stop::Done => String::new(),
+            Stop::Breakpoint(address) => format!("breakpoint at {}\n", address),
+            Stop::Watchpoint(address, old, new) =>
+                format!("watchpoint: [{}] changed from {} to {}\n", address, old, new),
+            Stop::Blocked => "waiting for input: use 'input <value>'\n".to_string(),
+            Stop::Halted => "halted\n".to_string(),
+            Stop::Fault(fault) => format!("fault: {}\n", fault),
+        };
+        return reason + &self.list(ms, ms.pc, 0, 1);
+    }
+
+    fn info(self: &Self, ms: &MachineState) -> String {
+        let mut out = String::new();
+        out.push_str(&format!("pc {} relative base {}\n", ms.pc, ms.relative_base));
+        out.push_str(&format!("input: {}\n", list_words(ms.input.iter())));
+        out.push_str(&format!("output: {}\n", list_words(ms.output.iter())));
+        out.push_str(&format!("breakpoints: {}\n", list_words(self.breakpoints.iter())));
+        out.push_str(&format!("watchpoints: {}", list_words(self.watchpoints.keys())));
+        return out;
+    }
+
+    /// Carry out one command, returning the text to show or an error.
+    pub fn command(self: &mut Self, ms: &mut MachineState, line: &str) -> Result<String, String> {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let name = match tokens.first() {
+            Some(name) => *name,
+            None => { return Ok(String::new()); },
+        };
+        let args = &tokens[1 ..];
+        match name {
+            "s" | "step" => {
+                let count = match args.first() {
+                    Some(_) => parse_word(args.first())?,
+                    None => 1,
+                };
+                if count < 1 {
+                    return Err("the count must be at least 1".to_string());
+                }
+                let stop = self.run(ms, count as u64);
+                return Ok(self.describe(ms, stop));
+            },
+            "c" | "continue" => {
+                let stop = self.run(ms, 0);
+                return Ok(self.describe(ms, stop));
+            },
+            "b" | "break" => {
+                if args.is_empty() {
+                    return Ok(format!("breakpoints: {}", list_words(self.breakpoints.iter())));
+                }
+                let address = parse_word(args.first())?;
+                self.breakpoints.insert(address);
+                return Ok(format!("breakpoint at {}", address));
+            },
+            "d" | "delete" => {
+                let address = parse_word(args.first())?;
+                if !self.breakpoints.remove(&address) {
+                    return Err(format!("no breakpoint at {}", address));
+                }
+                return Ok(String::new());
+            },
+            "w" | "watch" => {
+                if args.is_empty() {
+                    return Ok(format!("watchpoints: {}", list_words(self.watchpoints.keys())));
+                }
+                let address = parse_word(args.first())?;
+                self.watchpoints.insert(address, load_memory(ms, address));
+                return Ok(format!("watching [{}] = {}", address, load_memory(ms, address)));
+            },
+            "unwatch" => {
+                let address = parse_word(args.first())?;
+                if self.watchpoints.remove(&address).is_none() {
+                    return Err(format!("no watchpoint at {}", address));
+                }
+                return Ok(String::new());
+            },
+            "in" | "input" => {
+                if args.is_empty() {
+                    return Err("give one or more values".to_string());
+                }
+                let mut values: Vec<Word> = Vec::new();
+                for arg in args {
+                    values.push(parse_word(Some(arg))?);
+                }
+                ms.input.extend(values);
+                return Ok(format!("input: {}", list_words(ms.input.iter())));
+            },
+            "out" | "output" => {
+                let text = list_words(ms.output.iter());
+                ms.output.clear();
+                return Ok(format!("output: {}", text));
+            },
+            "i" | "info" => {
+                return Ok(self.info(ms));
+            },
+            "l" | "list" => {
+                let address = match args.first() {
+                    Some(_) => parse_word(args.first())?,
+                    None => ms.pc,
+                };
+                return Ok(self.list(ms, address, 5, 6));
+            },
+            "x" | "memory" => {
+                let address = parse_word(args.first())?;
+                let count = match args.get(1) {
+                    Some(_) => parse_word(args.get(1))?,
+                    None => 8,
+                };
+                if count > 0 && address.checked_add(count - 1).is_none() {
+                    return Err(format!("{} words from {} is past the end of memory",
+                                       count, address));
+                }
+                let mut lines: Vec<String> = Vec::new();
+                for a in (0 .. count).map(|i| address + i) {
+                    lines.push(format!("[{}] = {}", a, load_memory(ms, a)));
+                }
+                return Ok(lines.join("\n"));
+            },
+            "set" => {
+                let address = parse_word(args.first())?;
+                let value = parse_word(args.get(1))?;
+                store_memory(ms, address, value);
+                if let Some(last) = self.watchpoints.get_mut(&address) {
+                    *last = value;
+                }
+                return Ok(format!("[{}] = {}", address, value));
+            },
+            "save" => {
+                let filename = args.first().ok_or("a file name is required")?;
+                snapshot::save(ms, filename)?;
+                return Ok(format!("saved to {}", filename));
+            },
+            "restore" => {
+                let filename = args.first().ok_or("a file name is required")?;
+                *ms = snapshot::restore(filename)?;
+                // watchpoints compare against the restored memory from now on
+                for (address, last) in self.watchpoints.iter_mut() {
+                    *last = load_memory(ms, *address);
+                }
+                return Ok(self.describe(ms, Stop::Done));
+            },
+            "h" | "help" => {
+                return Ok(HELP.to_string());
+            },
+            _ => {
+                return Err(format!("unknown command '{}': try 'help'", name));
+            },
+        }
+    }
+}
+
+impl Default for Debugger {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+#[test]
+fn test_debugger() {
+    let source = "
+                in      input -> [x]
+        loop:   add     [x] + -1 -> [x]
+                out     [x] -> output
+                br      if [x] != 0 goto loop
+                halt
+        x:      data    0
+    ";
+    let program = crate::asm::assemble(source).unwrap();
+    let mut ms = crate::load(&crate::asm::program_text(&program));
+    let mut d = Debugger::new();
+
+    assert_eq!(d.command(&mut ms, "break 6").unwrap(), "breakpoint at 6");
+    assert_eq!(d.command(&mut ms, "continue").unwrap(),
+               "waiting for input: use 'input <value>'\n>  0\t3\tin\tinput -> [12]");
+    assert_eq!(d.command(&mut ms, "input 2").unwrap(), "input: 2");
+    assert_eq!(d.command(&mut ms, "c").unwrap(),
+               "breakpoint at 6\n>* 6\t4\tout\t[12] -> output");
+    assert_eq!(d.command(&mut ms, "watch 12").unwrap(), "watching [12] = 1");
+    assert_eq!(d.command(&mut ms, "continue").unwrap(),
+               "watchpoint: [12] changed from 1 to 0\n>* 6\t4\tout\t[12] -> output");
+    assert_eq!(d.command(&mut ms, "step 2").unwrap(),
+               ">  11\t99\thalt\t");
+    assert_eq!(d.command(&mut ms, "info").unwrap(),
+               "pc 11 relative base 0\ninput: \noutput: 1, 0\nbreakpoints: 6\nwatchpoints: 12");
+    assert_eq!(d.command(&mut ms, "list 6").unwrap(),
+               "   0\t3\tin\tinput -> [12]\n   2\t1001\tadd\t[12] + -1 -> [12]\n \
+                * 6\t4\tout\t[12] -> output\n   8\t1005\tbr\tif [12] != 0 goto 2\n\
+                >  11\t99\thalt\t\n   12\t0\t?\t? 0\n   13\t0\t?\t? 0\n   14\t0\t?\t? 0");
+    assert_eq!(d.command(&mut ms, "c").unwrap(), "halted\n>  11\t99\thalt\t");
+    assert_eq!(d.command(&mut ms, "output").unwrap(), "output: 1, 0");
+    assert_eq!(d.command(&mut ms, "memory 11 2").unwrap(), "[11] = 99\n[12] = 0");
+    assert_eq!(d.command(&mut ms, "memory 9223372036854775807 2").unwrap_err(),
+               "2 words from 9223372036854775807 is past the end of memory");
+    assert_eq!(d.command(&mut ms, "memory 9223372036854775807 1").unwrap(),
+               "[9223372036854775807] = 0");
+    assert_eq!(d.command(&mut ms, "list 9000000000000000000").unwrap(),
+               "   9000000000000000000\t0\t?\t? 0\n   9000000000000000001\t0\t?\t? 0\n   \
+                9000000000000000002\t0\t?\t? 0\n   9000000000000000003\t0\t?\t? 0\n   \
+                9000000000000000004\t0\t?\t? 0\n   9000000000000000005\t0\t?\t? 0");
+    assert_eq!(d.command(&mut ms, "list 9223372036854775807").unwrap(), "");
+    assert_eq!(d.command(&mut ms, "restore /nonexistent/file").unwrap_err()
+               .split(':').next().unwrap(), "/nonexistent/file");
+    assert!(d.command(&mut ms, "frobnicate").is_err());
+
+    // a fault stops execution without changing the state
+    assert_eq!(d.command(&mut ms, "set 11 98").unwrap(), "[11] = 98");
+    assert_eq!(d.command(&mut ms, "step").unwrap(),
+               "fault: unknown opcode 98 at 11\n>  11\t98\t?\t? 98");
+    assert_eq!(ms.pc, 11);
+}
@@ -0,0 +1,891 @@
+//! Decompiler for Intcode programs.
+//!
+//! `decompile` turns the code found by `flow::analyse` into C-like
+//! pseudocode, one function at a time. Calls are recognised by the
+//! sequence that compiled Intcode programs use:
+//!
+//! - the caller stores the arguments at `[sp+1]`, `[sp+2]`, ..., stores
+//!   the return address at `[sp+0]`, and jumps to the function (or to an
+//!   address held in memory, which is written as `(*mem[a])(...)`);
+//! - the function starts with `adj sp + n -> sp`, so that its return
+//!   address and arguments are now at `[sp-n]`, `[sp-n+1]`, ...;
+//! - it returns with `adj sp + -n -> sp` and `br if 0 == 0 goto [sp+0]`,
+//!   leaving any result where the first argument was, so that the caller
+//!   finds it at `[sp+1]`.
+//!
+//! Within a function, stack slots are named `arg1`, `arg2`, ... and then
+//! `local1`, `local2`, ...; the slots above the frame, where arguments are
+//! placed for other functions, are `out1`, `out2`, .... Everything else is
+//! `mem[a]`, including the stack of `main`, which has no frame.
+//!
+//! Loops are found from backward branches, and `if` and `else` from
+//! forward branches over a range of instructions. Anything that doesn't
+//! fit those patterns is written with `goto`, so the output describes what
+//! the program does, if not always neatly. Code that modifies itself is
+//! shown as it was loaded.
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use crate::{Word, MachineState, get_parameter_mode, load_memory};
+use crate::flow::{Flow, analyse};
+
+// A call: some stores to the stack, then a jump to a function
+struct Call {
+    // None if the address of the function is read from memory
+    function: Option<Word>,
+    // for each of [sp+1], [sp+2], ..., the instructions that build up its
+    // value, in order
+    args: Vec<Vec<Word>>,
+    // the arguments and the return address
+    stores: Vec<Word>,
+    // the instruction after the call, if all it does is copy the result
+    result: Option<Word>,
+}
+
+struct Function {
+    entry: Word,
+    // the stack adjustment made on entry; 0 for main
+    frame: Word,
+    params: Word,
+    returns_value: bool,
+    // each instruction, with the change to the relative base since the
+    // entry if it is the same on every path
+    body: BTreeMap<Word, Option<Word>>,
+}
+
+impl Function {
+    fn name(self: &Self) -> String {
+        if self.entry == 0 {
+            return "main".to_string();
+        }
+        return format!("f{}", self.entry);
+    }
+
+    // Name of the stack slot at the given offset from the relative base
+    // on entry
+    fn slot(self: &Self, slot: Word) -> String {
+        if self.entry == 0 {
+            // the relative base starts at 0, so this is the address
+            return format!("mem[{}]", slot);
+        }
+        if slot == 0 {
+            return "return_address".to_string();
+        } else if slot < 0 {
+            return format!("frame[{}]", slot);
+        } else if slot <= self.params {
+            return format!("arg{}", slot);
+        } else if slot < self.frame {
+            return format!("local{}", slot - self.params);
+        }
+        return format!("out{}", slot - self.frame);
+    }
+}
+
+enum Test {
+    Forever,
+    Before(String),
+    After(String),
+}
+
+// Statements begin with the address of their first instruction
+enum Stmt {
+    Line(Word, String),
+    // a branch: the conditions for taking and not taking it, and where to
+    Jump(Word, Option<(String, String)>, String),
+    If(Word, String, Vec<Stmt>, Vec<Stmt>),
+    Loop(Word, Test, Vec<Stmt>),
+}
+
+impl Stmt {
+    fn start(self: &Self) -> Word {
+        return match self {
+            Stmt::Line(start, _) | Stmt::Jump(start, _, _) |
+            Stmt::If(start, _, _, _) | Stmt::Loop(start, _, _) => *start,
+        };
+    }
+}
+
+fn opcode(ms: &MachineState, pc: Word) -> Word {
+    return load_memory(ms, pc) % 100;
+}
+
+fn mode(ms: &MachineState, pc: Word, index: Word) -> Word {
+    return get_parameter_mode(load_memory(ms, pc), index);
+}
+
+fn parameter(ms: &MachineState, pc: Word, index: Word) -> Word {
+    return load_memory(ms, pc + index);
+}
+
+fn is_branch(ms: &MachineState, pc: Word) -> bool {
+    return matches!(opcode(ms, pc), 5 | 6);
+}
+
+// Whether a branch is always taken (Some(true)), never taken (Some(false))
+// or depends on a value (None)
+fn always_taken(ms: &MachineState, pc: Word) -> Option<bool> {
+    if mode(ms, pc, 1) != 1 {
+        return None;
+    }
+    return Some((opcode(ms, pc) == 5) == (parameter(ms, pc, 1) != 0));
+}
+
+fn direct_target(ms: &MachineState, pc: Word) -> Option<Word> {
+    if is_branch(ms, pc) && mode(ms, pc, 2) == 1 {
+        return Some(parameter(ms, pc, 2));
+    }
+    return None;
+}
+
+// True if parameter 1 or 2 is [sp+k]
+fn reads_slot(ms: &MachineState, pc: Word, k: Word) -> bool {
+    return (1 .. 3).any(|index| mode(ms, pc, index) == 2 && parameter(ms, pc, index) == k);
+}
+
+// The offset of [sp+k] written by an arithmetic instruction that reads
+// nothing above the relative base, apart from [sp+k] itself
+fn stack_store(ms: &MachineState, pc: Word) -> Option<Word> {
+    if !matches!(opcode(ms, pc), 1 | 2 | 7 | 8) || mode(ms, pc, 3) != 2 {
+        return None;
+    }
+    let k = parameter(ms, pc, 3);
+    for index in 1 .. 3 {
+        let j = parameter(ms, pc, index);
+        if mode(ms, pc, index) == 2 && j > 0 && j != k {
+            return None;
+        }
+    }
+    return Some(k);
+}
+
+fn constant(ms: &MachineState, pc: Word) -> Option<Word> {
+    if mode(ms, pc, 1) != 1 || mode(ms, pc, 2) != 1 {
+        return None;
+    }
+    let a = parameter(ms, pc, 1);
+    let b = parameter(ms, pc, 2);
+    return match opcode(ms, pc) {
+        1 => a.checked_add(b),
+        2 => a.checked_mul(b),
+        _ => None,
+    };
+}
+
+// True for an instruction that copies [sp+1] to somewhere else
+fn copies_result(ms: &MachineState, pc: Word) -> bool {
+    let source = |index| mode(ms, pc, index) == 2 && parameter(ms, pc, index) == 1;
+    let unit = |index, value| mode(ms, pc, index) == 1 && parameter(ms, pc, index) == value;
+    let identity = match opcode(ms, pc) {
+        1 => 0,
+        2 => 1,
+        _ => { return false; },
+    };
+    return mode(ms, pc, 3) != 1 &&
+        ((source(1) && unit(2, identity)) || (unit(1, identity) && source(2)));
+}
+
+fn is_prologue(ms: &MachineState, flow: &Flow, pc: Word) -> bool {
+    return flow.instructions.contains_key(&pc) && opcode(ms, pc) == 9 &&
+        mode(ms, pc, 1) == 1 && parameter(ms, pc, 1) > 0;
+}
+
+// The instruction ending just before `pc`
+fn previous(flow: &Flow, pc: Word) -> Option<Word> {
+    let (start, info) = flow.instructions.range(.. pc).next_back()?;
+    if start + info.length == pc {
+        return Some(*start);
+    }
+    return None;
+}
+
+fn find_call(ms: &MachineState, flow: &Flow, pc: Word) -> Option<Call> {
+    if !is_branch(ms, pc) || always_taken(ms, pc) != Some(true) || flow.blocks.contains_key(&pc) {
+        return None;
+    }
+    let function = match direct_target(ms, pc) {
+        Some(f) if f != 0 && is_prologue(ms, flow, f) => Some(f),
+        None if mode(ms, pc, 2) == 0 => None,
+        _ => { return None; },
+    };
+    let return_address = pc + flow.instructions[&pc].length;
+
+    // stores to the stack just before the jump, within the same block
+    let mut run: Vec<(Word, Word)> = Vec::new();
+    let mut current = pc;
+    while !flow.blocks.contains_key(&current) {
+        let store = match previous(flow, current) {
+            Some(store) => store,
+            None => { break; },
+        };
+        match stack_store(ms, store) {
+            Some(k) if k > 0 || (k == 0 && run.iter().all(|(j, _)| *j != 0)) => {
+                run.push((k, store));
+            },
+            _ => { break; },
+        }
+        current = store;
+    }
+    run.reverse();
+    let mut steps: BTreeMap<Word, Vec<Word>> = BTreeMap::new();
+    for (k, store) in &run {
+        steps.entry(*k).or_default().push(*store);
+    }
+    match steps.get(&0) {
+        Some(stores) if constant(ms, stores[0]) == Some(return_address) => {},
+        _ => { return None; },
+    }
+    for (i, (k, stores)) in steps.iter().enumerate() {
+        // each slot is [sp+0] to [sp+n], and its first store must not
+        // depend on an earlier value
+        if *k != i as Word || reads_slot(ms, stores[0], *k) {
+            return None;
+        }
+    }
+
+    // the result can be folded into the call if nothing else goes there
+    let mut result: Option<Word> = None;
+    if flow.instructions.contains_key(&return_address) && copies_result(ms, return_address) {
+        let elsewhere = flow.instructions.iter().any(|(p, info)| {
+            *p != pc && info.successors.contains(&return_address) &&
+                (p + info.length == return_address ||
+                 direct_target(ms, *p) == Some(return_address))
+        });
+        if !elsewhere {
+            result = Some(return_address);
+        }
+    }
+    return Some(Call {
+        function: function,
+        args: steps.into_values().skip(1).collect(),
+        stores: run.iter().map(|(_, store)| *store).collect(),
+        result: result,
+    });
+}
+
+struct Decompiler<'a> {
+    ms: &'a MachineState,
+    flow: &'a Flow,
+    calls: BTreeMap<Word, Call>,
+    functions: BTreeMap<Word, Function>,
+}
+
+impl Decompiler<'_> {
+    fn length(self: &Self, pc: Word) -> Word {
+        return self.flow.instructions[&pc].length;
+    }
+
+    fn is_return(self: &Self, entry: Word, pc: Word, delta: Option<Word>) -> bool {
+        let ms = self.ms;
+        return entry != 0 && is_branch(ms, pc) && always_taken(ms, pc) == Some(true) &&
+            mode(ms, pc, 2) == 2 && parameter(ms, pc, 2).checked_neg().is_some_and(|p| delta == Some(p));
+    }
+
+    // Where the function starting at `entry` may go after `pc`, with the
+    // change to the relative base
+    fn successors(self: &Self, entry: Word, pc: Word,
+                  delta: Option<Word>) -> Vec<(Word, Option<Word>)> {
+        let ms = self.ms;
+        let next = pc + self.length(pc);
+        if self.calls.contains_key(&pc) {
+            return vec![(next, delta)];
+        }
+        if self.is_return(entry, pc, delta) {
+            return Vec::new();
+        }
+        let mut targets: Vec<Word> = self.flow.instructions[&pc].successors.iter()
+                                        .copied().collect();
+        if entry != 0 && is_branch(ms, pc) && direct_target(ms, pc).is_none() {
+            // the addresses found on the stack belong to the callers
+            targets.retain(|t| *t == next && always_taken(ms, pc) != Some(true));
+        }
+        let mut after = delta;
+        if opcode(ms, pc) == 9 {
+            after = match mode(ms, pc, 1) {
+                1 => delta.and_then(|d| d.checked_add(parameter(ms, pc, 1))),
+                _ => None,
+            };
+        }
+        return targets.into_iter().map(|t| (t, after)).collect();
+    }
+
+    fn find_body(self: &Self, entry: Word) -> BTreeMap<Word, Option<Word>> {
+        let mut body: BTreeMap<Word, Option<Word>> = BTreeMap::new();
+        let mut todo: Vec<(Word, Option<Word>)> = vec![(entry, Some(0))];
+        while let Some((pc, delta)) = todo.pop() {
+            if !self.flow.instructions.contains_key(&pc) {
+                continue;
+            }
+            let delta = match body.get(&pc) {
+                None => delta,
+                Some(known) if *known == delta || known.is_none() => { continue; },
+                Some(_) => None,
+            };
+            body.insert(pc, delta);
+            todo.extend(self.successors(entry, pc, delta));
+        }
+        return body;
+    }
+}
+
+// Decompiling one function
+struct Writer<'a> {
+    d: &'a Decompiler<'a>,
+    f: &'a Function,
+    // instructions that are part of another statement
+    folded: HashSet<Word>,
+    // statements that begin before their main instruction
+    starts: HashMap<Word, Word>,
+    // the number of branches to each address, not counting falling through
+    entries: HashMap<Word, usize>,
+    labels: BTreeSet<Word>,
+}
+
+impl Writer<'_> {
+    fn new<'a>(d: &'a Decompiler<'a>, f: &'a Function) -> Writer<'a> {
+        let mut w = Writer {
+            d: d,
+            f: f,
+            folded: HashSet::new(),
+            starts: HashMap::new(),
+            entries: HashMap::new(),
+            labels: BTreeSet::new(),
+        };
+        for (pc, delta) in &f.body {
+            let next = pc + d.length(*pc);
+            for (s, _) in d.successors(f.entry, *pc, *delta) {
+                if s != next {
+                    *w.entries.entry(s).or_default() += 1;
+                }
+            }
+        }
+        if f.entry != 0 && !w.entries.contains_key(&f.entry) &&
+                f.body.keys().next() == Some(&f.entry) {
+            w.folded.insert(f.entry);
+        }
+        for (pc, delta) in &f.body {
+            if let Some(call) = d.calls.get(pc) {
+                w.folded.extend(call.stores.iter().copied());
+                w.folded.extend(call.result);
+                w.starts.insert(*pc, *call.stores.iter().min().unwrap());
+            } else if d.is_return(f.entry, *pc, *delta) && !w.entries.contains_key(pc) {
+                match previous(d.flow, *pc) {
+                    Some(adj) if f.body.contains_key(&adj) && opcode(d.ms, adj) == 9 &&
+                                 mode(d.ms, adj, 1) == 1 => {
+                        w.folded.insert(adj);
+                        w.starts.insert(*pc, adj);
+                    },
+                    _ => {},
+                }
+            }
+        }
+        return w;
+    }
+
+    fn operand(self: &Self, pc: Word, index: Word) -> String {
+        let value = parameter(self.d.ms, pc, index);
+        return match mode(self.d.ms, pc, index) {
+            0 => format!("mem[{}]", value),
+            1 => value.to_string(),
+            2 => match self.f.body[&pc].and_then(|delta| value.checked_add(delta)) {
+                Some(slot) => self.f.slot(slot),
+                None => format!("stack[sp{:+}]", value),
+            },
+            _ => format!("?{}", value),
+        };
+    }
+
+    // The value computed by an arithmetic or comparison instruction
+    fn value(self: &Self, pc: Word) -> String {
+        return self.combine(pc, self.operand(pc, 1), self.operand(pc, 2));
+    }
+
+    fn combine(self: &Self, pc: Word, a: String, b: String) -> String {
+        let negate = |x: String| match x.strip_prefix('-') {
+            Some(x) => x.to_string(),
+            None => format!("-{}", x),
+        };
+        return match opcode(self.d.ms, pc) {
+            1 if a == "0" => b,
+            1 if b == "0" => a,
+            1 if b.starts_with('-') => format!("{} - {}", a, &b[1 ..]),
+            1 => format!("{} + {}", a, b),
+            2 if a == "1" => b,
+            2 if b == "1" => a,
+            2 if a == "-1" => negate(b),
+            2 if b == "-1" => negate(a),
+            2 => format!("{} * {}", a, b),
+            7 => format!("{} < {}", a, b),
+            _ => format!("{} == {}", a, b),
+        };
+    }
+
+    // The value of an argument built up by a series of instructions, each
+    // of which may use the value stored by the one before
+    fn argument(self: &Self, steps: &[Word]) -> String {
+        let ms = self.d.ms;
+        let mut text = String::new();
+        for step in steps {
+            let k = parameter(ms, *step, 3);
+            let operand = |index| {
+                if text.is_empty() || mode(ms, *step, index) != 2 ||
+                        parameter(ms, *step, index) != k {
+                    return self.operand(*step, index);
+                } else if text.contains(' ') {
+                    return format!("({})", text);
+                }
+                return text.clone();
+            };
+            text = self.combine(*step, operand(1), operand(2));
+        }
+        return text;
+    }
+
+    // Conditions for taking and not taking a branch
+    fn condition(self: &Self, pc: Word) -> (String, String) {
+        let a = self.operand(pc, 1);
+        let (nonzero, zero) = (format!("{} != 0", a), format!("{} == 0", a));
+        if opcode(self.d.ms, pc) == 5 {
+            return (nonzero, zero);
+        }
+        return (zero, nonzero);
+    }
+
+    fn jump(self: &mut Self, target: Word, loops: &[(Option<Word>, Word)]) -> String {
+        if let Some((again, exit)) = loops.last() {
+            if *again == Some(target) {
+                return "continue".to_string();
+            }
+            if *exit == target {
+                return "break".to_string();
+            }
+        }
+        self.labels.insert(target);
+        return format!("goto L{}", target);
+    }
+
+    fn is_return(self: &Self, pc: Word) -> bool {
+        return self.d.is_return(self.f.entry, pc, self.f.body[&pc]);
+    }
+
+    // A branch that isn't a call or return, and isn't just skipped
+    fn is_jump(self: &Self, pc: Word) -> bool {
+        let ms = self.d.ms;
+        return is_branch(ms, pc) && always_taken(ms, pc) != Some(false) &&
+            !self.d.calls.contains_key(&pc) && !self.is_return(pc);
+    }
+
+    fn statement(self: &mut Self, pc: Word, loops: &[(Option<Word>, Word)]) -> Stmt {
+        let ms = self.d.ms;
+        let start = *self.starts.get(&pc).unwrap_or(&pc);
+        if let Some(call) = self.d.calls.get(&pc) {
+            let args: Vec<String> = call.args.iter().map(|a| self.argument(a)).collect();
+            let function = match call.function {
+                Some(entry) => self.d.functions[&entry].name(),
+                None => format!("(*{})", self.operand(pc, 2)),
+            };
+            let text = format!("{}({})", function, args.join(", "));
+            return Stmt::Line(start, match call.result {
+                Some(result) => format!("{} = {};", self.operand(result, 3), text),
+                None => format!("{};", text),
+            });
+        }
+        if self.is_return(pc) {
+            if self.f.returns_value {
+                return Stmt::Line(start, format!("return {};", self.f.slot(1)));
+            }
+            return Stmt::Line(start, "return;".to_string());
+        }
+        let text = match opcode(ms, pc) {
+            1 | 2 | 7 | 8 => format!("{} = {};", self.operand(pc, 3), self.value(pc)),
+            3 => format!("{} = input();", self.operand(pc, 1)),
+            4 => format!("output({});", self.operand(pc, 1)),
+            5 | 6 => {
+                let taken = always_taken(ms, pc);
+                if taken == Some(false) {
+                    return Stmt::Line(start, String::new());
+                }
+                let jump = match direct_target(ms, pc) {
+                    Some(target) => self.jump(target, loops),
+                    None => format!("goto *{}", self.operand(pc, 2)),
+                };
+                let condition = match taken {
+                    Some(_) => None,
+                    None => Some(self.condition(pc)),
+                };
+                return Stmt::Jump(start, condition, jump);
+            },
+            9 => {
+                let amount = self.operand(pc, 1);
+                match amount.strip_prefix('-') {
+                    Some(amount) => format!("sp -= {};", amount),
+                    None => format!("sp += {};", amount),
+                }
+            },
+            99 => "halt;".to_string(),
+            _ => format!("invalid({});", load_memory(ms, pc)),
+        };
+        return Stmt::Line(start, text);
+    }
+
+    // If the instruction at `pc` can continue to the next instruction, but
+    // that isn't what comes next in the output, add a goto
+    fn fall_through(self: &mut Self, pc: Word, out: &mut Vec<Stmt>,
+                    loops: &[(Option<Word>, Word)]) {
+        let next = pc + self.d.length(pc);
+        let following = self.f.body.range(pc + 1 ..).next().map(|(a, _)| *a);
+        let continues = self.d.successors(self.f.entry, pc, self.f.body[&pc]).iter()
+                            .any(|(s, _)| *s == next);
+        if continues && following != Some(next) && self.f.body.contains_key(&next) {
+            let jump = self.jump(next, loops);
+            out.push(Stmt::Jump(pc, None, jump));
+        }
+    }
+
+    // Structure the instructions from `start` up to `end`. `loops` has
+    // the targets of continue (if any) and break for the enclosing loops.
+    fn structure(self: &mut Self, start: Word, end: Word,
+                 loops: &mut Vec<(Option<Word>, Word)>) -> Vec<Stmt> {
+        let ms = self.d.ms;
+        let mut out: Vec<Stmt> = Vec::new();
+        let mut position = start;
+        while let Some(pc) = self.f.body.range(position .. end).next().map(|(a, _)| *a) {
+            position = pc + 1;
+            if self.folded.contains(&pc) {
+                continue;
+            }
+            let next = pc + self.d.length(pc);
+
+            // a loop runs from here to the last branch back here
+            let back = self.f.body.range(pc .. end).map(|(b, _)| *b).rfind(|b| {
+                self.is_jump(*b) && direct_target(ms, *b) == Some(pc) &&
+                    b + self.d.length(*b) <= end && !self.entries.contains_key(b)
+            });
+            if let Some(back) = back {
+                let forever = always_taken(ms, back) == Some(true);
+                let exit = back + self.d.length(back);
+                loops.push((if forever { Some(pc) } else { None }, exit));
+                let mut body = self.structure(pc, back, loops);
+                loops.pop();
+                let test = if forever {
+                    // while (true) { if (x) break; ... } is while (!x) { ... }
+                    match body.first() {
+                        Some(Stmt::Jump(_, Some((_, negated)), jump)) if jump == "break" => {
+                            let test = Test::Before(negated.clone());
+                            body.remove(0);
+                            test
+                        },
+                        _ => Test::Forever,
+                    }
+                } else {
+                    Test::After(self.condition(back).0)
+                };
+                out.push(Stmt::Loop(pc, test, body));
+                if !forever {
+                    self.fall_through(back, &mut out, loops);
+                }
+                position = back + 1;
+                continue;
+            }
+
+            // a conditional branch forward is an if statement, and an
+            // else if the skipped code ends by jumping forward again
+            let following = self.f.body.range(pc + 1 ..).next().map(|(a, _)| *a);
+            if self.is_jump(pc) && always_taken(ms, pc).is_none() && following == Some(next) {
+                if let Some(target) = direct_target(ms, pc) {
+                    if next < target && target <= end {
+                        let last = self.f.body.range(next .. target).next_back().map(|(a, _)| *a);
+                        let join = last.and_then(|last| {
+                            if !self.is_jump(last) || always_taken(ms, last) != Some(true) ||
+                                    self.entries.contains_key(&last) {
+                                return None;
+                            }
+                            return direct_target(ms, last).filter(|j| target <= *j && *j <= end)
+                                    .map(|j| (last, j));
+                        });
+                        let (condition, negated) = self.condition(pc);
+                        let (then, otherwise) = match join {
+                            Some((last, join)) => {
+                                position = join;
+                                (self.structure(next, last, loops),
+                                 self.structure(target, join, loops))
+                            },
+                            None => {
+                                position = target;
+                                (self.structure(next, target, loops), Vec::new())
+                            },
+                        };
+                        if then.is_empty() && !otherwise.is_empty() {
+                            out.push(Stmt::If(pc, condition, otherwise, then));
+                        } else {
+                            out.push(Stmt::If(pc, negated, then, otherwise));
+                        }
+                        continue;
+                    }
+                }
+            }
+
+            let stmt = self.statement(pc, loops);
+            out.push(stmt);
+            let last = match self.d.calls.get(&pc) {
+                Some(Call { result: Some(result), .. }) => *result,
+                _ => pc,
+            };
+            self.fall_through(last, &mut out, loops);
+        }
+        return out;
+    }
+
+    fn print(self: &Self, stmts: &[Stmt], depth: usize, out: &mut String,
+             labelled: &mut HashSet<Word>) {
+        let indent = "    ".repeat(depth);
+        for stmt in stmts {
+            let start = stmt.start();
+            let label = self.labels.contains(&start) && labelled.insert(start);
+            if label {
+                out.push_str(&format!("{}L{}:\n", "    ".repeat(depth - 1), start));
+            }
+            match stmt {
+                Stmt::Line(_, text) => {
+                    if !text.is_empty() {
+                        out.push_str(&format!("{}{}\n", indent, text));
+                    } else if label {
+                        out.push_str(&format!("{};\n", indent));
+                    }
+                },
+                Stmt::Jump(_, Some((condition, _)), jump) => {
+                    out.push_str(&format!("{}if ({}) {};\n", indent, condition, jump));
+                },
+                Stmt::Jump(_, None, jump) => {
+                    out.push_str(&format!("{}{};\n", indent, jump));
+                },
+                Stmt::If(_, condition, then, otherwise) => {
+                    out.push_str(&format!("{}if ({}) {{\n", indent, condition));
+                    self.print(then, depth + 1, out, labelled);
+                    if !otherwise.is_empty() {
+                        out.push_str(&format!("{}}} else {{\n", indent));
+                        self.print(otherwise, depth + 1, out, labelled);
+                    }
+                    out.push_str(&format!("{}}}\n", indent));
+                },
+                Stmt::Loop(_, test, body) => {
+                    out.push_str(&match test {
+                        Test::Forever => format!("{}while (true) {{\n", indent),
+                        Test::Before(condition) => format!("{}while ({}) {{\n", indent, condition),
+                        Test::After(_) => format!("{}do {{\n", indent),
+                    });
+                    self.print(body, depth + 1, out, labelled);
+                    out.push_str(&match test {
+                        Test::After(condition) => format!("{}}} while ({});\n", indent, condition),
+                        _ => format!("{}}}\n", indent),
+                    });
+                },
+            }
+        }
+    }
+
+    fn write(self: &mut Self) -> String {
+        let f = self.f;
+        // the function may use code before its entry point
+        let first = *f.body.keys().next().unwrap();
+        let mut body = self.structure(first, Word::MAX, &mut Vec::new());
+        if first != f.entry {
+            let jump = self.jump(f.entry, &[]);
+            body.insert(0, Stmt::Jump(Word::MIN, None, jump));
+        }
+        let params: Vec<String> = (1 ..= f.params).map(|i| f.slot(i)).collect();
+        let mut out = format!("function {}({}) {{\n", f.name(), params.join(", "));
+        if f.frame > f.params + 1 {
+            let locals: Vec<String> = (f.params + 1 .. f.frame).map(|i| f.slot(i)).collect();
+            out.push_str(&format!("    var {};\n", locals.join(", ")));
+        }
+        self.print(&body, 1, &mut out, &mut HashSet::new());
+        out.push_str("}\n");
+        return out;
+    }
+}
+
+/// Decompile the program in the words from 0 up to `endpoint`, starting
+/// at address 0. That is `main`; each function it calls is written after
+/// it, named after its address.
+pub fn decompile(ms: &MachineState, endpoint: Word) -> String {
+    // Flow analysis can't always follow a return (for instance from a
+    // recursive function), so the code after each call is analysed as
+    // well, until no more calls are found
+    let mut entry_points: Vec<Word> = vec![0];
+    let (flow, calls) = loop {
+        let flow = analyse(ms, endpoint, &entry_points);
+        let mut calls: BTreeMap<Word, Call> = BTreeMap::new();
+        for pc in flow.instructions.keys() {
+            if let Some(call) = find_call(ms, &flow, *pc) {
+                calls.insert(*pc, call);
+            }
+        }
+        let count = entry_points.len();
+        for pc in calls.keys() {
+            let return_address = pc + flow.instructions[pc].length;
+            // analysis can't reach a return address at or after the endpoint
+            if return_address < endpoint && !entry_points.contains(&return_address)
+                    && !flow.instructions.contains_key(&return_address) {
+                entry_points.push(return_address);
+            }
+        }
+        if entry_points.len() == count {
+            break (flow, calls);
+        }
+    };
+    let mut d = Decompiler {
+        ms: ms,
+        flow: &flow,
+        calls: calls,
+        functions: BTreeMap::new(),
+    };
+
+    let mut entries: BTreeSet<Word> = d.calls.values().filter_map(|c| c.function).collect();
+    if d.flow.instructions.contains_key(&0) {
+        entries.insert(0);
+    }
+    for entry in entries {
+        let function = Function {
+            entry: entry,
+            frame: if entry == 0 { 0 } else { parameter(ms, entry, 1) },
+            params: 0,
+            returns_value: false,
+            body: d.find_body(entry),
+        };
+        d.functions.insert(entry, function);
+    }
+    for call in d.calls.values() {
+        let f = match call.function {
+            Some(entry) => d.functions.get_mut(&entry).unwrap(),
+            None => { continue; },
+        };
+        f.params = Word::max(f.params, Word::min(call.args.len() as Word, f.frame - 1));
+        f.returns_value |= call.result.is_some();
+    }
+
+    let mut texts: Vec<String> = Vec::new();
+    for f in d.functions.values() {
+        texts.push(Writer::new(&d, f).write());
+    }
+    return texts.join("\n");
+}
+
+#[test]
+fn test_decompile() {
+    let source = "
+                adj     sp + 100 -> sp
+        next:   in      input -> [value]
+                br      if [value] == 0 goto done
+                add     [value] + 0 -> [sp+1]
+                add     back + 0 -> [sp+0]
+                br      if 1 != 0 goto tri
+        back:   add     0 + [sp+1] -> [value]
+                out     [value] -> output
+                br      if 0 == 0 goto next
+        done:   halt
+        value:  data    0
+
+        # 1 + 2 + ... + n, or -1 if n is negative
+        tri:    adj     sp + 4 -> sp
+                cmp     [sp-3] < 0 -> [sp-1]
+                br      if [sp-1] == 0 goto sum
+                add     -1 + 0 -> [sp-3]
+                br      if 1 != 0 goto return
+        sum:    add     0 + 0 -> [sp-2]
+        loop:   br      if [sp-3] == 0 goto end
+                add     [sp-2] + [sp-3] -> [sp-2]
+                add     [sp-3] + -1 -> [sp-3]
+                br      if 0 == 0 goto loop
+        end:    add     [sp-2] + 0 -> [sp-3]
+        return: adj     sp + -4 -> sp
+                br      if 0 == 0 goto [sp+0]
+    ";
+    let program = crate::asm::assemble(source).unwrap();
+    let mut ms = crate::load(&crate::asm::program_text(&program));
+    assert_eq!(decompile(&ms, ms.memory.len() as Word), "\
+function main() {
+    sp += 100;
+    while (true) {
+        mem[28] = input();
+        if (mem[28] == 0) break;
+        mem[28] = f29(mem[28]);
+        output(mem[28]);
+    }
+    halt;
+}
+
+function f29(arg1) {
+    var local1, local2;
+    local2 = arg1 < 0;
+    if (local2 != 0) {
+        arg1 = -1;
+    } else {
+        local1 = 0;
+        while (arg1 != 0) {
+            local1 = local1 + arg1;
+            arg1 = arg1 - 1;
+        }
+        arg1 = local1;
+    }
+    return arg1;
+}
+");
+    for n in [4, -3, 0] {
+        ms.push_input(n);
+    }
+    assert_eq!(crate::run(&mut ms), Ok(Some(109)));
+    assert_eq!(Vec::from(ms.output.clone()), vec![10, -1]);
+
+    // "c" can't be the end of a loop, because it is also a branch target
+    let source = "
+        a:      in      input -> [x]
+        b:      br      if [x] != 0 goto c
+                out     1 -> output
+                br      if [x] == 0 goto b
+        c:      br      if 1 != 0 goto a
+        x:      data    0
+    ";
+    let program = crate::asm::assemble(source).unwrap();
+    let ms = crate::load(&crate::asm::program_text(&program));
+    assert_eq!(decompile(&ms, ms.memory.len() as Word), "\
+function main() {
+L0:
+    mem[13] = input();
+    do {
+        if (mem[13] != 0) break;
+        output(1);
+    } while (mem[13] == 0);
+    goto L0;
+}
+");
+}
+
+#[test]
+fn test_decompile_inputs() {
+    // every goto has a label
+    for day in ["2", "5", "7", "9", "11", "13", "15"] {
+        let ms = crate::load_from_input(&format!("../{}/input", day));
+        let text = decompile(&ms, ms.memory.len() as Word);
+        assert!(text.starts_with("function main() {\n"));
+        for (i, _) in text.match_indices("goto L") {
+            let label: String = text[i + 5 ..].chars().take_while(|c| *c != ';').collect();
+            assert!(text.contains(&format!("\n{}:\n", label)) ||
+                    text.contains(&format!(" {}:\n", label)), "{}: no {}", day, label);
+        }
+    }
+}
+
+#[test]
+fn test_decompile_edges() {
+    // the call's return address is the endpoint, so it can't be analysed
+    let ms = crate::load("1105,1,5,109,1,21101,12,0,0,1105,1,3");
+    let text = decompile(&ms, 12);
+    assert!(text.starts_with("function main() {\n    goto L5;\nL5:\n    f3();\n}\n"));
+
+    // an adjustment that overflows leaves the base unknown
+    let ms = crate::load("109,9223372036854775807,109,1,99");
+    let text = decompile(&ms, 5);
+    assert!(text.contains("\n    sp += 9223372036854775807;\n    sp += 1;\n    halt;\n"));
+}
@@ -0,0 +1,198 @@
+//! Disassembler for Intcode programs.
+//!
+//! Each instruction becomes one line such as `2\t1005\tbr\tif [8] != 0 goto 320`:
+//! the address, the opcode, the mnemonic and the operands. Operands are
+//! written as `[a]` (position mode), `a` (immediate mode) or `[sp+a]`
+//! (relative mode). Anything that can't be decoded is written with a `?`,
+//! but the original value is always kept, so that `asm::assemble` can turn
+//! the output back into the original program.
+
+use std::collections::HashMap;
+use crate::{Word, MachineState, get_parameter_mode, load_memory};
+
+fn load_parameter(out: &mut String, ms: &MachineState, pc: Word, index: Word) {
+    let opcode = load_memory(ms, pc);
+    let parameter = load_memory(ms, pc + index);
+
+    match get_parameter_mode(opcode, index) {
+        0 => {
+            out.push('[');
+            out.push_str(&parameter.to_string());
+            out.push(']');
+        },
+        1 => {
+            out.push_str(&parameter.to_string());
+        },
+        2 => {
+            out.push_str("[sp");
+            if parameter >= 0 {
+                out.push('+');
+            }
+            out.push_str(&parameter.to_string());
+            out.push(']');
+        },
+        _ => {
+            // invalid mode: keep the value so that it can be reassembled
+            out.push('?');
+            out.push_str(&parameter.to_string());
+        },
+    }
+}
+
+fn store_parameter(out: &mut String, ms: &MachineState, pc: Word, index: Word) {
+    let opcode = load_memory(ms, pc);
+    if get_parameter_mode(opcode, index) == 1 {
+        // illegal for store
+        out.push('?');
+        out.push_str(&load_memory(ms, pc + index).to_string());
+    } else {
+        load_parameter(out, ms, pc, index);
+    }
+}
+
+fn target_parameter(out: &mut String, ms: &MachineState, pc: Word, index: Word,
+                    labels: &HashMap<Word, String>) {
+    let opcode = load_memory(ms, pc);
+    let parameter = load_memory(ms, pc + index);
+    match labels.get(&parameter) {
+        Some(name) if get_parameter_mode(opcode, index) == 1 => {
+            out.push_str(name);
+        },
+        _ => {
+            load_parameter(out, ms, pc, index);
+        },
+    }
+}
+
+fn unknown(out: &mut String, value: Word) {
+    out.push_str("?\t? ");
+    out.push_str(&value.to_string());
+}
+
+/// Number of words used by the instruction with the given opcode.
+/// Unknown opcodes are treated as a single data word.
+pub fn instruction_length(opcode: Word) -> Word {
+    return match opcode % 100 {
+        1 | 2 | 7 | 8 => 4,
+        5 | 6 => 3,
+        3 | 4 | 9 => 2,
+        _ => 1,
+    };
+}
+
+/// Disassemble the instruction at `pc`, returning one line of text
+/// (address, opcode, mnemonic, operands) and advancing `pc` past it.
+pub fn disassemble(ms: &MachineState, pc: &mut Word) -> String {
+    return disassemble_labelled(ms, pc, &HashMap::new());
+}
+
+/// As `disassemble`, but branch targets found in `labels` are
+/// written as label names rather than addresses.
+pub fn disassemble_labelled(ms: &MachineState, pc: &mut Word,
+                            labels: &HashMap<Word, String>) -> String {
+    let opcode = load_memory(ms, *pc);
+    let mut out = pc.to_string();
+    out.push('\t');
+    out.push_str(&opcode.to_string());
+    out.push('\t');
+    match opcode % 100 {
+        1 => {
+            out.push_str("add\t");
+            load_parameter(&mut out, ms, *pc, 1);
+            out.push_str(" + ");
+            load_parameter(&mut out, ms, *pc, 2);
+            out.push_str(" -> ");
+            store_parameter(&mut out, ms, *pc, 3);
+            *pc += 4;
+        },
+        2 => {
+            out.push_str("mul\t");
+            load_parameter(&mut out, ms, *pc, 1);
+            out.push_str(" * ");
+            load_parameter(&mut out, ms, *pc, 2);
+            out.push_str(" -> ");
+            store_parameter(&mut out, ms, *pc, 3);
+            *pc += 4;
+        },
+        3 => {
+            out.push_str("in\tinput -> ");
+            store_parameter(&mut out, ms, *pc, 1);
+            *pc += 2;
+        },
+        4 => {
+            out.push_str("out\t");
+            load_parameter(&mut out, ms, *pc, 1);
+            out.push_str(" -> output");
+            *pc += 2;
+        },
+        5 => {
+            out.push_str("br\tif ");
+            load_parameter(&mut out, ms, *pc, 1);
+            out.push_str(" != 0 goto ");
+            target_parameter(&mut out, ms, *pc, 2, labels);
+            *pc += 3;
+        },
+        6 => {
+            out.push_str("br\tif ");
+            load_parameter(&mut out, ms, *pc, 1);
+            out.push_str(" == 0 goto ");
+            target_parameter(&mut out, ms, *pc, 2, labels);
+            *pc += 3;
+        },
+        7 => {
+            out.push_str("cmp\t");
+            load_parameter(&mut out, ms, *pc, 1);
+            out.push_str(" < ");
+            load_parameter(&mut out, ms, *pc, 2);
+            out.push_str(" -> ");
+            store_parameter(&mut out, ms, *pc, 3);
+            *pc += 4;
+        },
+        8 => {
+            out.push_str("cmp\t");
+            load_parameter(&mut out, ms, *pc, 1);
+            out.push_str(" == ");
+            load_parameter(&mut out, ms, *pc, 2);
+            out.push_str(" -> ");
+            store_parameter(&mut out, ms, *pc, 3);
+            *pc += 4;
+        },
+        9 => {
+            out.push_str("adj\tsp + ");
+            load_parameter(&mut out, ms, *pc, 1);
+            out.push_str(" -> sp");
+            *pc += 2;
+        },
+        99 => {
+            out.push_str("halt\t");
+            *pc += 1;
+        },
+        _ => {
+            unknown(&mut out, opcode);
+            *pc += 1;
+        }
+    }
+    return out;
+}
+
+/// Disassemble the words from 0 up to `endpoint`, one line per instruction.
+/// An instruction that would run past `endpoint` is written as data words.
+pub fn disassemble_program(ms: &MachineState, endpoint: Word) -> String {
+    let mut out = String::new();
+    let mut pc = 0;
+    while pc < endpoint {
+        let opcode = load_memory(ms, pc);
+        if (pc + instruction_length(opcode)) > endpoint {
+            out.push_str(&pc.to_string());
+            out.push('\t');
+            out.push_str(&opcode.to_string());
+            out.push('\t');
+            unknown(&mut out, opcode);
+            pc += 1;
+        } else {
+            out.push_str(&disassemble(ms, &mut pc));
+        }
+        out.push('\n');
+    }
+    return out;
+}
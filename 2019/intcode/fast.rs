@@ -0,0 +1,326 @@
+//! Faster Intcode execution.
+//!
+//! `FastMachine` runs programs exactly like `step` and `run`, with the same
+//! results and faults, but keeps memory in pages of a `Vec` rather than in
+//! a `HashMap`, and remembers how each instruction was decoded so that the
+//! parameter modes are not worked out again every time it is executed. A
+//! write to an address forgets the decoding there, so self-modifying code
+//! still works. Addresses beyond the pages are kept in a `HashMap`.
+//!
+//! Convert with `FastMachine::new` and `FastMachine::to_state` to use the
+//! debugger, snapshots and so on with a fast machine.
+
+use std::collections::HashMap;
+use crate::{Word, Memory, InputOutput, MachineState, Status, Fault, Cause};
+
+const PAGE_BITS: usize = 12;
+const PAGE_SIZE: usize = 1 << PAGE_BITS;
+// addresses below MAX_PAGES * PAGE_SIZE are paged
+const MAX_PAGES: usize = 4096;
+
+/// An instruction with its parameter modes separated out.
+#[derive(Copy, Clone)]
+struct Decoded {
+    operation: Word,
+    modes: [Word; 3],
+}
+
+fn decode(opcode: Word) -> Decoded {
+    return Decoded {
+        operation: opcode % 100,
+        modes: [(opcode / 100) % 10, (opcode / 1000) % 10, (opcode / 10000) % 10],
+    };
+}
+
+struct Page {
+    words: Vec<Word>,
+    decoded: Vec<Option<Decoded>>,
+}
+
+impl Page {
+    fn new() -> Self {
+        return Self {
+            words: vec![0; PAGE_SIZE],
+            decoded: vec![None; PAGE_SIZE],
+        };
+    }
+}
+
+pub struct FastMachine {
+    pages: Vec<Option<Box<Page>>>,
+    far: HashMap<Word, Word>,
+    pub input: InputOutput,
+    pub output: InputOutput,
+    pub pc: Word,
+    pub relative_base: Word,
+}
+
+fn paged(address: Word) -> Option<(usize, usize)> {
+    if address < 0 || address as usize >= MAX_PAGES * PAGE_SIZE {
+        return None;
+    }
+    let address = address as usize;
+    return Some((address >> PAGE_BITS, address & (PAGE_SIZE - 1)));
+}
+
+impl FastMachine {
+    /// Copy a machine state.
+    pub fn new(ms: &MachineState) -> Self {
+        let mut fm = Self {
+            pages: Vec::new(),
+            far: HashMap::new(),
+            input: ms.input.clone(),
+            output: ms.output.clone(),
+            pc: ms.pc,
+            relative_base: ms.relative_base,
+        };
+        for (address, value) in ms.memory.iter() {
+            fm.store(*address, *value);
+        }
+        return fm;
+    }
+
+    /// Copy back to a machine state. Words that are zero are left out of
+    /// its memory, which makes no difference to the program.
+    pub fn to_state(self: &Self) -> MachineState {
+        let mut memory: Memory = HashMap::new();
+        for (number, page) in self.pages.iter().enumerate() {
+            if let Some(page) = page {
+                for (offset, value) in page.words.iter().enumerate() {
+                    if *value != 0 {
+                        memory.insert(((number << PAGE_BITS) + offset) as Word, *value);
+                    }
+                }
+            }
+        }
+        for (address, value) in self.far.iter() {
+            if *value != 0 {
+                memory.insert(*address, *value);
+            }
+        }
+        let mut ms = MachineState::new(memory);
+        ms.input = self.input.clone();
+        ms.output = self.output.clone();
+        ms.pc = self.pc;
+        ms.relative_base = self.relative_base;
+        return ms;
+    }
+
+    /// Read a word of memory. Words that were never written are zero.
+    pub fn load(self: &Self, address: Word) -> Word {
+        return match paged(address) {
+            Some((number, offset)) => match self.pages.get(number) {
+                Some(Some(page)) => page.words[offset],
+                _ => 0,
+            },
+            None => *self.far.get(&address).unwrap_or(&0),
+        };
+    }
+
+    pub fn store(self: &mut Self, address: Word, value: Word) {
+        match paged(address) {
+            Some((number, offset)) => {
+                if number >= self.pages.len() {
+                    self.pages.resize_with(number + 1, || None);
+                }
+                let page = self.pages[number].get_or_insert_with(|| Box::new(Page::new()));
+                page.words[offset] = value;
+                page.decoded[offset] = None;
+            },
+            None => {
+                self.far.insert(address, value);
+            },
+        }
+    }
+
+    /// Queue a value for the program to read.
+    pub fn push_input(self: &mut Self, value: Word) {
+        self.input.push_back(value);
+    }
+
+    /// Take the oldest value written by the program, if any.
+    pub fn pop_output(self: &mut Self) -> Option<Word> {
+        return self.output.pop_front();
+    }
+
+    fn fault(self: &Self, cause: Cause) -> Fault {
+        return Fault {
+            pc: self.pc,
+            opcode: self.load(self.pc),
+            cause: cause,
+        };
+    }
+
+    fn no_overflow(self: &Self, result: Option<Word>) -> Result<Word, Fault> {
+        return result.ok_or_else(|| self.fault(Cause::Overflow));
+    }
+
+    fn checked_load(self: &Self, address: Word) -> Result<Word, Fault> {
+        if address < 0 {
+            return Err(self.fault(Cause::NegativeAddress(address)));
+        }
+        return Ok(self.load(address));
+    }
+
+    // The decoding of the instruction at pc, from the cache if possible
+    fn decoded(self: &mut Self) -> Result<Decoded, Fault> {
+        if let Some((number, offset)) = paged(self.pc) {
+            if let Some(Some(page)) = self.pages.get_mut(number) {
+                if let Some(d) = page.decoded[offset] {
+                    return Ok(d);
+                }
+                let d = decode(page.words[offset]);
+                page.decoded[offset] = Some(d);
+                return Ok(d);
+            }
+        }
+        return Ok(decode(self.checked_load(self.pc)?));
+    }
+
+    fn load_parameter(self: &Self, d: &Decoded, index: usize) -> Result<Word, Fault> {
+        let parameter = self.checked_load(self.no_overflow(self.pc.checked_add(index as Word))?)?;
+        return match d.modes[index - 1] {
+            0 => self.checked_load(parameter),
+            1 => Ok(parameter),
+            2 => self.checked_load(self.no_overflow(parameter.checked_add(self.relative_base))?),
+            mode => Err(self.fault(Cause::BadParameterMode(index as Word, mode))),
+        };
+    }
+
+    fn store_address(self: &Self, d: &Decoded, index: usize) -> Result<Word, Fault> {
+        let parameter = self.checked_load(self.no_overflow(self.pc.checked_add(index as Word))?)?;
+        let address = match d.modes[index - 1] {
+            0 => parameter,
+            1 => { return Err(self.fault(Cause::ImmediateStore(index as Word))); },
+            2 => self.no_overflow(parameter.checked_add(self.relative_base))?,
+            mode => { return Err(self.fault(Cause::BadParameterMode(index as Word, mode))); },
+        };
+        if address < 0 {
+            return Err(self.fault(Cause::NegativeAddress(address)));
+        }
+        return Ok(address);
+    }
+
+    fn next_pc(self: &Self, length: Word) -> Result<Word, Fault> {
+        return self.no_overflow(self.pc.checked_add(length));
+    }
+
+    /// Execute the instruction at `pc`, like `crate::step`.
+    pub fn step(self: &mut Self) -> Result<Status, Fault> {
+        let d = self.decoded()?;
+        match d.operation {
+            1 | 2 | 7 | 8 => {
+                let a = self.load_parameter(&d, 1)?;
+                let b = self.load_parameter(&d, 2)?;
+                let r = self.store_address(&d, 3)?;
+                let value = match d.operation {
+                    1 => self.no_overflow(a.checked_add(b))?,
+                    2 => self.no_overflow(a.checked_mul(b))?,
+                    7 => if a < b { 1 } else { 0 },
+                    _ => if a == b { 1 } else { 0 },
+                };
+                self.pc = self.next_pc(4)?;
+                self.store(r, value);
+            },
+            3 => {
+                let r = self.store_address(&d, 1)?;
+                if self.input.is_empty() {
+                    return Ok(Status::Blocked);
+                }
+                self.pc = self.next_pc(2)?;
+                let v = self.input.pop_front().unwrap();
+                self.store(r, v);
+            },
+            4 => {
+                let a = self.load_parameter(&d, 1)?;
+                self.pc = self.next_pc(2)?;
+                self.output.push_back(a);
+            },
+            5 | 6 => {
+                let a = self.load_parameter(&d, 1)?;
+                let b = self.load_parameter(&d, 2)?;
+                self.pc = self.next_pc(3)?;
+                if (a != 0) == (d.operation == 5) {
+                    self.pc = b;
+                }
+            },
+            9 => {
+                let a = self.load_parameter(&d, 1)?;
+                let base = self.no_overflow(self.relative_base.checked_add(a))?;
+                self.pc = self.next_pc(2)?;
+                self.relative_base = base;
+            },
+            99 => {
+                return Ok(Status::Halted);
+            },
+            _ => {
+                return Err(self.fault(Cause::UnknownOpcode));
+            },
+        }
+        return Ok(Status::Running);
+    }
+
+    /// Execute instructions until the program halts or needs input,
+    /// like `crate::run`.
+    pub fn run(self: &mut Self) -> Result<Option<Word>, Fault> {
+        loop {
+            match self.step()? {
+                Status::Running => {},
+                Status::Blocked => {
+                    return Ok(None);
+                },
+                Status::Halted => {
+                    return Ok(Some(self.load(0)));
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+fn same_as_reference(mut ms: MachineState) -> FastMachine {
+    let mut fm = FastMachine::new(&ms);
+    let expected = crate::run(&mut ms);
+    assert_eq!(fm.run(), expected);
+    let copy = fm.to_state();
+    assert_eq!((copy.pc, copy.relative_base), (ms.pc, ms.relative_base));
+    assert_eq!((&copy.input, &copy.output), (&ms.input, &ms.output));
+    for (address, value) in ms.memory.iter() {
+        assert_eq!(fm.load(*address), *value);
+    }
+    return fm;
+}
+
+#[test]
+fn test_fast_machine() {
+    let check = |program: &str, inputs: &[Word]| {
+        let mut ms = crate::load(program);
+        ms.input.extend(inputs);
+        return same_as_reference(ms);
+    };
+    check("1,9,10,3,2,3,11,0,99,30,40,50", &[]);
+    check("3,20,3,21,4,20,4,21,1,20,21,22,4,22,99", &[5, 7]);
+    check("3,20,3,21,4,20,4,21,1,20,21,22,4,22,99", &[5]);
+    // relative mode, and a large address outside the pages
+    check("109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99", &[]);
+    check("104,1125899906842624,99", &[]);
+    check("21101,3,4,100000000000,204,100000000000,99", &[]);
+
+    // the instruction at 0 is rewritten after it has been executed
+    let fm = check("1106,0,7,104,42,99,0,1101,1105,0,0,1105,1,0", &[]);
+    assert_eq!(Vec::from(fm.output.clone()), vec![42]);
+
+    // faults are reported the same way
+    for program in ["42,0,0,99", "11101,1,2,3,99", "103,5,99", "304,5,99",
+                    "1105,1,-3", "4,-1,99", "109,-10,203,5,99", "30001,0,0,0",
+                    "1102,4611686018427387904,2,0,99", "109,9223372036854775807,209,1,99",
+                    "1101,4,0,9223372036854775807,1105,1,9223372036854775807"] {
+        check(program, &[1]);
+    }
+
+    // the BOOST program tests every instruction
+    let mut ms = crate::load_from_input("../9/input");
+    ms.push_input(1);
+    let fm = same_as_reference(ms);
+    assert_eq!(Vec::from(fm.output.clone()), vec![3497884671]);
+}
@@ -0,0 +1,525 @@
+//! Control flow analysis for Intcode programs.
+//!
+//! Rather than decoding every word from 0 to the end, as `disasm` does,
+//! `analyse` starts at the entry point and follows each possible path
+//! through the program, so that data tables are not mistaken for code.
+//!
+//! Along each path, the relative base is tracked while it is only changed
+//! by `adj` with an immediate operand. Constants written to the stack (i.e.
+//! through relative mode) are tracked as well, which is enough to follow
+//! the usual call sequence: store a return address at `[sp+n]`, jump to
+//! the function, and later return with `br if 0 == 0 goto [sp+n]`.
+//! Code that modifies its own instructions can't be followed; 2019/5's
+//! diagnostic program stops at the first such instruction.
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use crate::{Word, MachineState, get_parameter_mode, load_memory};
+use crate::disasm::{disassemble_labelled, instruction_length};
+
+// A path may visit an instruction in this many different states before
+// the tracked information is discarded, which ensures that analysis ends.
+const MAX_STATES: usize = 8;
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct State {
+    relative_base: Option<Word>,
+    stack: BTreeMap<Word, Word>,
+}
+
+/// What is known about one reachable instruction.
+pub struct InstructionInfo {
+    pub length: Word,
+    /// Addresses that may execute next.
+    pub successors: BTreeSet<Word>,
+    /// A branch may be taken to an address that could not be determined.
+    pub indirect: bool,
+    /// The instruction can't be executed (unknown opcode or mode).
+    pub invalid: bool,
+    /// The relative base, if it is the same on every path.
+    pub relative_base: Option<Word>,
+}
+
+/// A straight-line sequence of instructions, entered only at the start.
+pub struct Block {
+    pub start: Word,
+    /// Address after the last instruction.
+    pub end: Word,
+    pub successors: BTreeSet<Word>,
+    pub indirect: bool,
+    pub relative_base: Option<Word>,
+}
+
+pub struct Flow {
+    pub endpoint: Word,
+    pub instructions: BTreeMap<Word, InstructionInfo>,
+    pub blocks: BTreeMap<Word, Block>,
+}
+
+impl Flow {
+    /// True if no reachable instruction uses the word at `address`.
+    pub fn is_data(self: &Self, address: Word) -> bool {
+        for (start, info) in self.instructions.range(..= address).rev() {
+            if start.saturating_add(info.length) > address {
+                return false;
+            }
+            if (address - start) >= 4 {
+                break;
+            }
+        }
+        return true;
+    }
+
+    /// Label for the block starting at `address`.
+    pub fn label(self: &Self, address: Word) -> Option<String> {
+        if self.blocks.contains_key(&address) {
+            return Some(format!("L{}", address));
+        }
+        return None;
+    }
+
+    fn labels(self: &Self) -> HashMap<Word, String> {
+        let mut labels: HashMap<Word, String> = HashMap::new();
+        for start in self.blocks.keys() {
+            labels.insert(*start, self.label(*start).unwrap());
+        }
+        return labels;
+    }
+}
+
+// An address that overflows isn't known, just as at runtime it would fault
+fn operand_value(ms: &MachineState, state: &State, pc: Word, index: Word) -> Option<Word> {
+    let opcode = load_memory(ms, pc);
+    let parameter = load_memory(ms, pc.checked_add(index)?);
+    return match get_parameter_mode(opcode, index) {
+        1 => Some(parameter),
+        2 => match state.relative_base.and_then(|base| base.checked_add(parameter)) {
+            Some(address) => state.stack.get(&address).copied(),
+            None => None,
+        },
+        _ => None,
+    };
+}
+
+fn is_immediate(ms: &MachineState, pc: Word, index: Word) -> bool {
+    return get_parameter_mode(load_memory(ms, pc), index) == 1;
+}
+
+// Record a write to parameter `index`. Returns false if the write is invalid.
+fn store(ms: &MachineState, state: &mut State, pc: Word, index: Word,
+         value: Option<Word>) -> bool {
+    let opcode = load_memory(ms, pc);
+    let parameter = match pc.checked_add(index) {
+        Some(address) => load_memory(ms, address),
+        None => { return false; },
+    };
+    match get_parameter_mode(opcode, index) {
+        0 => {
+            state.stack.remove(&parameter);
+        },
+        2 => match state.relative_base {
+            Some(base) => match (base.checked_add(parameter), value) {
+                (Some(address), Some(v)) => { state.stack.insert(address, v); },
+                (Some(address), None) => { state.stack.remove(&address); },
+                // the store would fault
+                (None, _) => { return false; },
+            },
+            None => {
+                // could be anywhere
+                state.stack.clear();
+            },
+        },
+        _ => {
+            return false;
+        },
+    }
+    return true;
+}
+
+fn modes_valid(opcode: Word) -> bool {
+    for index in 1 .. instruction_length(opcode) {
+        if get_parameter_mode(opcode, index) > 2 {
+            return false;
+        }
+    }
+    return true;
+}
+
+// Abstract execution of one instruction: returns the possible next states
+// and updates `info`.
+fn visit(ms: &MachineState, state: &State, pc: Word,
+         info: &mut InstructionInfo) -> Vec<(Word, State)> {
+    let opcode = load_memory(ms, pc);
+    let mut out = state.clone();
+    let mut result: Vec<(Word, State)> = Vec::new();
+
+    // once `next` is known, the operands at pc + 1 and so on can be read
+    let next = match pc.checked_add(info.length) {
+        Some(next) if modes_valid(opcode) => next,
+        _ => {
+            info.invalid = true;
+            return result;
+        },
+    };
+    match opcode % 100 {
+        1 | 2 | 7 | 8 => {
+            // Only constants are tracked, so that loop counters don't
+            // produce a new state on each iteration
+            let mut value: Option<Word> = None;
+            if is_immediate(ms, pc, 1) && is_immediate(ms, pc, 2) {
+                let a = load_memory(ms, pc + 1);
+                let b = load_memory(ms, pc + 2);
+                value = match opcode % 100 {
+                    1 => a.checked_add(b),
+                    2 => a.checked_mul(b),
+                    7 => Some(if a < b { 1 } else { 0 }),
+                    _ => Some(if a == b { 1 } else { 0 }),
+                };
+            }
+            if !store(ms, &mut out, pc, 3, value) {
+                info.invalid = true;
+                return result;
+            }
+            result.push((next, out));
+        },
+        3 => {
+            if !store(ms, &mut out, pc, 1, None) {
+                info.invalid = true;
+                return result;
+            }
+            result.push((next, out));
+        },
+        4 => {
+            result.push((next, out));
+        },
+        5 | 6 => {
+            let condition = operand_value(ms, state, pc, 1);
+            let (taken, not_taken) = match (condition, opcode % 100) {
+                (Some(c), 5) => (c != 0, c == 0),
+                (Some(c), _) => (c == 0, c != 0),
+                (None, _) => (true, true),
+            };
+            if taken {
+                match operand_value(ms, state, pc, 2) {
+                    Some(target) => { result.push((target, out.clone())); },
+                    None => { info.indirect = true; },
+                }
+            }
+            if not_taken {
+                result.push((next, out));
+            }
+        },
+        9 => {
+            if is_immediate(ms, pc, 1) {
+                // a base that overflows is unknown
+                let adjustment = load_memory(ms, pc + 1);
+                out.relative_base = out.relative_base.and_then(|b| b.checked_add(adjustment));
+            } else {
+                out.relative_base = None;
+            }
+            result.push((next, out));
+        },
+        99 => {},
+        _ => {
+            info.invalid = true;
+        },
+    }
+    return result;
+}
+
+/// Find the instructions that can be reached from the entry points,
+/// considering only the words from 0 up to `endpoint`.
+pub fn analyse(ms: &MachineState, endpoint: Word, entry_points: &[Word]) -> Flow {
+    let mut instructions: BTreeMap<Word, InstructionInfo> = BTreeMap::new();
+    let mut seen: HashMap<Word, HashSet<State>> = HashMap::new();
+    let mut bases: HashMap<Word, BTreeSet<Option<Word>>> = HashMap::new();
+    let mut todo: Vec<(Word, State)> = Vec::new();
+
+    for entry in entry_points {
+        todo.push((*entry, State {
+            relative_base: Some(0),
+            stack: BTreeMap::new(),
+        }));
+    }
+
+    while let Some((pc, mut state)) = todo.pop() {
+        if pc < 0 || pc >= endpoint {
+            continue;
+        }
+        let states = seen.entry(pc).or_default();
+        if states.contains(&state) {
+            continue;
+        }
+        if states.len() >= MAX_STATES {
+            state = State {
+                relative_base: None,
+                stack: BTreeMap::new(),
+            };
+            if states.contains(&state) {
+                continue;
+            }
+        }
+        states.insert(state.clone());
+        bases.entry(pc).or_default().insert(state.relative_base);
+
+        let info = instructions.entry(pc).or_insert(InstructionInfo {
+            length: instruction_length(load_memory(ms, pc)),
+            successors: BTreeSet::new(),
+            indirect: false,
+            invalid: false,
+            relative_base: None,
+        });
+        for (next_pc, next_state) in visit(ms, &state, pc, info) {
+            info.successors.insert(next_pc);
+            todo.push((next_pc, next_state));
+        }
+    }
+
+    for (pc, info) in instructions.iter_mut() {
+        let b = bases.get(pc).unwrap();
+        if b.len() == 1 {
+            info.relative_base = *b.iter().next().unwrap();
+        }
+    }
+
+    // Blocks start at entry points, at branch targets, after branches,
+    // and wherever paths merge
+    let mut predecessors: HashMap<Word, usize> = HashMap::new();
+    let mut leaders: BTreeSet<Word> = BTreeSet::new();
+    for entry in entry_points {
+        if instructions.contains_key(entry) {
+            leaders.insert(*entry);
+        }
+    }
+    for (pc, info) in &instructions {
+        let next = pc.saturating_add(info.length);
+        let straight = info.successors.len() == 1 && info.successors.contains(&next)
+                        && !info.indirect;
+        for s in &info.successors {
+            *predecessors.entry(*s).or_default() += 1;
+            if !straight && instructions.contains_key(s) {
+                leaders.insert(*s);
+            }
+        }
+    }
+    for (pc, count) in &predecessors {
+        if *count > 1 && instructions.contains_key(pc) {
+            leaders.insert(*pc);
+        }
+    }
+
+    let mut blocks: BTreeMap<Word, Block> = BTreeMap::new();
+    for start in &leaders {
+        let mut pc = *start;
+        loop {
+            let info = instructions.get(&pc).unwrap();
+            let next = pc.saturating_add(info.length);
+            if info.successors.len() == 1 && info.successors.contains(&next)
+                    && !info.indirect && !leaders.contains(&next)
+                    && instructions.contains_key(&next) {
+                pc = next;
+                continue;
+            }
+            blocks.insert(*start, Block {
+                start: *start,
+                end: next,
+                successors: info.successors.clone(),
+                indirect: info.indirect,
+                relative_base: instructions.get(start).unwrap().relative_base,
+            });
+            break;
+        }
+    }
+
+    return Flow {
+        endpoint: endpoint,
+        instructions: instructions,
+        blocks: blocks,
+    };
+}
+
+fn data_line(ms: &MachineState, pc: Word) -> String {
+    let value = load_memory(ms, pc);
+    return format!("{}\t{}\tdata\t{}", pc, value, value);
+}
+
+// Addresses of the instructions shown by `disassemble_reachable`
+fn listed(flow: &Flow) -> BTreeSet<Word> {
+    let mut result: BTreeSet<Word> = BTreeSet::new();
+    let mut pc = 0;
+    while pc < flow.endpoint {
+        match flow.instructions.get(&pc) {
+            Some(info) if pc.saturating_add(info.length) <= flow.endpoint => {
+                result.insert(pc);
+                pc += info.length;
+            },
+            _ => {
+                pc += 1;
+            },
+        }
+    }
+    return result;
+}
+
+/// Disassemble the reachable instructions, with a label at the start of each
+/// block. Words that are not reachable are written as data. The output can
+/// be reassembled with `asm::assemble`.
+pub fn disassemble_reachable(ms: &MachineState, flow: &Flow) -> String {
+    // Blocks that begin inside another instruction can't be labelled
+    let listed = listed(flow);
+    let mut labels = flow.labels();
+    labels.retain(|address, _| listed.contains(address));
+
+    let mut out = String::new();
+    let mut pc = 0;
+    while pc < flow.endpoint {
+        if listed.contains(&pc) {
+            if let Some(block) = flow.blocks.get(&pc) {
+                out.push_str(&labels[&pc]);
+                out.push(':');
+                if let Some(base) = block.relative_base {
+                    out.push_str(&format!("\t# sp={}", base));
+                }
+                out.push('\n');
+            }
+            let start = pc;
+            out.push_str(&disassemble_labelled(ms, &mut pc, &labels));
+            for overlap in flow.blocks.range(start + 1 .. pc) {
+                out.push_str(&format!("\t# also entered at {}", overlap.0));
+            }
+        } else {
+            out.push_str(&data_line(ms, pc));
+            pc += 1;
+        }
+        out.push('\n');
+    }
+    return out;
+}
+
+fn dot_escape(text: &str) -> String {
+    return text.replace('\\', "\\\\").replace('"', "\\\"").replace('\t', " ");
+}
+
+/// Control flow graph in Graphviz DOT format, one node per block.
+pub fn control_flow_dot(ms: &MachineState, flow: &Flow) -> String {
+    let labels = flow.labels();
+    let mut out = String::new();
+    out.push_str("digraph intcode {\n");
+    out.push_str("    node [shape=box, fontname=\"monospace\"];\n");
+    for block in flow.blocks.values() {
+        let name = &labels[&block.start];
+        let mut text = name.clone();
+        if let Some(base) = block.relative_base {
+            text.push_str(&format!(" (sp={})", base));
+        }
+        text.push_str("\\l");
+        let mut pc = block.start;
+        while pc < block.end {
+            text.push_str(&dot_escape(&disassemble_labelled(ms, &mut pc, &labels)));
+            text.push_str("\\l");
+        }
+        out.push_str(&format!("    {} [label=\"{}\"];\n", name, text));
+        for s in &block.successors {
+            if *s == block.end {
+                out.push_str(&format!("    {} -> L{};\n", name, s));
+            } else {
+                out.push_str(&format!("    {} -> L{} [style=bold];\n", name, s));
+            }
+        }
+        if block.indirect {
+            out.push_str(&format!("    {}_indirect [label=\"?\", shape=plaintext];\n", name));
+            out.push_str(&format!("    {} -> {}_indirect [style=dashed];\n", name, name));
+        }
+    }
+    out.push_str("}\n");
+    return out;
+}
+
+#[cfg(test)]
+fn test_program() -> MachineState {
+    let source = "
+                adj     sp + 100 -> sp
+                br      if 1 != 0 goto main
+        table:  data    1101, 99, 0, 7
+        main:   add     after + 0 -> [sp]
+                br      if 0 == 0 goto func
+        after:  out     [table] -> output
+                halt
+        func:   adj     sp + 2 -> sp
+                out     [sp-1] -> output
+                adj     sp + -2 -> sp
+                br      if 0 == 0 goto [sp]
+    ";
+    let program = crate::asm::assemble(source).unwrap();
+    return crate::load(&crate::asm::program_text(&program));
+}
+
+#[test]
+fn test_analyse() {
+    let ms = test_program();
+    let flow = analyse(&ms, ms.memory.len() as Word, &[0]);
+
+    let code: Vec<Word> = flow.instructions.keys().copied().collect();
+    assert_eq!(code, vec![0, 2, 9, 13, 16, 18, 19, 21, 23, 25]);
+    for address in 0 .. 28 {
+        assert_eq!(flow.is_data(address), (5 .. 9).contains(&address));
+    }
+
+    // the return from "func" is followed back to "after"
+    let blocks: Vec<(Word, Word, Vec<Word>)> = flow.blocks.values()
+        .map(|b| (b.start, b.end, b.successors.iter().copied().collect())).collect();
+    assert_eq!(blocks, vec![(0, 5, vec![9]), (9, 16, vec![19]),
+                            (16, 19, vec![]), (19, 28, vec![16])]);
+    assert_eq!(flow.instructions[&21].relative_base, Some(102));
+    assert_eq!(flow.instructions[&25].relative_base, Some(100));
+    assert!(!flow.blocks[&19].indirect);
+}
+
+#[test]
+fn test_disassemble_reachable() {
+    let ms = test_program();
+    let flow = analyse(&ms, ms.memory.len() as Word, &[0]);
+    let text = disassemble_reachable(&ms, &flow);
+    assert!(text.starts_with("L0:\t# sp=0\n0\t109\tadj\tsp + 100 -> sp\n2\t1105\tbr\tif 1 != 0 goto L9\n5\t1101\tdata\t1101\n"));
+    assert!(text.contains("L19:\t# sp=100\n"));
+
+    let dot = control_flow_dot(&ms, &flow);
+    assert!(dot.starts_with("digraph intcode {\n"));
+    assert!(dot.contains("    L19 -> L16 [style=bold];\n"));
+    assert!(dot.contains("    L0 -> L9 [style=bold];\n"));
+
+    // every puzzle input can be reassembled from this form as well
+    for day in ["2", "5", "7", "9", "11", "13", "15"] {
+        let filename = format!("../{}/input", day);
+        let ms = crate::load_from_input(&filename);
+        let endpoint = ms.memory.len() as Word;
+        let flow = analyse(&ms, endpoint, &[0]);
+        let program = crate::asm::assemble(&disassemble_reachable(&ms, &flow)).unwrap();
+        assert_eq!(program.len() as Word, endpoint);
+        for (address, value) in program.iter().enumerate() {
+            assert_eq!(*value, load_memory(&ms, address as Word));
+        }
+    }
+}
+
+#[test]
+fn test_overflow() {
+    // at runtime the second adj faults, so the base after it is unknown
+    let ms = crate::load("109,9223372036854775807,109,1,99");
+    let flow = analyse(&ms, 5, &[0]);
+    assert_eq!(flow.instructions[&2].relative_base, Some(Word::MAX));
+    assert_eq!(flow.instructions[&4].relative_base, None);
+    assert!(disassemble_reachable(&ms, &flow).contains("L0:\t# sp=0\n"));
+
+    // as are relative operands that overflow
+    let ms = crate::load("109,9223372036854775807,2105,1,1,99");
+    let flow = analyse(&ms, 6, &[0]);
+    assert!(flow.instructions[&2].indirect);
+    assert!(!flow.instructions[&2].invalid);
+    let ms = crate::load("109,9223372036854775807,21101,1,1,1,99");
+    let flow = analyse(&ms, 7, &[0]);
+    assert!(flow.instructions[&2].invalid);
+    assert!(!flow.instructions.contains_key(&6));
+}
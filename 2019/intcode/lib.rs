@@ -0,0 +1,383 @@
+//! Intcode virtual machine shared by the 2019 puzzles.
+//!
+//! A program is loaded from its comma-separated text form with `load` or
+//! `load_from_input`. It can then be executed one instruction at a time
+//! with `step`, or until it halts or needs more input with `run`.
+//!
+//! Input and output are FIFO queues: values are pushed onto the back of
+//! `input` and the program reads them from the front; the program pushes
+//! values onto the back of `output` and the caller pops them from the front.
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead};
+use std::collections::HashMap;
+use std::collections::VecDeque;
+
+pub mod adapter;
+pub mod asm;
+pub mod conformance;
+pub mod debug;
+pub mod decompile;
+pub mod disasm;
+pub mod fast;
+pub mod flow;
+pub mod network;
+pub mod snapshot;
+pub mod trace;
+
+pub type Word = i64;
+pub type Memory = HashMap<Word, Word>;
+pub type InputOutput = VecDeque<Word>;
+
+/// Cloning a machine forks it: the copy runs independently of the original.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct MachineState {
+    pub memory: Memory,
+    pub input: InputOutput,
+    pub output: InputOutput,
+    pub pc: Word,
+    pub relative_base: Word,
+}
+
+/// Outcome of executing a single instruction.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Status {
+    /// The instruction completed and the machine can continue.
+    Running,
+    /// The instruction at `pc` is an input and the input queue is empty.
+    /// Nothing has been changed: push some input and try again.
+    Blocked,
+    /// The instruction at `pc` is a halt. Nothing has been changed.
+    Halted,
+}
+
+impl MachineState {
+    /// Create a machine with the given memory, empty I/O queues,
+    /// and `pc` and `relative_base` both zero.
+    pub fn new(memory: Memory) -> Self {
+        return Self {
+            memory: memory,
+            input: VecDeque::new(),
+            output: VecDeque::new(),
+            pc: 0,
+            relative_base: 0,
+        };
+    }
+
+    /// Queue a value for the program to read.
+    pub fn push_input(self: &mut Self, value: Word) {
+        self.input.push_back(value);
+    }
+
+    /// Take the oldest value written by the program, if any.
+    pub fn pop_output(self: &mut Self) -> Option<Word> {
+        return self.output.pop_front();
+    }
+}
+
+/// Load a program from the first line of a file.
+pub fn load_from_input(filename: &str) -> MachineState {
+    let file = File::open(filename).unwrap();
+    let line = io::BufReader::new(file).lines().next().expect("line").unwrap();
+    return load(line.as_str());
+}
+
+/// Load a program from comma-separated text, e.g. "1,0,0,0,99".
+pub fn load(line: &str) -> MachineState {
+    let mut memory: Memory = HashMap::new();
+    let mut index: Word = 0;
+    for code in line.split(",") {
+        let trimmed = code.trim();
+        let parsed: Word = trimmed.parse().expect("number");
+        memory.insert(index, parsed);
+        index += 1;
+    }
+    return MachineState::new(memory);
+}
+
+/// Parameter mode for parameter `index` (counting from 1) of `opcode`:
+/// 0 is position mode, 1 is immediate mode, 2 is relative mode.
+pub fn get_parameter_mode(opcode: Word, index: Word) -> Word {
+    assert!(index >= 1);
+    let mut copy = opcode / 100;
+    for _ in 1 .. index {
+        copy = copy / 10;
+    }
+    return copy % 10;
+}
+
+/// Read a word of memory. Words that were never written are zero.
+pub fn load_memory(ms: &MachineState, address: Word) -> Word {
+    return *ms.memory.get(&address).unwrap_or(&0);
+}
+
+pub fn store_memory(ms: &mut MachineState, address: Word, value: Word) {
+    ms.memory.insert(address, value);
+}
+
+// Words separated by commas, as shown by the debugger and saved in snapshots
+pub(crate) fn list_words<'a>(words: impl Iterator<Item = &'a Word>) -> String {
+    let texts: Vec<String> = words.map(|w| w.to_string()).collect();
+    return texts.join(", ");
+}
+
+/// Why an instruction could not be executed.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Cause {
+    UnknownOpcode,
+    /// The given parameter is written to, but it is in immediate mode.
+    ImmediateStore(Word),
+    /// The given parameter has an unknown mode (the second value).
+    BadParameterMode(Word, Word),
+    /// The given address is negative.
+    NegativeAddress(Word),
+    /// A result, address or the next pc does not fit in a `Word`.
+    Overflow,
+}
+
+/// An instruction could not be executed. The machine state is left as it
+/// was before the instruction, with `pc` pointing at it.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct Fault {
+    pub pc: Word,
+    pub opcode: Word,
+    pub cause: Cause,
+}
+
+impl fmt::Display for Fault {
+    fn fmt(self: &Self, f: &mut fmt::Formatter) -> fmt::Result {
+        return match self.cause {
+            Cause::UnknownOpcode =>
+                write!(f, "unknown opcode {} at {}", self.opcode, self.pc),
+            Cause::ImmediateStore(index) =>
+                write!(f, "parameter {} of instruction {} at {} is written in immediate mode",
+                       index, self.opcode, self.pc),
+            Cause::BadParameterMode(index, mode) =>
+                write!(f, "parameter {} of instruction {} at {} has unknown mode {}",
+                       index, self.opcode, self.pc, mode),
+            Cause::NegativeAddress(address) =>
+                write!(f, "instruction {} at {} uses negative address {}",
+                       self.opcode, self.pc, address),
+            Cause::Overflow =>
+                write!(f, "instruction {} at {} overflows", self.opcode, self.pc),
+        };
+    }
+}
+
+fn fault(ms: &MachineState, cause: Cause) -> Fault {
+    return Fault {
+        pc: ms.pc,
+        opcode: *ms.memory.get(&ms.pc).unwrap_or(&0),
+        cause: cause,
+    };
+}
+
+// The result of checked arithmetic, or an overflow fault
+fn no_overflow(ms: &MachineState, result: Option<Word>) -> Result<Word, Fault> {
+    return result.ok_or_else(|| fault(ms, Cause::Overflow));
+}
+
+fn checked_load(ms: &MachineState, address: Word) -> Result<Word, Fault> {
+    if address < 0 {
+        return Err(fault(ms, Cause::NegativeAddress(address)));
+    }
+    return Ok(load_memory(ms, address));
+}
+
+fn load_parameter(ms: &MachineState, index: Word) -> Result<Word, Fault> {
+    assert!(index >= 1);
+    let opcode = checked_load(ms, ms.pc)?;
+    let parameter = checked_load(ms, no_overflow(ms, ms.pc.checked_add(index))?)?;
+
+    return match get_parameter_mode(opcode, index) {
+        0 => checked_load(ms, parameter),
+        1 => Ok(parameter),
+        2 => checked_load(ms, no_overflow(ms, parameter.checked_add(ms.relative_base))?),
+        mode => Err(fault(ms, Cause::BadParameterMode(index, mode))),
+    }
+}
+
+// Address written by parameter `index`; nothing is changed yet,
+// so that a fault leaves the machine state as it was
+fn store_address(ms: &MachineState, index: Word) -> Result<Word, Fault> {
+    assert!(index >= 1);
+    let opcode = checked_load(ms, ms.pc)?;
+    let parameter = checked_load(ms, no_overflow(ms, ms.pc.checked_add(index))?)?;
+
+    let address = match get_parameter_mode(opcode, index) {
+        0 => parameter,
+        1 => { return Err(fault(ms, Cause::ImmediateStore(index))); },
+        2 => no_overflow(ms, parameter.checked_add(ms.relative_base))?,
+        mode => { return Err(fault(ms, Cause::BadParameterMode(index, mode))); },
+    };
+    if address < 0 {
+        return Err(fault(ms, Cause::NegativeAddress(address)));
+    }
+    return Ok(address);
+}
+
+// The address of the instruction after the one at pc, which is `length` long
+fn next_pc(ms: &MachineState, length: Word) -> Result<Word, Fault> {
+    return no_overflow(ms, ms.pc.checked_add(length));
+}
+
+/// Execute the instruction at `pc`. If it can't be executed, the
+/// machine state is not changed. Arithmetic that overflows is a fault.
+pub fn step(ms: &mut MachineState) -> Result<Status, Fault> {
+    let opcode = checked_load(ms, ms.pc)?;
+    match opcode % 100 {
+        1 => {
+            let a = load_parameter(ms, 1)?;
+            let b = load_parameter(ms, 2)?;
+            let r = store_address(ms, 3)?;
+            let v = no_overflow(ms, a.checked_add(b))?;
+            ms.pc = next_pc(ms, 4)?;
+            store_memory(ms, r, v);
+        },
+        2 => {
+            let a = load_parameter(ms, 1)?;
+            let b = load_parameter(ms, 2)?;
+            let r = store_address(ms, 3)?;
+            let v = no_overflow(ms, a.checked_mul(b))?;
+            ms.pc = next_pc(ms, 4)?;
+            store_memory(ms, r, v);
+        },
+        3 => {
+            let r = store_address(ms, 1)?;
+            if ms.input.is_empty() {
+                return Ok(Status::Blocked);
+            }
+            ms.pc = next_pc(ms, 2)?;
+            let v = ms.input.pop_front().unwrap();
+            store_memory(ms, r, v);
+        },
+        4 => {
+            let a = load_parameter(ms, 1)?;
+            ms.pc = next_pc(ms, 2)?;
+            ms.output.push_back(a);
+        },
+        5 => {
+            let a = load_parameter(ms, 1)?;
+            let b = load_parameter(ms, 2)?;
+            ms.pc = next_pc(ms, 3)?;
+            if a != 0 {
+                ms.pc = b;
+            }
+        },
+        6 => {
+            let a = load_parameter(ms, 1)?;
+            let b = load_parameter(ms, 2)?;
+            ms.pc = next_pc(ms, 3)?;
+            if a == 0 {
+                ms.pc = b;
+            }
+        },
+        7 => {
+            let a = load_parameter(ms, 1)?;
+            let b = load_parameter(ms, 2)?;
+            let r = store_address(ms, 3)?;
+            ms.pc = next_pc(ms, 4)?;
+            store_memory(ms, r, if a < b { 1 } else { 0 });
+        },
+        8 => {
+            let a = load_parameter(ms, 1)?;
+            let b = load_parameter(ms, 2)?;
+            let r = store_address(ms, 3)?;
+            ms.pc = next_pc(ms, 4)?;
+            store_memory(ms, r, if a == b { 1 } else { 0 });
+        },
+        9 => {
+            let a = load_parameter(ms, 1)?;
+            let base = no_overflow(ms, ms.relative_base.checked_add(a))?;
+            ms.pc = next_pc(ms, 2)?;
+            ms.relative_base = base;
+        },
+        99 => {
+            return Ok(Status::Halted);
+        },
+        _ => {
+            return Err(fault(ms, Cause::UnknownOpcode));
+        }
+    }
+    return Ok(Status::Running);
+}
+
+/// Execute instructions until the program halts or needs input.
+/// Returns the value at address 0 if the program halted, or None if
+/// it is waiting for input; in that case, push some input and call
+/// `run` again to continue. If an instruction can't be executed,
+/// the fault is returned and the machine is left at that instruction.
+pub fn run(ms: &mut MachineState) -> Result<Option<Word>, Fault> {
+    loop {
+        match step(ms)? {
+            Status::Running => {},
+            Status::Blocked => {
+                return Ok(None);
+            },
+            Status::Halted => {
+                return Ok(Some(load_memory(ms, 0)));
+            },
+        }
+    }
+}
+
+#[test]
+fn test_old_program() {
+    assert_eq!(run(&mut load("1,9,10,3,2,3,11,0,99,30,40,50")), Ok(Some(3500)));
+    assert_eq!(run(&mut load("1,0,0,0,99")), Ok(Some(2)));
+    assert_eq!(run(&mut load("2,3,0,3,99")), Ok(Some(2)));
+    assert_eq!(run(&mut load("2,4,4,0,99,0")), Ok(Some(9801)));
+    assert_eq!(run(&mut load("1,1,1,4,99,5,6,0,99")), Ok(Some(30)));
+}
+
+#[test]
+fn test_io_order() {
+    // add two inputs: the first input must be read first
+    let mut ms = load("3,20,3,21,4,20,4,21,1,20,21,22,4,22,99");
+    ms.push_input(5);
+    assert_eq!(run(&mut ms), Ok(None));
+    ms.push_input(7);
+    assert!(run(&mut ms).unwrap().is_some());
+    assert_eq!(ms.pop_output(), Some(5));
+    assert_eq!(ms.pop_output(), Some(7));
+    assert_eq!(ms.pop_output(), Some(12));
+    assert_eq!(ms.pop_output(), None);
+}
+
+#[test]
+fn test_faults() {
+    let check = |program: &str, pc: Word, opcode: Word, cause: Cause| {
+        let mut ms = load(program);
+        ms.push_input(1);
+        let before = ms.memory.clone();
+        let f = run(&mut ms).unwrap_err();
+        assert_eq!(f, Fault { pc: pc, opcode: opcode, cause: cause });
+        // state is intact
+        assert_eq!(ms.pc, pc);
+        assert_eq!(ms.memory, before);
+        assert_eq!(Vec::from(ms.input.clone()), vec![1]);
+        // and the fault happens again if execution resumes
+        assert_eq!(step(&mut ms), Err(f));
+    };
+    check("42,0,0,99", 0, 42, Cause::UnknownOpcode);
+    check("11101,1,2,3,99", 0, 11101, Cause::ImmediateStore(3));
+    check("103,5,99", 0, 103, Cause::ImmediateStore(1));
+    check("304,5,99", 0, 304, Cause::BadParameterMode(1, 3));
+    check("1105,1,-3", -3, 0, Cause::NegativeAddress(-3));
+    check("4,-1,99", 0, 4, Cause::NegativeAddress(-1));
+    check("109,-10,203,5,99", 2, 203, Cause::NegativeAddress(-5));
+    check("1102,4611686018427387904,2,0,99", 0, 1102, Cause::Overflow);
+    check("1101,9223372036854775807,1,0,99", 0, 1101, Cause::Overflow);
+    check("109,9223372036854775807,209,1,99", 2, 209, Cause::Overflow);
+
+    let mut ms = load("11101,1,2,3,99");
+    assert_eq!(run(&mut ms).unwrap_err().to_string(),
+               "parameter 3 of instruction 11101 at 0 is written in immediate mode");
+    let mut ms = load("1102,4611686018427387904,2,0,99");
+    assert_eq!(run(&mut ms).unwrap_err().to_string(), "instruction 1102 at 0 overflows");
+
+    // an output at the highest address has its parameter beyond the end
+    let mut ms = load("1101,4,0,9223372036854775807,1105,1,9223372036854775807");
+    assert_eq!(run(&mut ms), Err(Fault { pc: Word::MAX, opcode: 4, cause: Cause::Overflow }));
+}
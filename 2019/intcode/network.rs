@@ -0,0 +1,284 @@
+//! Running several Intcode machines connected by channels.
+//!
+//! A channel is a named FIFO queue of words. Each machine in a `Network`
+//! reads its input from one channel, and its output is delivered according
+//! to a `Route`: to one channel, copied to several, or split into packets
+//! whose first word says where the rest should go. Chains and rings of
+//! machines, such as the amplifiers of 2019/7, are built by `chain` and
+//! `ring`.
+//!
+//! `Network::run` gives each machine a turn in order until every machine
+//! has halted, or until none of them can make progress because they are
+//! all halted or waiting for input that nobody is going to send. Words can
+//! be sent to a channel before running, or between runs.
+
+use std::fmt;
+use std::collections::BTreeMap;
+use crate::{Word, InputOutput, MachineState, Status, Fault, step};
+
+/// Where the output of a machine goes.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum Route {
+    /// Every word goes to the named channel.
+    Channel(String),
+    /// Every word is copied to each of the named channels.
+    Broadcast(Vec<String>),
+    /// Words are grouped into packets of `length` words. The first word of
+    /// a packet is an address and the others are sent to the channel for
+    /// that address.
+    Packets { length: usize, addresses: BTreeMap<Word, String> },
+}
+
+/// Why `Network::run` returned.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum Outcome {
+    /// Every machine has halted.
+    Halted,
+    /// The named machines are waiting for input and nothing else is
+    /// running; all other machines have halted.
+    Deadlock(Vec<String>),
+}
+
+/// A machine in the network could not continue.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum NetworkError {
+    /// The named machine faulted.
+    Fault(String, Fault),
+    /// The named machine sent a packet to an address with no channel.
+    UnknownAddress(String, Word),
+}
+
+impl fmt::Display for NetworkError {
+    fn fmt(self: &Self, f: &mut fmt::Formatter) -> fmt::Result {
+        return match self {
+            NetworkError::Fault(name, fault) =>
+                write!(f, "machine {}: {}", name, fault),
+            NetworkError::UnknownAddress(name, address) =>
+                write!(f, "machine {}: packet sent to unknown address {}", name, address),
+        };
+    }
+}
+
+struct Node {
+    name: String,
+    ms: MachineState,
+    input: String,
+    output: Route,
+    halted: bool,
+}
+
+pub struct Network {
+    nodes: Vec<Node>,
+    pub channels: BTreeMap<String, InputOutput>,
+    /// The most instructions a machine executes in one turn.
+    pub slice: usize,
+}
+
+impl Network {
+    pub fn new() -> Self {
+        return Self {
+            nodes: Vec::new(),
+            channels: BTreeMap::new(),
+            slice: 1000,
+        };
+    }
+
+    /// Add a machine reading from channel `input`. Each channel should be
+    /// read by at most one machine. Values already in the machine's input
+    /// queue are read before anything from the channel.
+    pub fn add(self: &mut Self, name: &str, ms: MachineState, input: &str, output: Route) {
+        self.channels.entry(input.to_string()).or_default();
+        match &output {
+            Route::Channel(channel) => {
+                self.channels.entry(channel.clone()).or_default();
+            },
+            Route::Broadcast(channels) => {
+                for channel in channels {
+                    self.channels.entry(channel.clone()).or_default();
+                }
+            },
+            Route::Packets { addresses, .. } => {
+                for channel in addresses.values() {
+                    self.channels.entry(channel.clone()).or_default();
+                }
+            },
+        }
+        self.nodes.push(Node {
+            name: name.to_string(),
+            ms: ms,
+            input: input.to_string(),
+            output: output,
+            halted: false,
+        });
+    }
+
+    /// Queue a word on a channel.
+    pub fn send(self: &mut Self, channel: &str, value: Word) {
+        self.channels.entry(channel.to_string()).or_default().push_back(value);
+    }
+
+    /// Take the oldest word from a channel, if any.
+    pub fn receive(self: &mut Self, channel: &str) -> Option<Word> {
+        return self.channels.get_mut(channel)?.pop_front();
+    }
+
+    /// The state of the named machine.
+    pub fn machine(self: &Self, name: &str) -> Option<&MachineState> {
+        return self.nodes.iter().find(|n| n.name == name).map(|n| &n.ms);
+    }
+
+    fn deliver(self: &mut Self, index: usize) -> Result<(), NetworkError> {
+        let node = &mut self.nodes[index];
+        match &node.output {
+            Route::Channel(channel) => {
+                self.channels.get_mut(channel).unwrap().extend(node.ms.output.drain(..));
+            },
+            Route::Broadcast(channels) => {
+                for value in node.ms.output.drain(..) {
+                    for channel in channels {
+                        self.channels.get_mut(channel).unwrap().push_back(value);
+                    }
+                }
+            },
+            Route::Packets { length, addresses } => {
+                // an incomplete packet waits for the rest of its words
+                while *length > 0 && node.ms.output.len() >= *length {
+                    let address = node.ms.output[0];
+                    let channel = addresses.get(&address).ok_or_else(
+                        || NetworkError::UnknownAddress(node.name.clone(), address))?;
+                    node.ms.output.pop_front();
+                    let queue = self.channels.get_mut(channel).unwrap();
+                    queue.extend(node.ms.output.drain(.. *length - 1));
+                }
+            },
+        }
+        return Ok(());
+    }
+
+    /// Run the machines until they have all halted or none can continue.
+    pub fn run(self: &mut Self) -> Result<Outcome, NetworkError> {
+        loop {
+            let mut executed: usize = 0;
+            for index in 0 .. self.nodes.len() {
+                let node = &mut self.nodes[index];
+                if node.halted {
+                    continue;
+                }
+                node.ms.input.extend(self.channels.get_mut(&node.input).unwrap().drain(..));
+                for _ in 0 .. self.slice {
+                    match step(&mut node.ms) {
+                        Ok(Status::Running) => { executed += 1; },
+                        Ok(Status::Blocked) => { break; },
+                        Ok(Status::Halted) => { node.halted = true; break; },
+                        Err(fault) => {
+                            return Err(NetworkError::Fault(node.name.clone(), fault));
+                        },
+                    }
+                }
+                self.deliver(index)?;
+            }
+            if executed == 0 {
+                let waiting: Vec<String> = self.nodes.iter()
+                    .filter(|n| !n.halted).map(|n| n.name.clone()).collect();
+                if waiting.is_empty() {
+                    return Ok(Outcome::Halted);
+                }
+                return Ok(Outcome::Deadlock(waiting));
+            }
+        }
+    }
+}
+
+impl Default for Network {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+/// Connect machines in a line, named "0", "1" and so on. Each machine reads
+/// from the channel with its own name and writes to the next one's; the
+/// last machine writes to channel "out".
+pub fn chain(machines: Vec<MachineState>) -> Network {
+    let mut network = Network::new();
+    let count = machines.len();
+    for (i, ms) in machines.into_iter().enumerate() {
+        let next = if i + 1 < count { (i + 1).to_string() } else { "out".to_string() };
+        network.add(&i.to_string(), ms, &i.to_string(), Route::Channel(next));
+    }
+    return network;
+}
+
+/// Connect machines in a loop, named "0", "1" and so on. Each machine reads
+/// from the channel with its own name and writes to the next one's; the
+/// last machine writes to channel "0".
+pub fn ring(machines: Vec<MachineState>) -> Network {
+    let mut network = Network::new();
+    let count = machines.len();
+    for (i, ms) in machines.into_iter().enumerate() {
+        let next = ((i + 1) % count).to_string();
+        network.add(&i.to_string(), ms, &i.to_string(), Route::Channel(next));
+    }
+    return network;
+}
+
+#[cfg(test)]
+fn with_inputs(program: &str, inputs: &[Word]) -> MachineState {
+    let mut ms = crate::load(program);
+    ms.input.extend(inputs);
+    return ms;
+}
+
+#[test]
+fn test_network() {
+    // 2019/7 examples: phases are queued before the first signal
+    let program = "3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0";
+    let amps = [4, 3, 2, 1, 0].iter().map(|p| with_inputs(program, &[*p])).collect();
+    let mut network = chain(amps);
+    network.send("0", 0);
+    assert_eq!(network.run(), Ok(Outcome::Halted));
+    assert_eq!(network.receive("out"), Some(43210));
+
+    let program = "3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,\
+                   27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5";
+    let amps = [9, 8, 7, 6, 5].iter().map(|p| with_inputs(program, &[*p])).collect();
+    let mut network = ring(amps);
+    network.send("0", 0);
+    assert_eq!(network.run(), Ok(Outcome::Halted));
+    assert_eq!(network.receive("0"), Some(139629729));
+    assert_eq!(network.receive("0"), None);
+
+    // double every input, forever
+    let double = "3,11,102,2,11,11,4,11,1105,1,0,0";
+    let mut network = Network::new();
+    network.add("source", with_inputs("4,5,4,6,99,5,6", &[]), "none",
+                Route::Broadcast(vec!["a".to_string(), "b".to_string()]));
+    network.add("a", with_inputs(double, &[]), "a", Route::Channel("out".to_string()));
+    network.add("b", with_inputs(double, &[]), "b", Route::Channel("out".to_string()));
+    assert_eq!(network.run(),
+               Ok(Outcome::Deadlock(vec!["a".to_string(), "b".to_string()])));
+    let out: Vec<Word> = network.channels["out"].iter().copied().collect();
+    assert_eq!(out, vec![10, 12, 10, 12]);
+
+    // packets: address, value; address 3 is not connected
+    let mut addresses = BTreeMap::new();
+    addresses.insert(1, "a".to_string());
+    addresses.insert(2, "b".to_string());
+    let sender = with_inputs("104,2,104,7,104,1,104,5,99", &[]);
+    let mut network = Network::new();
+    network.add("sender", sender, "none",
+                Route::Packets { length: 2, addresses: addresses.clone() });
+    network.add("a", with_inputs(double, &[]), "a", Route::Channel("out".to_string()));
+    assert_eq!(network.run(), Ok(Outcome::Deadlock(vec!["a".to_string()])));
+    assert_eq!(network.receive("out"), Some(10));
+    assert_eq!(network.receive("b"), Some(7));
+    assert_eq!(network.machine("a").unwrap().input.len(), 0);
+
+    let sender = with_inputs("104,3,104,1,99", &[]);
+    let mut network = Network::new();
+    network.add("sender", sender, "none", Route::Packets { length: 2, addresses: addresses });
+    assert_eq!(network.run(), Err(NetworkError::UnknownAddress("sender".to_string(), 3)));
+
+    let mut network = ring(vec![with_inputs("3,0,98", &[])]);
+    network.send("0", 1);
+    assert_eq!(network.run().unwrap_err().to_string(), "machine 0: unknown opcode 98 at 2");
+}
@@ -0,0 +1,168 @@
+//! Saving and restoring the state of an Intcode machine.
+//!
+//! A `MachineState` can be cloned to fork execution in memory. To keep a
+//! state for later, `save` writes it to a text file and `restore` reads
+//! it back. The format has one item per line:
+//!
+//! ```text
+//! pc 8
+//! relative_base 0
+//! input 5, 7
+//! output
+//! memory 0 3, 20, 3, 21, 4, 20
+//! memory 20 5
+//! ```
+//!
+//! Each `memory` line gives a start address followed by the words stored
+//! at consecutive addresses from there.
+
+use std::fs;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use crate::{Word, Memory, InputOutput, MachineState, list_words};
+
+fn queue_line(name: &str, queue: &InputOutput) -> String {
+    if queue.is_empty() {
+        return format!("{}\n", name);
+    }
+    return format!("{} {}\n", name, list_words(queue.iter()));
+}
+
+fn parse_words(text: &str) -> Result<Vec<Word>, String> {
+    let mut words: Vec<Word> = Vec::new();
+    for item in text.split(",") {
+        let trimmed = item.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        words.push(trimmed.parse().map_err(|_| format!("'{}' is not a number", trimmed))?);
+    }
+    return Ok(words);
+}
+
+/// Write the machine state as text.
+pub fn to_text(ms: &MachineState) -> String {
+    let mut out = String::new();
+    out.push_str(&format!("pc {}\n", ms.pc));
+    out.push_str(&format!("relative_base {}\n", ms.relative_base));
+    out.push_str(&queue_line("input", &ms.input));
+    out.push_str(&queue_line("output", &ms.output));
+
+    // memory is written as runs of consecutive addresses
+    let mut addresses: Vec<Word> = ms.memory.keys().copied().collect();
+    addresses.sort();
+    let mut i = 0;
+    while i < addresses.len() {
+        let start = addresses[i];
+        let mut run: Vec<Word> = Vec::new();
+        while i < addresses.len() && addresses[i] == start + run.len() as Word {
+            run.push(*ms.memory.get(&addresses[i]).unwrap());
+            i += 1;
+        }
+        out.push_str(&format!("memory {} {}\n", start, list_words(run.iter())));
+    }
+    return out;
+}
+
+/// Read a machine state written by `to_text`.
+pub fn from_text(text: &str) -> Result<MachineState, String> {
+    let mut memory: Memory = HashMap::new();
+    let mut input: InputOutput = VecDeque::new();
+    let mut output: InputOutput = VecDeque::new();
+    let mut pc: Option<Word> = None;
+    let mut relative_base: Option<Word> = None;
+
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (name, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let error = |e: String| format!("line {}: {}", number + 1, e);
+        match name {
+            "pc" => {
+                pc = Some(rest.trim().parse().map_err(|_| error("bad pc".to_string()))?);
+            },
+            "relative_base" => {
+                relative_base = Some(rest.trim().parse()
+                                     .map_err(|_| error("bad relative_base".to_string()))?);
+            },
+            "input" => {
+                input.extend(parse_words(rest).map_err(error)?);
+            },
+            "output" => {
+                output.extend(parse_words(rest).map_err(error)?);
+            },
+            "memory" => {
+                let (start, words) = rest.trim().split_once(' ').unwrap_or((rest.trim(), ""));
+                let start: Word = start.parse()
+                                    .map_err(|_| error(format!("bad address '{}'", start)))?;
+                for (offset, value) in parse_words(words).map_err(error)?.iter().enumerate() {
+                    let address = start.checked_add(offset as Word)
+                        .ok_or(error(format!("bad address {} + {}", start, offset)))?;
+                    memory.insert(address, *value);
+                }
+            },
+            _ => {
+                return Err(error(format!("unknown item '{}'", name)));
+            },
+        }
+    }
+    let mut ms = MachineState::new(memory);
+    ms.input = input;
+    ms.output = output;
+    ms.pc = pc.ok_or("pc is missing")?;
+    ms.relative_base = relative_base.ok_or("relative_base is missing")?;
+    return Ok(ms);
+}
+
+/// Save the machine state to a file.
+pub fn save(ms: &MachineState, filename: &str) -> Result<(), String> {
+    return fs::write(filename, to_text(ms))
+            .map_err(|e| format!("{}: {}", filename, e));
+}
+
+/// Restore a machine state saved by `save`.
+pub fn restore(filename: &str) -> Result<MachineState, String> {
+    let text = fs::read_to_string(filename)
+                .map_err(|e| format!("{}: {}", filename, e))?;
+    return from_text(&text).map_err(|e| format!("{}: {}", filename, e));
+}
+
+#[test]
+fn test_snapshot() {
+    let mut ms = crate::load("3,20,3,21,4,20,4,21,1,20,21,22,4,22,99");
+    ms.push_input(5);
+    assert_eq!(crate::run(&mut ms), Ok(None));
+    ms.push_input(7);
+    ms.push_input(8);
+    let text = to_text(&ms);
+    assert_eq!(text, "pc 2\nrelative_base 0\ninput 7, 8\noutput\n\
+                      memory 0 3, 20, 3, 21, 4, 20, 4, 21, 1, 20, 21, 22, 4, 22, 99\n\
+                      memory 20 5\n");
+
+    // the restored copy and a clone both behave like the original
+    let mut restored = from_text(&text).unwrap();
+    assert_eq!(restored, ms);
+    let mut forked = ms.clone();
+    for copy in [&mut ms, &mut restored, &mut forked] {
+        assert_eq!(crate::run(copy), Ok(Some(3)));
+        assert_eq!(Vec::from(copy.output.clone()), vec![5, 7, 12]);
+        assert_eq!(Vec::from(copy.input.clone()), vec![8]);
+    }
+
+    // a saved file can be restored
+    let filename = std::env::temp_dir().join(format!("intcode_snapshot_{}", std::process::id()));
+    let filename = filename.to_str().unwrap();
+    save(&ms, filename).unwrap();
+    assert_eq!(restore(filename).unwrap(), ms);
+    fs::remove_file(filename).unwrap();
+
+    assert_eq!(from_text("pc 0\n").unwrap_err(), "relative_base is missing");
+    assert_eq!(from_text("pc 0\nrelative_base 0\nmemory 0 1, x\n").unwrap_err(),
+               "line 3: 'x' is not a number");
+    assert_eq!(from_text("pc 0\nrelative_base 0\nregister 1\n").unwrap_err(),
+               "line 3: unknown item 'register'");
+    assert_eq!(from_text("pc 0\nrelative_base 0\nmemory 9223372036854775807 1, 2\n").unwrap_err(),
+               "line 3: bad address 9223372036854775807 + 1");
+}
@@ -0,0 +1,240 @@
+//! Tracing and profiling Intcode execution.
+//!
+//! A `Tracer` executes instructions like `step` and `run`, counting how
+//! many times each address is executed and, optionally, writing a trace
+//! with one line per instruction: the pc, the opcode and the resolved
+//! operands. A value that is read is written as the value itself, and a
+//! value that is written is written as its address, e.g. `2 1001 5 -1 12`
+//! for `add [12] + -1 -> [12]` when `[12]` is 5.
+//!
+//! The profile is saved as lines of `address count`. `annotate` merges it
+//! with the output of `disasm` or `flow::disassemble_reachable`, adding a
+//! column with the count for each instruction, or `-` if it never ran.
+
+use std::io::{self, Write};
+use std::collections::BTreeMap;
+use crate::{Word, MachineState, Status, Fault, get_parameter_mode, load_memory, step};
+use crate::disasm::instruction_length;
+
+pub type Profile = BTreeMap<Word, u64>;
+
+pub struct Tracer {
+    pub counts: Profile,
+    trace: Option<Box<dyn Write>>,
+    error: Option<io::Error>,
+}
+
+// The opcode and operands of the instruction at pc, resolved before it
+// executes, since it may write over itself. Addresses wrap around rather
+// than overflow: if they do, the instruction faults and is not traced.
+fn resolve(ms: &MachineState) -> Vec<Word> {
+    let opcode = load_memory(ms, ms.pc);
+    let mut words: Vec<Word> = vec![opcode];
+    for index in 1 .. instruction_length(opcode) {
+        let parameter = load_memory(ms, ms.pc.wrapping_add(index));
+        let stored = match opcode % 100 {
+            1 | 2 | 7 | 8 => index == 3,
+            3 => index == 1,
+            _ => false,
+        };
+        let address = match get_parameter_mode(opcode, index) {
+            1 => { words.push(parameter); continue; },
+            2 => parameter.wrapping_add(ms.relative_base),
+            _ => parameter,
+        };
+        words.push(if stored { address } else { load_memory(ms, address) });
+    }
+    return words;
+}
+
+impl Tracer {
+    /// A tracer that only counts.
+    pub fn new() -> Self {
+        return Self {
+            counts: Profile::new(),
+            trace: None,
+            error: None,
+        };
+    }
+
+    /// A tracer that counts and also writes a trace.
+    pub fn with_trace(trace: Box<dyn Write>) -> Self {
+        let mut tracer = Self::new();
+        tracer.trace = Some(trace);
+        return tracer;
+    }
+
+    /// Execute the instruction at `pc` like `crate::step`, recording it
+    /// if it was executed.
+    pub fn step(self: &mut Self, ms: &mut MachineState) -> Result<Status, Fault> {
+        let pc = ms.pc;
+        let words = if self.trace.is_some() { resolve(ms) } else { Vec::new() };
+        let status = step(ms)?;
+        if status == Status::Blocked {
+            return Ok(status);
+        }
+        *self.counts.entry(pc).or_insert(0) += 1;
+        if let Some(trace) = self.trace.as_mut() {
+            let mut line = format!("{}", pc);
+            for word in words {
+                line.push_str(&format!(" {}", word));
+            }
+            if let Err(e) = writeln!(trace, "{}", line) {
+                // stop tracing; finish() reports the error
+                self.error = Some(e);
+                self.trace = None;
+            }
+        }
+        return Ok(status);
+    }
+
+    /// Execute instructions until the program halts or needs input,
+    /// like `crate::run`.
+    pub fn run(self: &mut Self, ms: &mut MachineState) -> Result<Option<Word>, Fault> {
+        loop {
+            match self.step(ms)? {
+                Status::Running => {},
+                Status::Blocked => {
+                    return Ok(None);
+                },
+                Status::Halted => {
+                    return Ok(Some(load_memory(ms, 0)));
+                },
+            }
+        }
+    }
+
+    /// Flush the trace, reporting any error that stopped it.
+    pub fn finish(self: &mut Self) -> io::Result<()> {
+        if let Some(e) = self.error.take() {
+            return Err(e);
+        }
+        if let Some(trace) = self.trace.as_mut() {
+            trace.flush()?;
+        }
+        return Ok(());
+    }
+}
+
+impl Default for Tracer {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+/// The profile as text, one `address count` line per executed address.
+pub fn profile_text(counts: &Profile) -> String {
+    let mut out = String::new();
+    for (address, count) in counts {
+        out.push_str(&format!("{} {}\n", address, count));
+    }
+    return out;
+}
+
+/// Read a profile written by `profile_text`.
+pub fn read_profile(text: &str) -> Result<Profile, String> {
+    let mut counts = Profile::new();
+    for (number, line) in text.lines().enumerate() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.is_empty() {
+            continue;
+        }
+        let parsed = match fields[..] {
+            [address, count] => address.parse().ok().zip(count.parse().ok()),
+            _ => None,
+        };
+        let (address, count) = parsed.ok_or(format!("line {}: expected 'address count'",
+                                                    number + 1))?;
+        counts.insert(address, count);
+    }
+    return Ok(counts);
+}
+
+/// Add a column with the execution count to each instruction line of a
+/// disassembly. Instructions that never ran are marked `-`.
+pub fn annotate(listing: &str, counts: &Profile) -> String {
+    let mut out = String::new();
+    for line in listing.lines() {
+        // instruction lines begin with the address and a tab;
+        // labels and data lines don't
+        let address: Option<Word> = match line.split_once('\t') {
+            Some((first, _)) => first.parse().ok(),
+            None => None,
+        };
+        match address {
+            Some(address) => match counts.get(&address) {
+                Some(count) => out.push_str(&format!("{:>8}\t", count)),
+                None => out.push_str(&format!("{:>8}\t", "-")),
+            },
+            None => out.push_str(&format!("{:>8}\t", "")),
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+    return out;
+}
+
+#[cfg(test)]
+struct SharedBuffer(std::rc::Rc<std::cell::RefCell<Vec<u8>>>);
+
+#[cfg(test)]
+impl Write for SharedBuffer {
+    fn write(self: &mut Self, data: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(data);
+        return Ok(data.len());
+    }
+    fn flush(self: &mut Self) -> io::Result<()> {
+        return Ok(());
+    }
+}
+
+#[test]
+fn test_tracer() {
+    let source = "
+                in      input -> [x]
+        loop:   add     [x] + -1 -> [x]
+                out     [x] -> output
+                br      if [x] != 0 goto loop
+                halt
+        x:      data    0
+    ";
+    let program = crate::asm::assemble(source).unwrap();
+    let mut ms = crate::load(&crate::asm::program_text(&program));
+    let buffer = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+    let mut tracer = Tracer::with_trace(Box::new(SharedBuffer(buffer.clone())));
+
+    // blocked instructions are not recorded
+    assert_eq!(tracer.run(&mut ms), Ok(None));
+    ms.push_input(2);
+    assert_eq!(tracer.run(&mut ms), Ok(Some(3)));
+    tracer.finish().unwrap();
+    assert_eq!(String::from_utf8(buffer.borrow().clone()).unwrap(),
+               "0 3 12\n2 1001 2 -1 12\n6 4 1\n8 1005 1 2\n\
+                2 1001 1 -1 12\n6 4 0\n8 1005 0 2\n11 99\n");
+
+    let text = profile_text(&tracer.counts);
+    assert_eq!(text, "0 1\n2 2\n6 2\n8 2\n11 1\n");
+    let counts = read_profile(&text).unwrap();
+    assert_eq!(counts, tracer.counts);
+    assert!(read_profile("1 2 3\n").is_err());
+
+    // a word that is never executed is marked, and a label line is not
+    let listing = crate::disasm::disassemble_program(&ms, 13);
+    let annotated = annotate(&format!("x:\n{}", listing), &counts);
+    let lines: Vec<&str> = annotated.lines().collect();
+    assert_eq!(lines[0], "        \tx:");
+    assert_eq!(lines[2], "       2\t2\t1001\tadd\t[12] + -1 -> [12]");
+    assert_eq!(lines[6], "       -\t12\t0\t?\t? 0");
+}
+
+#[test]
+fn test_self_modifying() {
+    // the first instruction writes over its own opcode, and is traced as
+    // it was before it executed
+    let mut ms = crate::load("1,0,0,0,99");
+    let buffer = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+    let mut tracer = Tracer::with_trace(Box::new(SharedBuffer(buffer.clone())));
+    assert_eq!(tracer.run(&mut ms), Ok(Some(2)));
+    tracer.finish().unwrap();
+    assert_eq!(String::from_utf8(buffer.borrow().clone()).unwrap(), "0 1 1 1 0\n4 99\n");
+}
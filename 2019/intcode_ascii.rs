@@ -0,0 +1,35 @@
+use std::env;
+use std::io::{self, BufRead, Write};
+
+extern crate intcode;
+use intcode::{load_from_input, run};
+use intcode::adapter::{Ascii, send_line, receive_text};
+
+
+fn main() -> Result<(), String>{
+    let args: Vec<String> = env::args().collect();
+    if args.len() != 2 {
+        return Err("Provide an intcode file name as a parameter. The program's \
+                    input is read from stdin a line at a time".to_string());
+    }
+    let mut ms = load_from_input(args.get(1).unwrap());
+    loop {
+        let rc = run(&mut ms).map_err(|f| f.to_string())?;
+        for piece in receive_text(&mut ms) {
+            match piece {
+                Ascii::Text(text) => { print!("{}", text); },
+                Ascii::Value(value) => { println!("{}", value); },
+            }
+        }
+        io::stdout().flush().unwrap();
+        if rc.is_some() {
+            break;
+        }
+        let mut line = String::new();
+        if io::stdin().lock().read_line(&mut line).unwrap() == 0 {
+            return Err("end of input, but the program is waiting for more".to_string());
+        }
+        send_line(&mut ms, line.trim_end_matches(['\r', '\n']));
+    }
+    return Ok(());
+}
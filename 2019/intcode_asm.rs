@@ -0,0 +1,17 @@
+use std::env;
+use std::fs;
+
+extern crate intcode;
+use intcode::asm::{assemble, program_text};
+
+
+fn main() -> Result<(), String>{
+    let args: Vec<String> = env::args().collect();
+    if args.len() != 2 {
+        return Err("Provide an assembly file name as a parameter".to_string());
+    }
+    let source = fs::read_to_string(args.get(1).unwrap()).map_err(|e| e.to_string())?;
+    let program = assemble(&source)?;
+    println!("{}", program_text(&program));
+    return Ok(());
+}
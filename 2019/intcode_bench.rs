@@ -0,0 +1,110 @@
+use std::env;
+use std::time::{Duration, Instant};
+
+extern crate intcode;
+use intcode::{Word, MachineState, Fault, InputOutput, load_from_input, run};
+use intcode::fast::FastMachine;
+
+// The interpreters being compared
+trait Machine {
+    fn push_input(self: &mut Self, value: Word);
+    fn run(self: &mut Self) -> Result<Option<Word>, Fault>;
+    fn output(self: &mut Self) -> &mut InputOutput;
+}
+
+impl Machine for MachineState {
+    fn push_input(self: &mut Self, value: Word) {
+        self.input.push_back(value);
+    }
+    fn run(self: &mut Self) -> Result<Option<Word>, Fault> {
+        return run(self);
+    }
+    fn output(self: &mut Self) -> &mut InputOutput {
+        return &mut self.output;
+    }
+}
+
+impl Machine for FastMachine {
+    fn push_input(self: &mut Self, value: Word) {
+        self.input.push_back(value);
+    }
+    fn run(self: &mut Self) -> Result<Option<Word>, Fault> {
+        return FastMachine::run(self);
+    }
+    fn output(self: &mut Self) -> &mut InputOutput {
+        return &mut self.output;
+    }
+}
+
+// 2019/9 part 2: the BOOST program in sensor boost mode
+fn boost(m: &mut impl Machine) -> Result<Word, Fault> {
+    m.push_input(2);
+    assert!(m.run()?.is_some());
+    return Ok(m.output().pop_front().unwrap());
+}
+
+// 2019/13 part 2: play the arcade game by following the ball with the paddle
+fn breakout(m: &mut impl Machine) -> Result<Word, Fault> {
+    let mut ball: Word = 0;
+    let mut paddle: Word = 0;
+    let mut score: Word = 0;
+    loop {
+        let halted = m.run()?.is_some();
+        let output: Vec<Word> = m.output().drain(..).collect();
+        for tile in output.chunks(3) {
+            match tile {
+                [-1, 0, value] => { score = *value; },
+                [x, _, 3] => { paddle = *x; },
+                [x, _, 4] => { ball = *x; },
+                _ => {},
+            }
+        }
+        if halted {
+            return Ok(score);
+        }
+        m.push_input((ball - paddle).signum());
+    }
+}
+
+fn time<M: Machine>(make: impl Fn() -> M, task: fn(&mut M) -> Result<Word, Fault>,
+                    repeats: u32) -> Result<(Word, Duration), Fault> {
+    let mut result: Word = 0;
+    let start = Instant::now();
+    for _ in 0 .. repeats {
+        result = task(&mut make())?;
+    }
+    return Ok((result, start.elapsed() / repeats));
+}
+
+fn compare(name: &str, filename: &str, setup: fn(&mut MachineState),
+           reference: fn(&mut MachineState) -> Result<Word, Fault>,
+           fast: fn(&mut FastMachine) -> Result<Word, Fault>,
+           repeats: u32) -> Result<(), String> {
+    let mut ms = load_from_input(filename);
+    setup(&mut ms);
+    let (r1, t1) = time(|| ms.clone(), reference, repeats).map_err(|f| f.to_string())?;
+    let (r2, t2) = time(|| FastMachine::new(&ms), fast, repeats).map_err(|f| f.to_string())?;
+    if r1 != r2 {
+        return Err(format!("{}: results differ: {} and {}", name, r1, r2));
+    }
+    println!("{}: result {}", name, r1);
+    println!("    HashMap memory    {:>10.3} ms", t1.as_secs_f64() * 1000.0);
+    println!("    FastMachine       {:>10.3} ms  ({:.1}x)", t2.as_secs_f64() * 1000.0,
+             t1.as_secs_f64() / t2.as_secs_f64());
+    return Ok(());
+}
+
+fn main() -> Result<(), String> {
+    let args: Vec<String> = env::args().collect();
+    let repeats: u32 = match args.get(1) {
+        Some(text) => text.parse().map_err(|_| "the repeat count must be a number")?,
+        None => 3,
+    };
+    if repeats == 0 {
+        return Err("the repeat count must be at least 1".to_string());
+    }
+    compare("2019/9 part 2", "9/input", |_| {}, boost, boost, repeats)?;
+    compare("2019/13 part 2", "13/input", |ms| { ms.memory.insert(0, 2); },
+            breakout, breakout, repeats)?;
+    return Ok(());
+}
@@ -0,0 +1,50 @@
+use std::env;
+
+extern crate intcode;
+use intcode::{Word, load_from_input};
+use intcode::conformance::{Case, RANDOM_LIMIT, compare, examples, implementations, random_case};
+
+
+fn main() -> Result<(), String>{
+    let args: Vec<String> = env::args().collect();
+    let usage = "Usage: intcode_conformance [--random <count>] [--limit <steps>] [<intcode file> [input ...]]\n\
+                 Runs the puzzle examples, <count> random programs (default 1000), and\n\
+                 the given program for up to <steps> instructions (default 1000000),\n\
+                 on every Intcode implementation, and reports the first place where\n\
+                 one differs from the reference.";
+    let mut count: u64 = 1000;
+    let mut limit: u64 = 1_000_000;
+    let mut index = 1;
+    loop {
+        let value = |index: usize| -> Result<u64, String> {
+            let arg = args.get(index).ok_or(usage)?;
+            return arg.parse().map_err(|_| format!("'{}' is not a number", arg));
+        };
+        match args.get(index).map(|a| a.as_str()) {
+            Some("--random") => { count = value(index + 1)?; },
+            Some("--limit") => { limit = value(index + 1)?; },
+            _ => { break; },
+        }
+        index += 2;
+    }
+
+    let mut cases: Vec<(Case, u64)> = examples().into_iter().map(|c| (c, limit)).collect();
+    cases.extend((0 .. count).map(|seed| (random_case(seed), RANDOM_LIMIT)));
+    if let Some(filename) = args.get(index) {
+        let mut ms = load_from_input(filename);
+        for arg in &args[index + 1 ..] {
+            let value: Word = arg.parse().map_err(|_| format!("'{}' is not a number", arg))?;
+            ms.push_input(value);
+        }
+        cases.push((Case { name: filename.clone(), ms: ms }, limit));
+    }
+
+    let implementations = implementations();
+    let mut steps: u64 = 0;
+    for (case, limit) in &cases {
+        steps += compare(case, &implementations, *limit).map_err(|d| d.to_string())?;
+    }
+    println!("{} programs, {} steps: all {} implementations agree",
+             cases.len(), steps, implementations.len());
+    return Ok(());
+}
@@ -0,0 +1,47 @@
+use std::env;
+use std::io::{self, BufRead, Write};
+
+extern crate intcode;
+use intcode::load_from_input;
+use intcode::debug::Debugger;
+
+
+fn main() -> Result<(), String>{
+    let args: Vec<String> = env::args().collect();
+    if args.len() != 2 {
+        return Err("Provide an intcode file name as a parameter".to_string());
+    }
+    let mut ms = load_from_input(args.get(1).unwrap());
+    let mut debugger = Debugger::new();
+    let mut previous = String::new();
+
+    println!("{}", debugger.list(&ms, ms.pc, 0, 1));
+    loop {
+        print!("(debug) ");
+        io::stdout().flush().unwrap();
+        let mut line = String::new();
+        if io::stdin().lock().read_line(&mut line).unwrap() == 0 {
+            break;
+        }
+        // an empty line repeats the previous command
+        if line.trim().is_empty() {
+            line = previous.clone();
+        }
+        match line.trim() {
+            "q" | "quit" => { break; },
+            _ => {},
+        }
+        match debugger.command(&mut ms, &line) {
+            Ok(text) => {
+                if !text.is_empty() {
+                    println!("{}", text);
+                }
+            },
+            Err(text) => {
+                println!("error: {}", text);
+            },
+        }
+        previous = line;
+    }
+    return Ok(());
+}
@@ -0,0 +1,18 @@
+use std::env;
+
+extern crate intcode;
+use intcode::{Word, load_from_input};
+use intcode::decompile::decompile;
+
+
+fn main() -> Result<(), String>{
+    let args: Vec<String> = env::args().collect();
+    let usage = "Provide an intcode file name as a parameter: the program is written\n\
+                 as C-like pseudocode, with each function that it calls";
+    if args.len() != 2 {
+        return Err(usage.to_string());
+    }
+    let ms = load_from_input(&args[1]);
+    print!("{}", decompile(&ms, ms.memory.len() as Word));
+    return Ok(());
+}
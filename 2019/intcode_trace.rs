@@ -0,0 +1,61 @@
+use std::env;
+use std::fs::{self, File};
+use std::io::BufWriter;
+
+extern crate intcode;
+use intcode::{Word, load_from_input};
+use intcode::trace::{Tracer, profile_text};
+
+
+fn main() -> Result<(), String>{
+    let args: Vec<String> = env::args().collect();
+    let usage = "Usage: intcode_trace [--trace <file>] [--profile <file>] <intcode file> [input ...]\n\
+                 Runs the program with the given input values until it halts or needs\n\
+                 more input, and prints its output. --trace writes each instruction\n\
+                 executed and --profile writes the execution count of each address.";
+    let mut trace: Option<&String> = None;
+    let mut profile: Option<&String> = None;
+    let mut index = 1;
+    loop {
+        match args.get(index).map(|a| a.as_str()) {
+            Some("--trace") if trace.is_none() => {
+                trace = Some(args.get(index + 1).ok_or(usage)?);
+            },
+            Some("--profile") if profile.is_none() => {
+                profile = Some(args.get(index + 1).ok_or(usage)?);
+            },
+            _ => { break; },
+        }
+        index += 2;
+    }
+    let filename = args.get(index).ok_or(usage)?;
+    let mut ms = load_from_input(filename);
+    for arg in &args[index + 1 ..] {
+        let value: Word = arg.parse().map_err(|_| format!("'{}' is not a number", arg))?;
+        ms.push_input(value);
+    }
+
+    let mut tracer = match trace {
+        Some(trace) => {
+            let file = File::create(trace).map_err(|e| format!("{}: {}", trace, e))?;
+            Tracer::with_trace(Box::new(BufWriter::new(file)))
+        },
+        None => Tracer::new(),
+    };
+    let result = tracer.run(&mut ms);
+    if let Some(trace) = trace {
+        tracer.finish().map_err(|e| format!("{}: {}", trace, e))?;
+    }
+    if let Some(profile) = profile {
+        fs::write(profile, profile_text(&tracer.counts))
+            .map_err(|e| format!("{}: {}", profile, e))?;
+    }
+    let output: Vec<String> = ms.output.iter().map(|v| v.to_string()).collect();
+    println!("{}", output.join(","));
+    match result {
+        Ok(Some(_)) => {},
+        Ok(None) => { eprintln!("stopped at {}: waiting for input", ms.pc); },
+        Err(fault) => { return Err(fault.to_string()); },
+    }
+    return Ok(());
+}
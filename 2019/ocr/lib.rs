@@ -0,0 +1,141 @@
+//! Reading the block letters that some puzzles draw as their answer.
+//!
+//! Two fonts are known: letters 6 pixels high (most are 4 pixels wide)
+//! and letters 10 pixels high and 6 wide. Letters are separated by
+//! columns with nothing lit; blank rows and columns around the text are
+//! ignored, so the grid can be any size.
+
+// Each letter is drawn with '#' for lit pixels, one row per line
+const SMALL: [(char, &str); 18] = [
+    ('A', ".##.\n#..#\n#..#\n####\n#..#\n#..#"),
+    ('B', "###.\n#..#\n###.\n#..#\n#..#\n###."),
+    ('C', ".##.\n#..#\n#...\n#...\n#..#\n.##."),
+    ('E', "####\n#...\n###.\n#...\n#...\n####"),
+    ('F', "####\n#...\n###.\n#...\n#...\n#..."),
+    ('G', ".##.\n#..#\n#...\n#.##\n#..#\n.###"),
+    ('H', "#..#\n#..#\n####\n#..#\n#..#\n#..#"),
+    ('I', "###\n.#.\n.#.\n.#.\n.#.\n###"),
+    ('J', "..##\n...#\n...#\n...#\n#..#\n.##."),
+    ('K', "#..#\n#.#.\n##..\n#.#.\n#.#.\n#..#"),
+    ('L', "#...\n#...\n#...\n#...\n#...\n####"),
+    ('O', ".##.\n#..#\n#..#\n#..#\n#..#\n.##."),
+    ('P', "###.\n#..#\n#..#\n###.\n#...\n#..."),
+    ('R', "###.\n#..#\n#..#\n###.\n#.#.\n#..#"),
+    ('S', ".###\n#...\n#...\n.##.\n...#\n###."),
+    ('U', "#..#\n#..#\n#..#\n#..#\n#..#\n.##."),
+    ('Y', "#...#\n#...#\n.#.#.\n..#..\n..#..\n..#.."),
+    ('Z', "####\n...#\n..#.\n.#..\n#...\n####"),
+];
+
+const LARGE: [(char, &str); 15] = [
+    ('A', "..##..\n.#..#.\n#....#\n#....#\n#....#\n######\n#....#\n#....#\n#....#\n#....#"),
+    ('B', "#####.\n#....#\n#....#\n#....#\n#####.\n#....#\n#....#\n#....#\n#....#\n#####."),
+    ('C', ".####.\n#....#\n#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n#....#\n.####."),
+    ('E', "######\n#.....\n#.....\n#.....\n#####.\n#.....\n#.....\n#.....\n#.....\n######"),
+    ('F', "######\n#.....\n#.....\n#.....\n#####.\n#.....\n#.....\n#.....\n#.....\n#....."),
+    ('G', ".####.\n#....#\n#.....\n#.....\n#.....\n#..###\n#....#\n#....#\n#...##\n.###.#"),
+    ('H', "#....#\n#....#\n#....#\n#....#\n######\n#....#\n#....#\n#....#\n#....#\n#....#"),
+    ('J', "...###\n....#.\n....#.\n....#.\n....#.\n....#.\n....#.\n#...#.\n#...#.\n.###.."),
+    ('K', "#....#\n#...#.\n#..#..\n#.#...\n##....\n##....\n#.#...\n#..#..\n#...#.\n#....#"),
+    ('L', "#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n######"),
+    ('N', "#....#\n##...#\n##...#\n#.#..#\n#.#..#\n#..#.#\n#..#.#\n#...##\n#...##\n#....#"),
+    ('P', "#####.\n#....#\n#....#\n#....#\n#####.\n#.....\n#.....\n#.....\n#.....\n#....."),
+    ('R', "#####.\n#....#\n#....#\n#....#\n#####.\n#..#..\n#...#.\n#...#.\n#....#\n#....#"),
+    ('X', "#....#\n#....#\n.#..#.\n.#..#.\n..##..\n..##..\n.#..#.\n.#..#.\n#....#\n#....#"),
+    ('Z', "######\n.....#\n.....#\n....#.\n...#..\n..#...\n.#....\n#.....\n#.....\n######"),
+];
+
+fn draw(grid: &[Vec<bool>], columns: std::ops::Range<usize>) -> String {
+    let rows: Vec<String> = grid.iter().map(|row| {
+        columns.clone().map(|x| if row[x] { '#' } else { '.' }).collect()
+    }).collect();
+    return rows.join("\n");
+}
+
+/// Read the letters drawn in `grid`, which is a list of rows with `true`
+/// for lit pixels.
+pub fn recognise(grid: &[Vec<bool>]) -> Result<String, String> {
+    let width = grid.iter().map(|row| row.len()).max().unwrap_or(0);
+    let lit = |x: usize, y: usize| *grid[y].get(x).unwrap_or(&false);
+
+    // remove blank rows from the top and bottom
+    let top = (0 .. grid.len()).find(|y| (0 .. width).any(|x| lit(x, *y)));
+    let top = match top {
+        Some(top) => top,
+        None => { return Ok(String::new()); },
+    };
+    let bottom = (0 .. grid.len()).rev().find(|y| (0 .. width).any(|x| lit(x, *y))).unwrap();
+    let rows: Vec<Vec<bool>> = (top ..= bottom).map(|y| {
+        (0 .. width).map(|x| lit(x, y)).collect()
+    }).collect();
+    let font: &[(char, &str)] = match rows.len() {
+        6 => &SMALL,
+        10 => &LARGE,
+        height => { return Err(format!("letters {} pixels high are not known", height)); },
+    };
+
+    // split into letters at blank columns
+    let mut text = String::new();
+    let blank = |x: usize| rows.iter().all(|row| !row[x]);
+    let mut x = 0;
+    while x < width {
+        if blank(x) {
+            x += 1;
+            continue;
+        }
+        let start = x;
+        while x < width && !blank(x) {
+            x += 1;
+        }
+        let glyph = draw(&rows, start .. x);
+        match font.iter().find(|(_, drawing)| *drawing == glyph) {
+            Some((letter, _)) => { text.push(*letter); },
+            None => {
+                return Err(format!("unknown letter at column {}:\n{}", start, glyph));
+            },
+        }
+    }
+    return Ok(text);
+}
+
+/// Read letters drawn as text, with `#` for lit pixels.
+pub fn recognise_text(text: &str) -> Result<String, String> {
+    let grid: Vec<Vec<bool>> = text.lines().map(|line| {
+        line.chars().map(|c| c == '#').collect()
+    }).collect();
+    return recognise(&grid);
+}
+
+#[test]
+fn test_recognise() {
+    // every letter of each font
+    for font in [&SMALL[..], &LARGE[..]] {
+        let mut rows: Vec<String> = vec![String::new(); font[0].1.lines().count()];
+        for (_, drawing) in font {
+            for (row, line) in rows.iter_mut().zip(drawing.lines()) {
+                row.push_str(line);
+                row.push('.');
+            }
+        }
+        let expected: String = font.iter().map(|(letter, _)| *letter).collect();
+        assert_eq!(recognise_text(&rows.join("\n")), Ok(expected));
+    }
+
+    // 2022/10 example layout, with a margin
+    let text = "
+        ..........................
+        .####.#..#..##..###..###..
+        .#....#.#..#..#.#..#.#..#.
+        .###..##...#....#..#.#..#.
+        .#....#.#..#.##.###..###..
+        .#....#.#..#..#.#....#.#..
+        .#....#..#..###.#....#..#.
+        ..........................";
+    let trimmed: Vec<&str> = text.lines().map(|line| line.trim()).collect();
+    assert_eq!(recognise_text(&trimmed.join("\n")), Ok("FKGPR".to_string()));
+
+    assert_eq!(recognise_text(""), Ok(String::new()));
+    assert!(recognise_text("#\n#\n#").unwrap_err().contains("3 pixels high"));
+    assert_eq!(recognise_text("#..#\n#..#\n#..#\n####\n#..#\n#..#").unwrap_err(),
+               "unknown letter at column 0:\n#..#\n#..#\n#..#\n####\n#..#\n#..#");
+}
@@ -0,0 +1,321 @@
+//! A tree of objects, each orbiting at most one other, as in 2019/6.
+//!
+//! The map is given as lines like `COM)B`, meaning that B orbits COM.
+//! There must be exactly one object that orbits nothing (the root), and
+//! no object may orbit two others or, indirectly, itself; `Space::parse`
+//! reports any of these problems.
+//!
+//! Objects are kept in a `Vec` and refer to each other by index, so the
+//! queries need no pointers. They take object names and return None for
+//! names that are not in the map.
+
+use std::collections::HashMap;
+use std::collections::VecDeque;
+
+pub struct Space {
+    names: Vec<String>,
+    index: HashMap<String, usize>,
+    parent: Vec<Option<usize>>,
+    children: Vec<Vec<usize>>,
+    root: usize,
+    depth: Vec<usize>,
+    size: Vec<usize>,
+}
+
+impl Space {
+    /// Read a map with one `planet)satellite` pair on each line.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut links: Vec<(&str, &str)> = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            match line.split_once(')') {
+                Some((planet, satellite)) if !planet.is_empty() && !satellite.is_empty() => {
+                    links.push((planet, satellite));
+                },
+                _ => {
+                    return Err(format!("line {}: '{}' should be two names separated by ')'",
+                                       number + 1, line));
+                },
+            }
+        }
+        return Self::from_links(&links);
+    }
+
+    /// Build the map from (planet, satellite) pairs.
+    pub fn from_links(links: &[(&str, &str)]) -> Result<Self, String> {
+        if links.is_empty() {
+            return Err("there are no orbits".to_string());
+        }
+        let mut names: Vec<String> = Vec::new();
+        let mut index: HashMap<String, usize> = HashMap::new();
+        let mut parent: Vec<Option<usize>> = Vec::new();
+        let mut lookup = |name: &str, parent: &mut Vec<Option<usize>>| -> usize {
+            if let Some(i) = index.get(name) {
+                return *i;
+            }
+            index.insert(name.to_string(), names.len());
+            names.push(name.to_string());
+            parent.push(None);
+            return names.len() - 1;
+        };
+        let mut pairs: Vec<(usize, usize)> = Vec::new();
+        for (planet, satellite) in links {
+            let p = lookup(planet, &mut parent);
+            let s = lookup(satellite, &mut parent);
+            pairs.push((p, s));
+        }
+        for (p, s) in pairs {
+            match parent[s] {
+                Some(q) if q != p => {
+                    return Err(format!("{} orbits both {} and {}", names[s], names[q], names[p]));
+                },
+                _ => { parent[s] = Some(p); },
+            }
+        }
+
+        let mut roots: Vec<&str> = (0 .. names.len()).filter(|i| parent[*i].is_none())
+            .map(|i| names[i].as_str()).collect();
+        roots.sort();
+        if roots.len() > 1 {
+            return Err(format!("there is more than one root: {}", roots.join(", ")));
+        }
+        let mut children: Vec<Vec<usize>> = vec![Vec::new(); names.len()];
+        for (s, p) in parent.iter().enumerate() {
+            if let Some(p) = p {
+                children[*p].push(s);
+            }
+        }
+        for list in children.iter_mut() {
+            list.sort_by(|a, b| names[*a].cmp(&names[*b]));
+        }
+
+        // visit from the root, parents before children; anything not
+        // visited is in a cycle, or orbits one
+        let mut order: Vec<usize> = Vec::new();
+        let mut depth: Vec<usize> = vec![0; names.len()];
+        let mut todo: VecDeque<usize> = VecDeque::new();
+        let root = index.get(roots.first().copied().unwrap_or("")).copied();
+        todo.extend(root);
+        while let Some(i) = todo.pop_front() {
+            order.push(i);
+            for c in &children[i] {
+                depth[*c] = depth[i] + 1;
+                todo.push_back(*c);
+            }
+        }
+        if order.len() < names.len() {
+            return Err(Self::cycle(&names, &parent, &order));
+        }
+
+        let mut size: Vec<usize> = vec![1; names.len()];
+        for i in order.iter().rev() {
+            if let Some(p) = parent[*i] {
+                size[p] += size[*i];
+            }
+        }
+        return Ok(Space {
+            names: names,
+            index: index,
+            parent: parent,
+            children: children,
+            root: root.unwrap(),
+            depth: depth,
+            size: size,
+        });
+    }
+
+    // Describe a cycle among the objects that were not visited
+    fn cycle(names: &[String], parent: &[Option<usize>], visited: &[usize]) -> String {
+        let mut seen: Vec<bool> = vec![false; names.len()];
+        for i in visited {
+            seen[*i] = true;
+        }
+        // every unvisited object has a parent, so following them from one
+        // must eventually come back round
+        let mut i = (0 .. names.len()).filter(|i| !seen[*i]).min_by_key(|i| &names[*i]).unwrap();
+        let mut path: Vec<usize> = Vec::new();
+        while !path.contains(&i) {
+            path.push(i);
+            i = parent[i].unwrap();
+        }
+        let start = path.iter().position(|j| *j == i).unwrap();
+        let mut cycle: Vec<&str> = path[start ..].iter().map(|j| names[*j].as_str()).collect();
+        cycle.push(&names[i]);
+        return format!("the orbits form a cycle: {}", cycle.join(" orbits "));
+    }
+
+    fn find(self: &Self, name: &str) -> Option<usize> {
+        return self.index.get(name).copied();
+    }
+
+    fn names(self: &Self, indexes: &[usize]) -> Vec<&str> {
+        return indexes.iter().map(|i| self.names[*i].as_str()).collect();
+    }
+
+    /// The object that orbits nothing.
+    pub fn root(self: &Self) -> &str {
+        return &self.names[self.root];
+    }
+
+    /// The number of objects, including the root.
+    pub fn count_objects(self: &Self) -> usize {
+        return self.names.len();
+    }
+
+    pub fn contains(self: &Self, name: &str) -> bool {
+        return self.index.contains_key(name);
+    }
+
+    /// The object that `name` orbits directly.
+    pub fn parent(self: &Self, name: &str) -> Option<&str> {
+        return self.parent[self.find(name)?].map(|p| self.names[p].as_str());
+    }
+
+    /// The objects that orbit `name` directly, in name order.
+    pub fn children(self: &Self, name: &str) -> Option<Vec<&str>> {
+        return Some(self.names(&self.children[self.find(name)?]));
+    }
+
+    /// The number of objects that `name` orbits, directly or indirectly.
+    pub fn depth(self: &Self, name: &str) -> Option<usize> {
+        return Some(self.depth[self.find(name)?]);
+    }
+
+    /// The objects that `name` orbits, directly or indirectly, starting
+    /// with its parent and ending with the root.
+    pub fn ancestors(self: &Self, name: &str) -> Option<Vec<&str>> {
+        let mut i = self.find(name)?;
+        let mut result: Vec<&str> = Vec::new();
+        while let Some(p) = self.parent[i] {
+            result.push(&self.names[p]);
+            i = p;
+        }
+        return Some(result);
+    }
+
+    /// The number of objects orbiting `name` directly or indirectly, plus
+    /// one for `name` itself.
+    pub fn subtree_size(self: &Self, name: &str) -> Option<usize> {
+        return Some(self.size[self.find(name)?]);
+    }
+
+    fn lowest_common_ancestor(self: &Self, mut a: usize, mut b: usize) -> usize {
+        while self.depth[a] > self.depth[b] {
+            a = self.parent[a].unwrap();
+        }
+        while self.depth[b] > self.depth[a] {
+            b = self.parent[b].unwrap();
+        }
+        while a != b {
+            a = self.parent[a].unwrap();
+            b = self.parent[b].unwrap();
+        }
+        return a;
+    }
+
+    /// The deepest object that both `a` and `b` orbit, or are. If one of
+    /// them orbits the other, that is the other.
+    pub fn common_ancestor(self: &Self, a: &str, b: &str) -> Option<&str> {
+        let lca = self.lowest_common_ancestor(self.find(a)?, self.find(b)?);
+        return Some(&self.names[lca]);
+    }
+
+    /// The objects on the way from `a` to `b`, including both.
+    pub fn path(self: &Self, a: &str, b: &str) -> Option<Vec<&str>> {
+        let (mut a, mut b) = (self.find(a)?, self.find(b)?);
+        let lca = self.lowest_common_ancestor(a, b);
+        let mut up: Vec<usize> = vec![a];
+        while a != lca {
+            a = self.parent[a].unwrap();
+            up.push(a);
+        }
+        let mut down: Vec<usize> = Vec::new();
+        while b != lca {
+            down.push(b);
+            b = self.parent[b].unwrap();
+        }
+        up.extend(down.iter().rev());
+        return Some(self.names(&up));
+    }
+
+    /// The total number of direct and indirect orbits.
+    pub fn count_transitive(self: &Self) -> usize {
+        return self.depth.iter().sum();
+    }
+
+    /// The map in Graphviz format, with an arrow from each object to the
+    /// objects orbiting it.
+    pub fn to_dot(self: &Self) -> String {
+        let quote = |i: usize| format!("\"{}\"", self.names[i].replace('\\', "\\\\").replace('"', "\\\""));
+        let mut out = String::from("digraph orbits {\n");
+        out.push_str(&format!("    {} [shape=doublecircle];\n", quote(self.root)));
+        let mut todo: Vec<usize> = vec![self.root];
+        while let Some(i) = todo.pop() {
+            for c in &self.children[i] {
+                out.push_str(&format!("    {} -> {};\n", quote(i), quote(*c)));
+            }
+            todo.extend(self.children[i].iter().rev());
+        }
+        out.push_str("}\n");
+        return out;
+    }
+}
+
+#[test]
+fn test_queries() {
+    let space = Space::parse("COM)B\nB)C\nC)D\nD)E\nE)F\nB)G\nG)H\nD)I\nE)J\nJ)K\nK)L\n\
+                              K)YOU\nI)SAN\n").unwrap();
+    assert_eq!(space.count_transitive(), 54);
+    assert_eq!(space.root(), "COM");
+    assert_eq!(space.count_objects(), 14);
+    assert!(space.contains("SAN") && !space.contains("Z"));
+    assert_eq!(space.depth("L"), Some(7));
+    assert_eq!(space.depth("COM"), Some(0));
+    assert_eq!(space.depth("Z"), None);
+    assert_eq!(space.parent("YOU"), Some("K"));
+    assert_eq!(space.parent("COM"), None);
+    assert_eq!(space.children("E"), Some(vec!["F", "J"]));
+    assert_eq!(space.ancestors("D"), Some(vec!["C", "B", "COM"]));
+    assert_eq!(space.subtree_size("D"), Some(9));
+    assert_eq!(space.subtree_size("COM"), Some(14));
+    assert_eq!(space.common_ancestor("YOU", "SAN"), Some("D"));
+    assert_eq!(space.common_ancestor("E", "L"), Some("E"));
+    assert_eq!(space.common_ancestor("H", "H"), Some("H"));
+    assert_eq!(space.path("YOU", "SAN"), Some(vec!["YOU", "K", "J", "E", "D", "I", "SAN"]));
+    assert_eq!(space.path("C", "F"), Some(vec!["C", "D", "E", "F"]));
+    assert_eq!(space.path("F", "C"), Some(vec!["F", "E", "D", "C"]));
+    assert_eq!(space.path("G", "G"), Some(vec!["G"]));
+    assert_eq!(space.path("G", "Z"), None);
+}
+
+#[test]
+fn test_errors() {
+    assert_eq!(Space::parse("A)B\nB)C\nX)Y").err(),
+               Some("there is more than one root: A, X".to_string()));
+    assert_eq!(Space::parse("A)B\nB)C\nD)C").err(), Some("C orbits both B and D".to_string()));
+    assert_eq!(Space::parse("A)B\nB)C\nC)D\nE)C\nD)E").err(),
+               Some("C orbits both B and E".to_string()));
+    assert_eq!(Space::parse("A)B\nC)D\nD)E\nE)C").err(),
+               Some("the orbits form a cycle: C orbits E orbits D orbits C".to_string()));
+    assert_eq!(Space::parse("A)B\nB)A").err(),
+               Some("the orbits form a cycle: A orbits B orbits A".to_string()));
+    assert_eq!(Space::parse("A)A").err(), Some("the orbits form a cycle: A orbits A".to_string()));
+    assert_eq!(Space::parse("A)B\nAB\n").err(),
+               Some("line 2: 'AB' should be two names separated by ')'".to_string()));
+    assert_eq!(Space::parse("A)").err(),
+               Some("line 1: 'A)' should be two names separated by ')'".to_string()));
+    assert_eq!(Space::parse("\n").err(), Some("there are no orbits".to_string()));
+    // the same orbit twice is allowed
+    assert_eq!(Space::parse("A)B\nA)B").unwrap().count_objects(), 2);
+}
+
+#[test]
+fn test_dot() {
+    let space = Space::parse("COM)B\nB)C\nB)\"G\"\nC)D\n").unwrap();
+    assert_eq!(space.to_dot(), "digraph orbits {\n    \"COM\" [shape=doublecircle];\n    \
+                                \"COM\" -> \"B\";\n    \"B\" -> \"\\\"G\\\"\";\n    \"B\" -> \"C\";\n    \
+                                \"C\" -> \"D\";\n}\n");
+}
@@ -0,0 +1,436 @@
+//! Counting and finding strings that follow a set of rules, such as the
+//! passwords in 2019/4 and 2015/11.
+//!
+//! Each `Rule` reads a string one symbol at a time, keeping a small state,
+//! and can reject it as soon as no string starting that way could be
+//! valid. `Rules` combines some rules with an alphabet. Because the rules
+//! only see their own state, the number of valid strings with a given
+//! prefix depends only on the length remaining and the states after the
+//! prefix; `Rules` counts those once each (digit dynamic programming), so
+//! ranges of strings with 18 or more digits are counted as quickly as
+//! short ones, without trying each string.
+
+use std::collections::HashMap;
+
+/// A test applied to a string, one symbol at a time.
+pub trait Rule {
+    /// The state before the first symbol.
+    fn start(self: &Self) -> u64 {
+        return 0;
+    }
+    /// The state after `symbol`, or None if no string beginning this way
+    /// can be valid.
+    fn next(self: &Self, state: u64, symbol: u8) -> Option<u64>;
+    /// Whether a whole string ending in `state` is valid.
+    fn accept(self: &Self, state: u64) -> bool;
+}
+
+/// Each symbol is the same as the one before or comes after it,
+/// like 111123 or 135679.
+pub struct NonDecreasing;
+
+impl Rule for NonDecreasing {
+    // the state is one more than the last symbol, or 0 at the start
+    fn next(self: &Self, state: u64, symbol: u8) -> Option<u64> {
+        if symbol as u64 + 1 < state {
+            return None;
+        }
+        return Some(symbol as u64 + 1);
+    }
+    fn accept(self: &Self, _: u64) -> bool {
+        return true;
+    }
+}
+
+/// Some run of the same symbol repeated is between `min` and `max` long,
+/// like 22 in 122345.
+pub struct Run {
+    min: u64,
+    max: u64,
+}
+
+impl Run {
+    pub fn at_least(min: u64) -> Self {
+        return Run { min: min, max: u64::MAX };
+    }
+
+    pub fn exactly(length: u64) -> Self {
+        return Run { min: length, max: length };
+    }
+
+    // run lengths beyond this all count the same
+    fn cap(self: &Self) -> u64 {
+        return if self.max == u64::MAX { self.min } else { self.max + 1 };
+    }
+
+    fn fits(self: &Self, length: u64) -> bool {
+        return self.min <= length && length <= self.max;
+    }
+}
+
+impl Rule for Run {
+    // bit 0: a run has fitted; bits 1-8: the last symbol;
+    // above those: the length of the current run
+    fn next(self: &Self, state: u64, symbol: u8) -> Option<u64> {
+        let mut met = state & 1;
+        let last = (state >> 1) & 0xff;
+        let mut length = state >> 9;
+        if length > 0 && last == symbol as u64 {
+            length = u64::min(length + 1, self.cap());
+        } else {
+            if self.fits(length) {
+                met = 1;
+            }
+            length = 1;
+        }
+        return Some(met | ((symbol as u64) << 1) | (length << 9));
+    }
+    fn accept(self: &Self, state: u64) -> bool {
+        return (state & 1) == 1 || self.fits(state >> 9);
+    }
+}
+
+/// None of the given symbols appear.
+pub struct Forbidden {
+    symbols: Vec<u8>,
+}
+
+impl Forbidden {
+    pub fn new(symbols: &str) -> Self {
+        return Forbidden { symbols: symbols.bytes().collect() };
+    }
+}
+
+impl Rule for Forbidden {
+    fn next(self: &Self, state: u64, symbol: u8) -> Option<u64> {
+        if self.symbols.contains(&symbol) {
+            return None;
+        }
+        return Some(state);
+    }
+    fn accept(self: &Self, _: u64) -> bool {
+        return true;
+    }
+}
+
+/// Somewhere there are `length` symbols in a row that each come straight
+/// after the one before, like abc or 345; abd doesn't count.
+pub struct Straight(pub u64);
+
+impl Rule for Straight {
+    // bit 0: a straight was found; bits 1-8: the last symbol;
+    // above those: the length of the current straight
+    fn next(self: &Self, state: u64, symbol: u8) -> Option<u64> {
+        let mut met = state & 1;
+        let last = (state >> 1) & 0xff;
+        let mut length = state >> 9;
+        if length > 0 && last + 1 == symbol as u64 {
+            length = u64::min(length + 1, self.0);
+        } else {
+            length = 1;
+        }
+        if length >= self.0 {
+            met = 1;
+        }
+        return Some(met | ((symbol as u64) << 1) | (length << 9));
+    }
+    fn accept(self: &Self, state: u64) -> bool {
+        return (state & 1) == 1;
+    }
+}
+
+/// At least `count` different symbols each appear twice in a row, like
+/// aa and bb in aabcbb. As the pairs are different they can't overlap.
+pub struct Pairs {
+    count: u64,
+}
+
+impl Pairs {
+    /// `count` can be up to 7.
+    pub fn new(count: u64) -> Self {
+        assert!(count <= 7);
+        return Pairs { count: count };
+    }
+}
+
+impl Rule for Pairs {
+    // bits 0-7: the last symbol; bits 8-15: the number of different pairs
+    // found, up to `count`; above those, the symbols paired so far, one
+    // per byte, until there are enough
+    fn next(self: &Self, state: u64, symbol: u8) -> Option<u64> {
+        let last = state & 0xff;
+        let mut found = (state >> 8) & 0xff;
+        let mut paired = state >> 16;
+        if found < self.count && last == symbol as u64 &&
+                (0 .. found).all(|i| (paired >> (8 * i)) & 0xff != symbol as u64) {
+            found += 1;
+            if found == self.count {
+                paired = 0;
+            } else {
+                paired |= (symbol as u64) << (8 * (found - 1));
+            }
+        }
+        return Some((symbol as u64) | (found << 8) | (paired << 16));
+    }
+    fn accept(self: &Self, state: u64) -> bool {
+        return (state >> 8) & 0xff >= self.count;
+    }
+}
+
+/// An alphabet and the rules that strings made from it must follow.
+pub struct Rules {
+    alphabet: Vec<u8>,
+    rules: Vec<Box<dyn Rule>>,
+}
+
+type States = Vec<u64>;
+
+impl Rules {
+    pub fn new(alphabet: impl IntoIterator<Item = u8>, rules: Vec<Box<dyn Rule>>) -> Self {
+        let mut alphabet: Vec<u8> = alphabet.into_iter().collect();
+        alphabet.sort();
+        alphabet.dedup();
+        return Rules { alphabet: alphabet, rules: rules };
+    }
+
+    fn start(self: &Self) -> States {
+        return self.rules.iter().map(|rule| rule.start()).collect();
+    }
+
+    fn next(self: &Self, states: &States, symbol: u8) -> Option<States> {
+        return self.rules.iter().zip(states).map(|(rule, state)| rule.next(*state, symbol)).collect();
+    }
+
+    fn accept(self: &Self, states: &States) -> bool {
+        return self.rules.iter().zip(states).all(|(rule, state)| rule.accept(*state));
+    }
+
+    // Whether `symbols` is valid
+    fn accept_all(self: &Self, symbols: &[u8]) -> bool {
+        let mut states = self.start();
+        for symbol in symbols {
+            states = match self.next(&states, *symbol) {
+                Some(states) => states,
+                None => { return false; },
+            };
+        }
+        return self.accept(&states);
+    }
+
+    fn symbols(self: &Self, text: &str) -> Result<Vec<u8>, String> {
+        for c in text.chars() {
+            if !c.is_ascii() || !self.alphabet.contains(&(c as u8)) {
+                return Err(format!("'{}' is not in the alphabet", c));
+            }
+        }
+        return Ok(text.bytes().collect());
+    }
+
+    pub fn is_valid(self: &Self, text: &str) -> bool {
+        return match self.symbols(text) {
+            Ok(symbols) => self.accept_all(&symbols),
+            Err(_) => false,
+        };
+    }
+
+    /// The number of valid strings from `low` to `high` inclusive, which
+    /// must be the same length.
+    pub fn count(self: &Self, low: &str, high: &str) -> Result<u128, String> {
+        let low = self.symbols(low)?;
+        let high = self.symbols(high)?;
+        if low.len() != high.len() {
+            return Err("the ends of the range should be the same length".to_string());
+        }
+        if low > high {
+            return Ok(0);
+        }
+        let mut search = Search::new(self);
+        let last = if self.accept_all(&high) { 1 } else { 0 };
+        return Ok(search.below(&high) + last - search.below(&low));
+    }
+
+    /// The first `n` valid strings after `after` and the same length as
+    /// it, in order. There are fewer if the strings run out.
+    pub fn following(self: &Self, after: &str, n: usize) -> Result<Vec<String>, String> {
+        let mut current = self.symbols(after)?;
+        let mut search = Search::new(self);
+        let mut result: Vec<String> = Vec::new();
+        while result.len() < n {
+            current = match search.after(&current) {
+                Some(next) => next,
+                None => { break; },
+            };
+            result.push(String::from_utf8(current.clone()).unwrap());
+        }
+        return Ok(result);
+    }
+}
+
+struct Search<'a> {
+    rules: &'a Rules,
+    // the number of valid ways to add some more symbols after reaching
+    // some states
+    completions: HashMap<(usize, States), u128>,
+}
+
+impl<'a> Search<'a> {
+    fn new(rules: &'a Rules) -> Self {
+        return Search { rules: rules, completions: HashMap::new() };
+    }
+
+    fn completions(self: &mut Self, remaining: usize, states: &States) -> u128 {
+        if remaining == 0 {
+            return if self.rules.accept(states) { 1 } else { 0 };
+        }
+        let key = (remaining, states.clone());
+        if let Some(count) = self.completions.get(&key) {
+            return *count;
+        }
+        let mut count: u128 = 0;
+        for symbol in self.rules.alphabet.iter() {
+            if let Some(next) = self.rules.next(states, *symbol) {
+                count += self.completions(remaining - 1, &next);
+            }
+        }
+        self.completions.insert(key, count);
+        return count;
+    }
+
+    // The number of valid strings that are the same length as `bound`
+    // and come before it
+    fn below(self: &mut Self, bound: &[u8]) -> u128 {
+        let mut count: u128 = 0;
+        let mut states = self.rules.start();
+        for (i, b) in bound.iter().enumerate() {
+            let remaining = bound.len() - i - 1;
+            for symbol in self.rules.alphabet.iter().filter(|symbol| *symbol < b) {
+                if let Some(next) = self.rules.next(&states, *symbol) {
+                    count += self.completions(remaining, &next);
+                }
+            }
+            states = match self.rules.next(&states, *b) {
+                Some(next) => next,
+                None => { break; },
+            };
+        }
+        return count;
+    }
+
+    // The first valid string that is the same length as `after` and comes
+    // after it
+    fn after(self: &mut Self, after: &[u8]) -> Option<Vec<u8>> {
+        // states after each prefix of `after`, while it could still be valid
+        let mut prefix_states: Vec<States> = vec![self.rules.start()];
+        for symbol in after {
+            match self.rules.next(prefix_states.last().unwrap(), *symbol) {
+                Some(next) => { prefix_states.push(next); },
+                None => { break; },
+            }
+        }
+        // change the last symbol possible to a larger one
+        for i in (0 .. usize::min(after.len(), prefix_states.len())).rev() {
+            let remaining = after.len() - i - 1;
+            for symbol in self.rules.alphabet.iter().filter(|symbol| **symbol > after[i]) {
+                if let Some(next) = self.rules.next(&prefix_states[i], *symbol) {
+                    if self.completions(remaining, &next) > 0 {
+                        let mut result = after[.. i].to_vec();
+                        result.push(*symbol);
+                        self.smallest(&mut result, next, remaining);
+                        return Some(result);
+                    }
+                }
+            }
+        }
+        return None;
+    }
+
+    // Add the smallest `remaining` symbols that make a valid string
+    fn smallest(self: &mut Self, result: &mut Vec<u8>, mut states: States, remaining: usize) {
+        for r in (0 .. remaining).rev() {
+            for symbol in self.rules.alphabet.iter() {
+                if let Some(next) = self.rules.next(&states, *symbol) {
+                    if self.completions(r, &next) > 0 {
+                        result.push(*symbol);
+                        states = next;
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+type RuleSet = fn() -> Vec<Box<dyn Rule>>;
+
+#[cfg(test)]
+fn digits(rules: Vec<Box<dyn Rule>>) -> Rules {
+    return Rules::new(b'0' ..= b'9', rules);
+}
+
+#[test]
+fn test_rules() {
+    let rules = digits(vec![Box::new(NonDecreasing), Box::new(Run::at_least(2))]);
+    assert!(rules.is_valid("111111"));
+    assert!(!rules.is_valid("223450"));
+    assert!(!rules.is_valid("123789"));
+    assert!(!rules.is_valid("12a789"));
+
+    let rules = digits(vec![Box::new(NonDecreasing), Box::new(Run::exactly(2))]);
+    assert!(rules.is_valid("112233"));
+    assert!(!rules.is_valid("123444"));
+    assert!(rules.is_valid("111122"));
+
+    let rules = Rules::new(b'a' ..= b'z', vec![Box::new(Straight(3)), Box::new(Forbidden::new("iol")),
+                                               Box::new(Pairs::new(2))]);
+    assert!(!rules.is_valid("hijklmmn"));
+    assert!(!rules.is_valid("abbceffg"));
+    assert!(!rules.is_valid("abbcegjk"));
+    assert!(rules.is_valid("abcdffaa"));
+    assert!(!rules.is_valid("abcdaaaa"));
+    assert!(rules.is_valid("aabbxyz"));
+}
+
+#[test]
+fn test_count() {
+    // compare with trying every string
+    let rule_sets: Vec<RuleSet> = vec![
+        || vec![Box::new(NonDecreasing), Box::new(Run::at_least(2))],
+        || vec![Box::new(NonDecreasing), Box::new(Run::exactly(2))],
+        || vec![Box::new(Run::exactly(3)), Box::new(Forbidden::new("7"))],
+        || vec![Box::new(Straight(3)), Box::new(Pairs::new(2))],
+        || vec![],
+    ];
+    for rule_set in rule_sets {
+        let rules = digits(rule_set());
+        for (low, high) in [(0, 99999), (12345, 67890), (55555, 55555), (1000, 999)] {
+            let expected = (low ..= high).filter(|n| rules.is_valid(&format!("{:05}", n))).count();
+            assert_eq!(rules.count(&format!("{:05}", low), &format!("{:05}", high)),
+                       Ok(expected as u128));
+        }
+    }
+
+    // long strings: every 20 digit non-decreasing string has a pair
+    let rules = digits(vec![Box::new(NonDecreasing), Box::new(Run::at_least(2))]);
+    assert_eq!(rules.count(&"0".repeat(20), &"9".repeat(20)), Ok(10015005));
+    let rules = digits(vec![]);
+    assert_eq!(rules.count(&"0".repeat(30), &"9".repeat(30)), Ok(10u128.pow(30)));
+
+    assert!(rules.count("12", "123").is_err());
+    assert_eq!(rules.count("1a", "20"), Err("'a' is not in the alphabet".to_string()));
+}
+
+#[test]
+fn test_following() {
+    let rules = digits(vec![Box::new(NonDecreasing), Box::new(Run::exactly(2))]);
+    assert_eq!(rules.following("1000", 4), Ok(vec!["1122".to_string(), "1123".to_string(),
+                                                  "1124".to_string(), "1125".to_string()]));
+    assert_eq!(rules.following("8889", 5), Ok(vec!["8899".to_string()]));
+
+    // compare with trying every string
+    let rules = digits(vec![Box::new(Straight(2)), Box::new(Pairs::new(1))]);
+    let mut expected: Vec<String> = (4322 ..= 9999).map(|n| n.to_string())
+        .filter(|s| rules.is_valid(s)).collect();
+    expected.truncate(100);
+    assert_eq!(rules.following("4321", 100), Ok(expected));
+}
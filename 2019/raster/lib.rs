@@ -0,0 +1,281 @@
+//! Images of grids, saved as PBM, PGM or PPM files.
+//!
+//! Several puzzles build a `HashMap` from a location to what is there.
+//! `render` draws such a map into an `Image`, using a function or a
+//! `Palette` to choose the colour of each location, and scaling each
+//! location up to a square of pixels if required. The bounding box of the
+//! locations becomes the image; locations missing from the map get the
+//! colour for `None`.
+//!
+//! Images are written in the plain (text) forms of the Netpbm formats, so
+//! that they can be compared with `diff` as well as viewed:
+//! PBM is black and white, PGM is grey and PPM is full colour. Images in
+//! these forms can be read back with `Image::decode`.
+
+use std::fs;
+use std::hash::Hash;
+use std::collections::HashMap;
+
+pub type Rgb = [u8; 3];
+
+pub const BLACK: Rgb = [0, 0, 0];
+pub const WHITE: Rgb = [255, 255, 255];
+
+/// A location in a grid: `x` increases to the right and `y` downwards.
+pub trait Position {
+    fn position(self: &Self) -> (i64, i64);
+}
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Format {
+    /// Black and white: pixels darker than mid-grey are black.
+    Pbm,
+    /// Greyscale.
+    Pgm,
+    /// Colour.
+    Ppm,
+}
+
+impl Format {
+    /// The format for a file name ending .pbm, .pgm or .ppm.
+    pub fn from_filename(filename: &str) -> Option<Self> {
+        let lower = filename.to_ascii_lowercase();
+        if lower.ends_with(".pbm") {
+            return Some(Format::Pbm);
+        } else if lower.ends_with(".pgm") {
+            return Some(Format::Pgm);
+        } else if lower.ends_with(".ppm") {
+            return Some(Format::Ppm);
+        }
+        return None;
+    }
+}
+
+/// Colours for values, with a colour for anything else.
+pub struct Palette<T> {
+    colours: HashMap<T, Rgb>,
+    other: Rgb,
+}
+
+impl<T: Eq + Hash> Palette<T> {
+    /// Values not in the palette, and missing locations, are `other`.
+    pub fn new(colours: impl IntoIterator<Item = (T, Rgb)>, other: Rgb) -> Self {
+        return Self {
+            colours: colours.into_iter().collect(),
+            other: other,
+        };
+    }
+
+    pub fn colour(self: &Self, value: Option<&T>) -> Rgb {
+        return match value {
+            Some(value) => *self.colours.get(value).unwrap_or(&self.other),
+            None => self.other,
+        };
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    /// Row by row from the top left.
+    pub pixels: Vec<Rgb>,
+}
+
+/// The brightness of a colour, from 0 for black to 255 for white.
+pub fn grey(rgb: Rgb) -> u8 {
+    // ITU-R BT.601 luma
+    let [r, g, b] = rgb.map(|c| c as u32);
+    return ((299 * r + 587 * g + 114 * b + 500) / 1000) as u8;
+}
+
+impl Image {
+    /// The image as a file in the given format.
+    pub fn encode(self: &Self, format: Format) -> String {
+        let mut out = String::new();
+        let (magic, maximum) = match format {
+            Format::Pbm => ("P1", ""),
+            Format::Pgm => ("P2", "255\n"),
+            Format::Ppm => ("P3", "255\n"),
+        };
+        out.push_str(&format!("{}\n{} {}\n{}", magic, self.width, self.height, maximum));
+        for row in self.pixels.chunks(self.width.max(1)) {
+            let values: Vec<String> = row.iter().map(|rgb| match format {
+                Format::Pbm => if grey(*rgb) < 128 { "1" } else { "0" }.to_string(),
+                Format::Pgm => grey(*rgb).to_string(),
+                Format::Ppm => format!("{} {} {}", rgb[0], rgb[1], rgb[2]),
+            }).collect();
+            out.push_str(&values.join(" "));
+            out.push('\n');
+        }
+        return out;
+    }
+
+    /// Read an image in the plain form of any of the formats. Values are
+    /// scaled to the range 0 to 255.
+    pub fn decode(text: &str) -> Result<Self, String> {
+        let mut tokens: Vec<&str> = Vec::new();
+        for line in text.lines() {
+            let data = line.split('#').next().unwrap_or("");
+            tokens.extend(data.split_whitespace());
+        }
+        let format = match tokens.first() {
+            Some(&"P1") => Format::Pbm,
+            Some(&"P2") => Format::Pgm,
+            Some(&"P3") => Format::Ppm,
+            _ => { return Err("the image should start P1, P2 or P3".to_string()); },
+        };
+        let number = |index: usize, what: &str| -> Result<usize, String> {
+            let token = tokens.get(index).ok_or(format!("the {} is missing", what))?;
+            return token.parse().map_err(|_| format!("'{}' is not a valid {}", token, what));
+        };
+        let width = number(1, "width")?;
+        let height = number(2, "height")?;
+        let (maximum, start) = match format {
+            Format::Pbm => (1, 3),
+            _ => (number(3, "maximum value")?, 4),
+        };
+        if maximum == 0 || maximum > 65535 {
+            return Err(format!("the maximum value {} should be from 1 to 65535", maximum));
+        }
+
+        // PBM pixels don't have to be separated
+        let values: Vec<&str> = match format {
+            Format::Pbm => tokens[start ..].iter().flat_map(|t| {
+                t.char_indices().map(|(i, c)| &t[i .. i + c.len_utf8()])
+            }).collect(),
+            _ => tokens[start ..].to_vec(),
+        };
+        let channels = if format == Format::Ppm { 3 } else { 1 };
+        let expected = width.checked_mul(height).and_then(|n| n.checked_mul(channels))
+            .ok_or(format!("a {} by {} image is too large", width, height))?;
+        if values.len() != expected {
+            return Err(format!("there should be {} values, not {}", expected, values.len()));
+        }
+        let mut levels: Vec<u8> = Vec::with_capacity(values.len());
+        for value in values {
+            let v: usize = value.parse().ok().filter(|v| *v <= maximum)
+                .ok_or(format!("'{}' is not a value from 0 to {}", value, maximum))?;
+            levels.push(((v * 255 + maximum / 2) / maximum) as u8);
+        }
+        let pixels: Vec<Rgb> = match format {
+            // 1 is black
+            Format::Pbm => levels.iter().map(|v| [255 - v; 3]).collect(),
+            Format::Pgm => levels.iter().map(|v| [*v; 3]).collect(),
+            Format::Ppm => levels.chunks(3).map(|c| [c[0], c[1], c[2]]).collect(),
+        };
+        return Ok(Image { width: width, height: height, pixels: pixels });
+    }
+
+    /// Save the image, choosing the format from the file name.
+    pub fn save(self: &Self, filename: &str) -> Result<(), String> {
+        let format = Format::from_filename(filename)
+            .ok_or(format!("{}: the name should end .pbm, .pgm or .ppm", filename))?;
+        return fs::write(filename, self.encode(format))
+            .map_err(|e| format!("{}: {}", filename, e));
+    }
+}
+
+/// Draw `map` with each location as a `scale` by `scale` square.
+pub fn render<L: Position, T>(map: &HashMap<L, T>, colour: impl Fn(Option<&T>) -> Rgb,
+                              scale: usize) -> Image {
+    let mut x1 = i64::MAX;
+    let mut y1 = i64::MAX;
+    let mut x2 = i64::MIN;
+    let mut y2 = i64::MIN;
+    let mut values: HashMap<(i64, i64), &T> = HashMap::new();
+    for (loc, value) in map {
+        let (x, y) = loc.position();
+        x1 = i64::min(x1, x);
+        x2 = i64::max(x2, x);
+        y1 = i64::min(y1, y);
+        y2 = i64::max(y2, y);
+        values.insert((x, y), value);
+    }
+    if values.is_empty() {
+        return Image { width: 0, height: 0, pixels: Vec::new() };
+    }
+    let width = (x2 - x1 + 1) as usize * scale;
+    let height = (y2 - y1 + 1) as usize * scale;
+    let mut pixels: Vec<Rgb> = Vec::with_capacity(width * height);
+    for py in 0 .. height {
+        for px in 0 .. width {
+            let x = x1 + (px / scale) as i64;
+            let y = y1 + (py / scale) as i64;
+            pixels.push(colour(values.get(&(x, y)).copied()));
+        }
+    }
+    return Image { width: width, height: height, pixels: pixels };
+}
+
+#[test]
+fn test_decode() {
+    let image = Image {
+        width: 3,
+        height: 2,
+        pixels: vec![BLACK, WHITE, [255, 0, 0], [1, 2, 3], WHITE, WHITE],
+    };
+    assert_eq!(Image::decode(&image.encode(Format::Ppm)), Ok(image.clone()));
+    let grey: Vec<Rgb> = image.pixels.iter().map(|p| [grey(*p); 3]).collect();
+    assert_eq!(Image::decode(&image.encode(Format::Pgm)).unwrap().pixels, grey);
+    assert_eq!(Image::decode(&image.encode(Format::Pbm)).unwrap().pixels,
+               vec![BLACK, WHITE, BLACK, BLACK, WHITE, WHITE]);
+
+    // comments, run-together PBM pixels, and other maximum values
+    assert_eq!(Image::decode("P1\n# a comment\n3 1\n101\n").unwrap().pixels,
+               vec![BLACK, WHITE, BLACK]);
+    assert_eq!(Image::decode("P2 2 1 4 0 2").unwrap().pixels, vec![BLACK, [128; 3]]);
+
+    assert_eq!(Image::decode("P6 1 1 255 0 0 0").unwrap_err(),
+               "the image should start P1, P2 or P3");
+    assert_eq!(Image::decode("P2 2 1").unwrap_err(), "the maximum value is missing");
+    assert_eq!(Image::decode("P2 2 x 3").unwrap_err(), "'x' is not a valid height");
+    assert_eq!(Image::decode("P2 2 1 3 0 1 2").unwrap_err(), "there should be 2 values, not 3");
+    assert_eq!(Image::decode("P2 2 1 3 0 4").unwrap_err(), "'4' is not a value from 0 to 3");
+    assert_eq!(Image::decode("P1 1 1 é").unwrap_err(), "'é' is not a value from 0 to 1");
+    assert_eq!(Image::decode("P1 2 1 1é").unwrap_err(), "'é' is not a value from 0 to 1");
+    assert_eq!(Image::decode("P3 4294967296 4294967296 255 0").unwrap_err(),
+               "a 4294967296 by 4294967296 image is too large");
+    assert_eq!(Image::decode("P2 6148914691236517206 3 255 0").unwrap_err(),
+               "a 6148914691236517206 by 3 image is too large");
+}
+
+#[cfg(test)]
+#[derive(Eq, PartialEq, Hash)]
+struct Location {
+    x: i64,
+    y: i64,
+}
+
+#[cfg(test)]
+impl Position for Location {
+    fn position(self: &Self) -> (i64, i64) {
+        return (self.x, self.y);
+    }
+}
+
+#[test]
+fn test_render() {
+    let mut map: HashMap<Location, char> = HashMap::new();
+    map.insert(Location { x: -1, y: 5 }, '#');
+    map.insert(Location { x: 1, y: 5 }, 'o');
+    map.insert(Location { x: 0, y: 6 }, '.');
+    let palette = Palette::new([('#', BLACK), ('o', [255, 0, 0])], WHITE);
+
+    let image = render(&map, |v| palette.colour(v), 1);
+    assert_eq!((image.width, image.height), (3, 2));
+    assert_eq!(image.encode(Format::Pbm), "P1\n3 2\n1 0 1\n0 0 0\n");
+    assert_eq!(image.encode(Format::Pgm), "P2\n3 2\n255\n0 255 76\n255 255 255\n");
+    assert_eq!(image.encode(Format::Ppm),
+               "P3\n3 2\n255\n0 0 0 255 255 255 255 0 0\n\
+                255 255 255 255 255 255 255 255 255\n");
+
+    let image = render(&map, |v| if v == Some(&'#') { BLACK } else { WHITE }, 2);
+    assert_eq!(image.encode(Format::Pbm), "P1\n6 4\n1 1 0 0 0 0\n1 1 0 0 0 0\n\
+                                           0 0 0 0 0 0\n0 0 0 0 0 0\n");
+
+    assert_eq!(Format::from_filename("maze.PPM"), Some(Format::Ppm));
+    assert!(image.save("maze.png").is_err());
+    assert_eq!(render(&HashMap::<Location, char>::new(), |_| BLACK, 3).encode(Format::Pbm),
+               "P1\n0 0\n");
+}
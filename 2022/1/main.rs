@@ -4,9 +4,9 @@ use std::io::{self, BufRead};
 use std::collections::BinaryHeap;
 
 
-fn main() {
-    // read input
-    let file = File::open("input").unwrap();
+// The total calories carried by each elf
+fn load(filename: &str) -> BinaryHeap<u32> {
+    let file = File::open(filename).unwrap();
     let mut total: u32 = 0;
     let mut most: BinaryHeap<u32> = BinaryHeap::new();
     for line in io::BufReader::new(file).lines() {
@@ -21,14 +21,35 @@ fn main() {
             }
         }
     }
+    return most;
+}
 
-    // part 1
-    println!("{}", most.peek().unwrap());
+fn part1(filename: &str) -> u32 {
+    return *load(filename).peek().unwrap();
+}
 
-    // part 2
+fn part2(filename: &str) -> u32 {
+    let mut most = load(filename);
     let mut top_3: u32 = 0;
     for _ in 0 .. 3 {
         top_3 += most.pop().unwrap();
     }
-    println!("{}", top_3);
+    return top_3;
+}
+
+pub struct Solution;
+
+impl aoc::Puzzle for Solution {
+    fn part1(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part1(filename).to_string());
+    }
+
+    fn part2(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part2(filename).to_string());
+    }
+}
+
+fn main() {
+    println!("{}", part1("input"));
+    println!("{}", part2("input"));
 }
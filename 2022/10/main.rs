@@ -55,26 +55,43 @@ fn test_part1() {
     assert_eq!(part1("test13140"), 13140);
 }
 
-fn part2(filename: &str) {
+fn part2(filename: &str) -> Result<String, String> {
     let trace = load_trace(filename);
     let mut examine: usize = 1;
+    let mut screen: Vec<Vec<bool>> = Vec::new();
     for _ in 0 .. 6 {
+        let mut row: Vec<bool> = Vec::new();
         for x in 0 .. 40 {
             let value = trace.get(examine).unwrap();
-            if Word::abs(x - value) <= 1 {
-                print!("#");
-            } else {
-                print!(" ");
-            }
+            row.push(Word::abs(x - value) <= 1);
             examine += 1;
         }
-        println!();
+        screen.push(row);
+    }
+    return ocr::recognise(&screen);
+}
+
+#[test]
+fn test_part2() {
+    assert_eq!(part2("input"), Ok("ZCBAJFJZ".to_string()));
+}
+
+pub struct Solution;
+
+impl aoc::Puzzle for Solution {
+    fn part1(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part1(filename).to_string());
+    }
+
+    fn part2(self: &Self, filename: &str) -> Result<String, String> {
+        return part2(filename);
     }
 }
 
-fn main() {
+fn main() -> Result<(), String> {
     println!("{}", part1("input"));
-    part2("input");
+    println!("{}", part2("input")?);
+    return Ok(());
 }
 
 
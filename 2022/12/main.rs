@@ -170,9 +170,15 @@ fn test_part1() {
     assert_eq!(shortest_path(&mut load("test31")), 31);
 }
 
-fn main() {
-    let mut p = load("input");
-    println!("{}", shortest_path(&mut p));
+fn part1(filename: &str) -> usize {
+    return shortest_path(&mut load(filename));
+}
+
+// The shortest path from any lowest point, using the distances to the
+// end found for part 1
+fn part2(filename: &str) -> usize {
+    let mut p = load(filename);
+    shortest_path(&mut p);
 
     let mut sp = usize::MAX;
     for y in 0 .. p.map.len() {
@@ -183,7 +189,24 @@ fn main() {
             }
         }
     }
-    println!("{}", sp);
+    return sp;
+}
+
+pub struct Solution;
+
+impl aoc::Puzzle for Solution {
+    fn part1(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part1(filename).to_string());
+    }
+
+    fn part2(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part2(filename).to_string());
+    }
+}
+
+fn main() {
+    println!("{}", part1("input"));
+    println!("{}", part2("input"));
 }
 
 
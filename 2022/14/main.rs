@@ -121,9 +121,29 @@ fn test_part2() {
     assert_eq!(solve(&mut load("test24"), false), 93);
 }
 
+fn part1(filename: &str) -> usize {
+    return solve(&mut load(filename), true);
+}
+
+fn part2(filename: &str) -> usize {
+    return solve(&mut load(filename), false);
+}
+
+pub struct Solution;
+
+impl aoc::Puzzle for Solution {
+    fn part1(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part1(filename).to_string());
+    }
+
+    fn part2(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part2(filename).to_string());
+    }
+}
+
 fn main() {
-    println!("{}", solve(&mut load("input"), true));
-    println!("{}", solve(&mut load("input"), false));
+    println!("{}", part1("input"));
+    println!("{}", part2("input"));
 }
 
 
@@ -341,6 +341,18 @@ fn test_part2() {
     assert_eq!(part2(&load("test"), 20), 56000011);
 }
 
+pub struct Solution;
+
+impl aoc::Puzzle for Solution {
+    fn part1(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part1(&load(filename), 2000000).to_string());
+    }
+
+    fn part2(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part2(&load(filename), 4000000).to_string());
+    }
+}
+
 fn main() {
     println!("{}", part1(&load("input"), 2000000));
     println!("{}", part2(&load("input"), 4000000));
@@ -182,6 +182,18 @@ fn part2(filename: &str) -> u32 {
     return total;
 }
 
+pub struct Solution;
+
+impl aoc::Puzzle for Solution {
+    fn part1(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part1(filename).to_string());
+    }
+
+    fn part2(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part2(filename).to_string());
+    }
+}
+
 fn main() {
     println!("{}", part1(&"input"));
     println!("{}", part2(&"input"));
@@ -60,8 +60,9 @@ fn get_move(opponent_move: char, outcome_code: char) -> char {
     panic!();
 }
 
-fn main() {
-    let file = File::open("input").unwrap();
+// The total scores for both parts
+fn scores(filename: &str) -> (u32, u32) {
+    let file = File::open(filename).unwrap();
     let mut part1_total: u32 = 0;
     let mut part2_total: u32 = 0;
     for line in io::BufReader::new(file).lines() {
@@ -74,6 +75,30 @@ fn main() {
         }
     }
 
-    println!("{}", part1_total);
-    println!("{}", part2_total);
+    return (part1_total, part2_total);
+}
+
+fn part1(filename: &str) -> u32 {
+    return scores(filename).0;
+}
+
+fn part2(filename: &str) -> u32 {
+    return scores(filename).1;
+}
+
+pub struct Solution;
+
+impl aoc::Puzzle for Solution {
+    fn part1(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part1(filename).to_string());
+    }
+
+    fn part2(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part2(filename).to_string());
+    }
+}
+
+fn main() {
+    println!("{}", part1("input"));
+    println!("{}", part2("input"));
 }
@@ -115,6 +115,18 @@ fn test_part2() {
     assert_eq!(part2(&"test"), 1623178306);
 }
 
+pub struct Solution;
+
+impl aoc::Puzzle for Solution {
+    fn part1(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part1(filename).to_string());
+    }
+
+    fn part2(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part2(filename).to_string());
+    }
+}
+
 fn main() {
     println!("{}", part1(&"input"));
     println!("{}", part2(&"input"));
@@ -244,6 +244,18 @@ fn test_part2() {
     assert_eq!(part2(&load(&"test")), 301);
 }
 
+pub struct Solution;
+
+impl aoc::Puzzle for Solution {
+    fn part1(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part1(&load(filename)).to_string());
+    }
+
+    fn part2(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part2(&load(filename)).to_string());
+    }
+}
+
 fn main() {
     println!("{}", part1(&load(&"input")));
     println!("{}", part2(&load(&"input")));
@@ -87,6 +87,22 @@ fn test_part1() {
     assert_eq!(unparse_snafu(314159265), "1121-1110-1=0".to_string());
 }
 
+fn part1(filename: &str) -> String {
+    return unparse_snafu(load(filename));
+}
+
+pub struct Solution;
+
+impl aoc::Puzzle for Solution {
+    fn part1(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part1(filename));
+    }
+
+    fn part2(self: &Self, _filename: &str) -> Result<String, String> {
+        return Err("there is no part 2 on day 25".to_string());
+    }
+}
+
 fn main() {
-    println!("{}", unparse_snafu(load(&"input")));
+    println!("{}", part1("input"));
 }
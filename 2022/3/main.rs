@@ -18,8 +18,8 @@ fn priority(ch: char) -> u32 {
     }
 }
 
-fn part1() {
-    let file = File::open("input").unwrap();
+fn part1(filename: &str) -> u32 {
+    let file = File::open(filename).unwrap();
     let mut part1_total: u32 = 0;
     let mut repeat: HashSet<char> = HashSet::new();
     for line in io::BufReader::new(file).lines() {
@@ -41,11 +41,11 @@ fn part1() {
         }
     }
 
-    println!("{}", part1_total);
+    return part1_total;
 }
 
-fn part2() {
-    let file = File::open("input").unwrap();
+fn part2(filename: &str) -> u32 {
+    let file = File::open(filename).unwrap();
     let mut part2_total: u32 = 0;
     let mut present: HashMap<char, u8> = HashMap::new();
     let mut group_bit: u8 = 1;
@@ -76,10 +76,22 @@ fn part2() {
     }
     assert!(group_bit == 1);
 
-    println!("{}", part2_total);
+    return part2_total;
+}
+
+pub struct Solution;
+
+impl aoc::Puzzle for Solution {
+    fn part1(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part1(filename).to_string());
+    }
+
+    fn part2(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part2(filename).to_string());
+    }
 }
 
 fn main() {
-    part1();
-    part2();
+    println!("{}", part1("input"));
+    println!("{}", part2("input"));
 }
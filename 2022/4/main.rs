@@ -10,8 +10,9 @@ fn split_pair(text: &str) -> (u32, u32) {
     return (num1, num2);
 }
 
-fn main() {
-    let file = File::open("input").unwrap();
+// The number of pairs for both parts
+fn counts(filename: &str) -> (u32, u32) {
+    let file = File::open(filename).unwrap();
     let mut part1_total: u32 = 0;
     let mut part2_total: u32 = 0;
     for line in io::BufReader::new(file).lines() {
@@ -35,6 +36,30 @@ fn main() {
         }
     }
 
-    println!("{}", part1_total);
-    println!("{}", part2_total);
+    return (part1_total, part2_total);
+}
+
+fn part1(filename: &str) -> u32 {
+    return counts(filename).0;
+}
+
+fn part2(filename: &str) -> u32 {
+    return counts(filename).1;
+}
+
+pub struct Solution;
+
+impl aoc::Puzzle for Solution {
+    fn part1(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part1(filename).to_string());
+    }
+
+    fn part2(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part2(filename).to_string());
+    }
+}
+
+fn main() {
+    println!("{}", part1("input"));
+    println!("{}", part2("input"));
 }
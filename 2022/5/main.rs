@@ -9,8 +9,8 @@ const NUM_STACKS: usize = 9;
 type Stack = VecDeque<char>;
 type Stacks = Vec<Stack>;
 
-fn part(part_number: u32) {
-    let file = File::open("input").unwrap();
+fn part(filename: &str, part_number: u32) -> String {
+    let file = File::open(filename).unwrap();
 
     let mut stacks: Stacks = Vec::new();
 
@@ -67,15 +67,36 @@ fn part(part_number: u32) {
         }
     }
 
+    let mut tops = String::new();
     for i in 0 .. NUM_STACKS {
         let src_stack = stacks.get(i).unwrap();
-        print!("{}", src_stack.back().unwrap());
+        tops.push(*src_stack.back().unwrap());
+    }
+    return tops;
+}
+
+fn part1(filename: &str) -> String {
+    return part(filename, 1);
+}
+
+fn part2(filename: &str) -> String {
+    return part(filename, 2);
+}
+
+pub struct Solution;
+
+impl aoc::Puzzle for Solution {
+    fn part1(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part1(filename));
+    }
+
+    fn part2(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part2(filename));
     }
-    println!("");
 }
 
 fn main() {
-    part(1);
-    part(2);
+    println!("{}", part1("input"));
+    println!("{}", part2("input"));
 }
 
@@ -4,8 +4,9 @@ use std::io::{self, BufRead};
 use std::collections::VecDeque;
 use std::collections::HashMap;
 
-fn part(size: usize) {
-    let file = File::open("input").unwrap();
+// The number of characters received when the last `size` were all different
+fn part(filename: &str, size: usize) -> usize {
+    let file = File::open(filename).unwrap();
     let line = io::BufReader::new(file).lines().next().unwrap();
     let mut delay: VecDeque<char> = VecDeque::new();
     let mut ch_count: HashMap<char, u32> = HashMap::new();
@@ -29,15 +30,34 @@ fn part(size: usize) {
                 }
             }
             if !repeat {
-                println!("{}", rx_count);
-                break;
+                return rx_count;
             }
         }
     }
+    panic!("no marker found");
 }
 
+fn part1(filename: &str) -> usize {
+    return part(filename, 4);
+}
+
+fn part2(filename: &str) -> usize {
+    return part(filename, 14);
+}
+
+pub struct Solution;
+
+impl aoc::Puzzle for Solution {
+    fn part1(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part1(filename).to_string());
+    }
+
+    fn part2(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part2(filename).to_string());
+    }
+}
 
-fn main () {
-    part(4);
-    part(14);
+fn main() {
+    println!("{}", part1("input"));
+    println!("{}", part2("input"));
 }
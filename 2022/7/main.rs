@@ -18,8 +18,9 @@ fn add_to_tree_size(tree_size: &mut HashMap<String, usize>,
     }
 }
 
-fn main () {
-    let file = File::open("input").unwrap();
+// The total size of each directory, by path
+fn load(filename: &str) -> HashMap<String, usize> {
+    let file = File::open(filename).unwrap();
     let mut path: Vec<String> = Vec::new();
     let mut tree_size: HashMap<String, usize> = HashMap::new();
 
@@ -53,14 +54,22 @@ fn main () {
         }
     }
 
+    return tree_size;
+}
+
+fn part1(filename: &str) -> usize {
+    let tree_size = load(filename);
     let mut part1_used: usize = 0;
     for size in tree_size.values() {
         if *size <= 100000 {
             part1_used += *size;
         }
     }
-    println!("{}", part1_used);
+    return part1_used;
+}
 
+fn part2(filename: &str) -> usize {
+    let tree_size = load(filename);
     let disk_size: usize = 70000000;
     let free_space_required: usize = 30000000;
     let total_used: usize = *tree_size.get("").unwrap(); // tree size at the root
@@ -73,5 +82,22 @@ fn main () {
             part2_choice = usize::min(part2_choice, *size);
         }
     }
-    println!("{}", part2_choice);
+    return part2_choice;
+}
+
+pub struct Solution;
+
+impl aoc::Puzzle for Solution {
+    fn part1(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part1(filename).to_string());
+    }
+
+    fn part2(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part2(filename).to_string());
+    }
+}
+
+fn main() {
+    println!("{}", part1("input"));
+    println!("{}", part2("input"));
 }
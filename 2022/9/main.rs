@@ -120,7 +120,7 @@ fn part2(filename: &str) -> usize {
         let mut previous: Location = head;
         for i in 0 .. rope.len() {
             // move this part
-            let mut part = rope.get_mut(i).unwrap();
+            let part = rope.get_mut(i).unwrap();
             let dx = previous.x - part.x;
             let dy = previous.y - part.y;
             part.x += step(dx, dy);
@@ -143,6 +143,18 @@ fn test_part2() {
     assert_eq!(part2("test36"), 36);
 }
 
+pub struct Solution;
+
+impl aoc::Puzzle for Solution {
+    fn part1(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part1(filename).to_string());
+    }
+
+    fn part2(self: &Self, filename: &str) -> Result<String, String> {
+        return Ok(part2(filename).to_string());
+    }
+}
+
 fn main() {
     println!("{}", part1("input"));
     println!("{}", part2("input"));
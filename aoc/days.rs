@@ -0,0 +1,173 @@
+// Every Rust day in the repository, compiled in from its own directory, with
+// the answers for the input that is kept alongside it. A day's own main() and
+// its helpers for extra output are unused here.
+#![allow(dead_code)]
+
+use aoc::Puzzle;
+
+#[path = "../2015/11/main.rs"]
+mod aoc2015_day11;
+#[path = "../2015/14/main.rs"]
+mod aoc2015_day14;
+#[path = "../2015/15/main.rs"]
+mod aoc2015_day15;
+#[path = "../2015/16/main.rs"]
+mod aoc2015_day16;
+#[path = "../2015/17/main.rs"]
+mod aoc2015_day17;
+#[path = "../2015/18/main.rs"]
+mod aoc2015_day18;
+#[path = "../2015/19/main.rs"]
+mod aoc2015_day19;
+#[path = "../2015/20/main.rs"]
+mod aoc2015_day20;
+#[path = "../2015/21/main.rs"]
+mod aoc2015_day21;
+#[path = "../2015/22/main.rs"]
+mod aoc2015_day22;
+#[path = "../2015/23/main.rs"]
+mod aoc2015_day23;
+#[path = "../2015/24/main.rs"]
+mod aoc2015_day24;
+#[path = "../2015/25/main.rs"]
+mod aoc2015_day25;
+#[path = "../2019/2/main.rs"]
+mod aoc2019_day2;
+#[path = "../2019/3/main.rs"]
+mod aoc2019_day3;
+#[path = "../2019/4/main.rs"]
+mod aoc2019_day4;
+#[path = "../2019/5/main.rs"]
+mod aoc2019_day5;
+#[path = "../2019/6/main.rs"]
+mod aoc2019_day6;
+#[path = "../2019/7/main.rs"]
+mod aoc2019_day7;
+#[path = "../2019/8/main.rs"]
+mod aoc2019_day8;
+#[path = "../2019/9/main.rs"]
+mod aoc2019_day9;
+#[path = "../2019/10/main.rs"]
+mod aoc2019_day10;
+#[path = "../2019/11/main.rs"]
+mod aoc2019_day11;
+#[path = "../2019/12/main.rs"]
+mod aoc2019_day12;
+#[path = "../2019/13/main.rs"]
+mod aoc2019_day13;
+#[path = "../2019/14/main.rs"]
+mod aoc2019_day14;
+#[path = "../2019/15/main.rs"]
+mod aoc2019_day15;
+#[path = "../2022/1/main.rs"]
+mod aoc2022_day1;
+#[path = "../2022/2/main.rs"]
+mod aoc2022_day2;
+#[path = "../2022/3/main.rs"]
+mod aoc2022_day3;
+#[path = "../2022/4/main.rs"]
+mod aoc2022_day4;
+#[path = "../2022/5/main.rs"]
+mod aoc2022_day5;
+#[path = "../2022/6/main.rs"]
+mod aoc2022_day6;
+#[path = "../2022/7/main.rs"]
+mod aoc2022_day7;
+#[path = "../2022/8/main.rs"]
+mod aoc2022_day8;
+#[path = "../2022/9/main.rs"]
+mod aoc2022_day9;
+#[path = "../2022/10/main.rs"]
+mod aoc2022_day10;
+#[path = "../2022/11/main.rs"]
+mod aoc2022_day11;
+#[path = "../2022/12/main.rs"]
+mod aoc2022_day12;
+#[path = "../2022/13/main.rs"]
+mod aoc2022_day13;
+#[path = "../2022/14/main.rs"]
+mod aoc2022_day14;
+#[path = "../2022/15/main.rs"]
+mod aoc2022_day15;
+#[path = "../2022/16/main.rs"]
+mod aoc2022_day16;
+#[path = "../2022/17/main.rs"]
+mod aoc2022_day17;
+#[path = "../2022/18/main.rs"]
+mod aoc2022_day18;
+#[path = "../2022/19/main.rs"]
+mod aoc2022_day19;
+#[path = "../2022/20/main.rs"]
+mod aoc2022_day20;
+#[path = "../2022/21/main.rs"]
+mod aoc2022_day21;
+#[path = "../2022/22/main.rs"]
+mod aoc2022_day22;
+#[path = "../2022/23/main.rs"]
+mod aoc2022_day23;
+#[path = "../2022/24/main.rs"]
+mod aoc2022_day24;
+#[path = "../2022/25/main.rs"]
+mod aoc2022_day25;
+
+pub struct Day {
+    pub year: u32,
+    pub day: u32,
+    pub puzzle: &'static dyn Puzzle,
+    pub answers: [Option<&'static str>; 2],
+}
+
+pub const DAYS: &[Day] = &[
+    Day { year: 2015, day: 11, puzzle: &aoc2015_day11::Solution, answers: [Some("vzbxxyzz"), Some("vzcaabcc")] },
+    Day { year: 2015, day: 14, puzzle: &aoc2015_day14::Solution, answers: [Some("2660"), Some("1256")] },
+    Day { year: 2015, day: 15, puzzle: &aoc2015_day15::Solution, answers: [Some("18965440"), Some("15862900")] },
+    Day { year: 2015, day: 16, puzzle: &aoc2015_day16::Solution, answers: [Some("213"), Some("323")] },
+    Day { year: 2015, day: 17, puzzle: &aoc2015_day17::Solution, answers: [Some("4372"), Some("4")] },
+    Day { year: 2015, day: 18, puzzle: &aoc2015_day18::Solution, answers: [Some("821"), Some("886")] },
+    Day { year: 2015, day: 19, puzzle: &aoc2015_day19::Solution, answers: [Some("576"), None] },
+    Day { year: 2015, day: 20, puzzle: &aoc2015_day20::Solution, answers: [Some("786240"), Some("831600")] },
+    Day { year: 2015, day: 21, puzzle: &aoc2015_day21::Solution, answers: [Some("78"), Some("148")] },
+    Day { year: 2015, day: 22, puzzle: &aoc2015_day22::Solution, answers: [Some("900"), Some("1216")] },
+    Day { year: 2015, day: 23, puzzle: &aoc2015_day23::Solution, answers: [Some("170"), Some("247")] },
+    Day { year: 2015, day: 24, puzzle: &aoc2015_day24::Solution, answers: [Some("10439961859"), Some("72050269")] },
+    Day { year: 2015, day: 25, puzzle: &aoc2015_day25::Solution, answers: [Some("19980801"), None] },
+    Day { year: 2019, day: 2, puzzle: &aoc2019_day2::Solution, answers: [Some("6327510"), Some("4112")] },
+    Day { year: 2019, day: 3, puzzle: &aoc2019_day3::Solution, answers: [Some("1211"), Some("101386")] },
+    Day { year: 2019, day: 4, puzzle: &aoc2019_day4::Solution, answers: [Some("1767"), Some("1192")] },
+    Day { year: 2019, day: 5, puzzle: &aoc2019_day5::Solution, answers: [Some("5346030"), Some("513116")] },
+    Day { year: 2019, day: 6, puzzle: &aoc2019_day6::Solution, answers: [Some("139597"), Some("286")] },
+    Day { year: 2019, day: 7, puzzle: &aoc2019_day7::Solution, answers: [Some("21860"), Some("2645740")] },
+    Day { year: 2019, day: 8, puzzle: &aoc2019_day8::Solution, answers: [Some("1206"), Some("EJRGP")] },
+    Day { year: 2019, day: 9, puzzle: &aoc2019_day9::Solution, answers: [Some("3497884671"), Some("46470")] },
+    Day { year: 2019, day: 10, puzzle: &aoc2019_day10::Solution, answers: [Some("286"), Some("504")] },
+    Day { year: 2019, day: 11, puzzle: &aoc2019_day11::Solution, answers: [Some("2469"), Some("KLCZAEGU")] },
+    Day { year: 2019, day: 12, puzzle: &aoc2019_day12::Solution, answers: [Some("12070"), Some("500903629351944")] },
+    Day { year: 2019, day: 13, puzzle: &aoc2019_day13::Solution, answers: [Some("193"), Some("10547")] },
+    Day { year: 2019, day: 14, puzzle: &aoc2019_day14::Solution, answers: [Some("899155"), Some("2390226")] },
+    Day { year: 2019, day: 15, puzzle: &aoc2019_day15::Solution, answers: [Some("380"), Some("410")] },
+    Day { year: 2022, day: 1, puzzle: &aoc2022_day1::Solution, answers: [Some("65912"), Some("195625")] },
+    Day { year: 2022, day: 2, puzzle: &aoc2022_day2::Solution, answers: [Some("13446"), Some("13509")] },
+    Day { year: 2022, day: 3, puzzle: &aoc2022_day3::Solution, answers: [Some("7848"), Some("2616")] },
+    Day { year: 2022, day: 4, puzzle: &aoc2022_day4::Solution, answers: [Some("550"), Some("931")] },
+    Day { year: 2022, day: 5, puzzle: &aoc2022_day5::Solution, answers: [Some("TLFGBZHCN"), Some("QRQFHFWCL")] },
+    Day { year: 2022, day: 6, puzzle: &aoc2022_day6::Solution, answers: [Some("1034"), Some("2472")] },
+    Day { year: 2022, day: 7, puzzle: &aoc2022_day7::Solution, answers: [Some("2031851"), Some("2568781")] },
+    Day { year: 2022, day: 8, puzzle: &aoc2022_day8::Solution, answers: [Some("1676"), Some("313200")] },
+    Day { year: 2022, day: 9, puzzle: &aoc2022_day9::Solution, answers: [Some("6376"), Some("2607")] },
+    Day { year: 2022, day: 10, puzzle: &aoc2022_day10::Solution, answers: [Some("17940"), Some("ZCBAJFJZ")] },
+    Day { year: 2022, day: 11, puzzle: &aoc2022_day11::Solution, answers: [Some("55930"), Some("14636993466")] },
+    Day { year: 2022, day: 12, puzzle: &aoc2022_day12::Solution, answers: [Some("383"), Some("377")] },
+    Day { year: 2022, day: 13, puzzle: &aoc2022_day13::Solution, answers: [Some("5208"), Some("25792")] },
+    Day { year: 2022, day: 14, puzzle: &aoc2022_day14::Solution, answers: [Some("1003"), Some("25771")] },
+    Day { year: 2022, day: 15, puzzle: &aoc2022_day15::Solution, answers: [Some("4724228"), Some("13622251246513")] },
+    Day { year: 2022, day: 16, puzzle: &aoc2022_day16::Solution, answers: [Some("2056"), Some("2513")] },
+    Day { year: 2022, day: 17, puzzle: &aoc2022_day17::Solution, answers: [Some("3067"), Some("1514369501484")] },
+    Day { year: 2022, day: 18, puzzle: &aoc2022_day18::Solution, answers: [Some("3542"), Some("2080")] },
+    Day { year: 2022, day: 19, puzzle: &aoc2022_day19::Solution, answers: [Some("1487"), Some("13440")] },
+    Day { year: 2022, day: 20, puzzle: &aoc2022_day20::Solution, answers: [Some("2215"), Some("8927480683")] },
+    Day { year: 2022, day: 21, puzzle: &aoc2022_day21::Solution, answers: [Some("170237589447588"), Some("3712643961892")] },
+    Day { year: 2022, day: 22, puzzle: &aoc2022_day22::Solution, answers: [Some("95358"), Some("144361")] },
+    Day { year: 2022, day: 23, puzzle: &aoc2022_day23::Solution, answers: [Some("3947"), Some("1012")] },
+    Day { year: 2022, day: 24, puzzle: &aoc2022_day24::Solution, answers: [Some("286"), Some("820")] },
+    Day { year: 2022, day: 25, puzzle: &aoc2022_day25::Solution, answers: [Some("121=2=1==0=10=2-20=2"), None] },
+];
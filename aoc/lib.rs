@@ -0,0 +1,13 @@
+//! The interface between the `aoc` runner and each day's solution.
+//!
+//! Each day's main.rs has a `Solution` that implements `Puzzle`. The runner
+//! compiles the day in as a module and calls it through the trait, so the
+//! day's own binary and the runner share the same code. Both parts take the
+//! name of the input file and give the answer as text, whatever its type.
+//! Input that a part can't use should give an error rather than a panic,
+//! although the runner reports a panic as an error too.
+
+pub trait Puzzle {
+    fn part1(self: &Self, filename: &str) -> Result<String, String>;
+    fn part2(self: &Self, filename: &str) -> Result<String, String>;
+}
@@ -0,0 +1,176 @@
+//! Runs any of the puzzles in the repository from one place.
+//!
+//!     aoc run <year> <day> [--part <1|2>] [--input <file>]
+//!     aoc time <year> <day> [--part <1|2>] [--input <file>]
+//!     aoc test [<year> [<day>]]
+//!
+//! `run` prints the answers, `time` also says how long each part took, and
+//! `test` checks every matching day against the answers recorded for its
+//! input. The input defaults to the one kept in the day's directory.
+
+use std::env;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+mod days;
+use days::{Day, DAYS};
+
+const USAGE: &str = "usage: aoc run|time <year> <day> [--part <1|2>] [--input <file>], \
+                     or aoc test [<year> [<day>]]";
+
+struct Options {
+    part: Option<usize>,
+    input: Option<String>,
+}
+
+fn parse_number(text: &str, what: &str) -> Result<u32, String> {
+    return text.parse().map_err(|_| format!("the {} must be a number, not '{}'", what, text));
+}
+
+fn find_day(year: u32, day: u32) -> Result<&'static Day, String> {
+    return DAYS.iter().find(|d| d.year == year && d.day == day)
+               .ok_or(format!("there is no Rust solution for {} day {}", year, day));
+}
+
+fn default_input(day: &Day) -> PathBuf {
+    return PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..")
+        .join(day.year.to_string()).join(day.day.to_string()).join("input");
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut options = Options { part: None, input: None };
+    let mut i = 0;
+    while i < args.len() {
+        let value = args.get(i + 1).ok_or(USAGE)?;
+        match args[i].as_str() {
+            "--part" => {
+                options.part = match value.as_str() {
+                    "1" => Some(1),
+                    "2" => Some(2),
+                    _ => { return Err(format!("the part must be 1 or 2, not '{}'", value)); },
+                };
+            },
+            "--input" => {
+                options.input = Some(value.clone());
+            },
+            _ => { return Err(USAGE.to_string()); },
+        }
+        i += 2;
+    }
+    return Ok(options);
+}
+
+// Solve one part, giving the answer and how long it took. Many days panic
+// on input they can't use, so a panic becomes an error like any other.
+fn solve(day: &Day, part: usize, filename: &str) -> (Result<String, String>, Duration) {
+    let start = Instant::now();
+    let answer = panic::catch_unwind(AssertUnwindSafe(|| match part {
+        1 => day.puzzle.part1(filename),
+        _ => day.puzzle.part2(filename),
+    })).unwrap_or_else(|payload| {
+        let message = match payload.downcast_ref::<&str>() {
+            Some(message) => message.to_string(),
+            None => payload.downcast_ref::<String>().cloned()
+                           .unwrap_or("no message".to_string()),
+        };
+        return Err(format!("panicked: {}", message));
+    });
+    return (answer, start.elapsed());
+}
+
+fn run(day: &Day, options: &Options, timed: bool) -> Result<(), String> {
+    let filename = match &options.input {
+        Some(filename) => PathBuf::from(filename),
+        None => default_input(day),
+    };
+    if !filename.exists() {
+        return Err(format!("cannot find the input file {}", filename.display()));
+    }
+    let filename = filename.to_str().ok_or("the input file name is not valid UTF-8")?;
+    for part in 1 ..= 2 {
+        if options.part.is_some_and(|p| p != part) {
+            continue;
+        }
+        let (answer, elapsed) = solve(day, part, filename);
+        let answer = match answer {
+            Ok(answer) => answer,
+            // with no part asked for, a day without a part 2 still gives part 1
+            Err(msg) if options.part.is_none() && part == 2 => {
+                eprintln!("{}/{} part {}: {}", day.year, day.day, part, msg);
+                continue;
+            },
+            Err(msg) => { return Err(msg); },
+        };
+        if timed {
+            println!("{}/{} part {}: {}  ({:.3} ms)", day.year, day.day, part, answer,
+                     elapsed.as_secs_f64() * 1000.0);
+        } else {
+            println!("{}", answer);
+        }
+    }
+    return Ok(());
+}
+
+// Check each part that has an answer recorded, and that the others say why
+// they have none
+fn test(days: &[&Day]) -> Result<(), String> {
+    let mut failures = 0;
+    let mut total = Duration::ZERO;
+    for day in days.iter() {
+        let filename = default_input(day);
+        let filename = filename.to_str().ok_or("the input file name is not valid UTF-8")?;
+        for part in 1 ..= 2 {
+            let (answer, elapsed) = solve(day, part, filename);
+            total += elapsed;
+            let expected = day.answers[part - 1];
+            let outcome = match (&answer, expected) {
+                (Ok(a), Some(e)) if a == e => "ok".to_string(),
+                (Err(_), None) => "ok".to_string(),
+                (Ok(a), Some(e)) => format!("FAILED: got {}, expected {}", a, e),
+                (Ok(a), None) => format!("FAILED: got {}, expected no answer", a),
+                (Err(msg), Some(_)) => format!("FAILED: {}", msg),
+            };
+            if outcome != "ok" {
+                failures += 1;
+            }
+            println!("{}/{} part {}: {}  ({:.3} ms)", day.year, day.day, part, outcome,
+                     elapsed.as_secs_f64() * 1000.0);
+        }
+    }
+    println!("{} parts checked, {} failed, {:.3} s", days.len() * 2, failures,
+             total.as_secs_f64());
+    if failures != 0 {
+        return Err(format!("{} parts failed", failures));
+    }
+    return Ok(());
+}
+
+fn main() -> Result<(), String> {
+    // solve() reports panics itself
+    panic::set_hook(Box::new(|_| {}));
+    let args: Vec<String> = env::args().collect();
+    match args.get(1).map(|a| a.as_str()) {
+        Some(command @ ("run" | "time")) if args.len() >= 4 => {
+            let year = parse_number(&args[2], "year")?;
+            let day = find_day(year, parse_number(&args[3], "day")?)?;
+            let options = parse_options(&args[4 ..])?;
+            return run(day, &options, command == "time");
+        },
+        Some("test") if args.len() <= 4 => {
+            let year = args.get(2).map(|a| parse_number(a, "year")).transpose()?;
+            let day = args.get(3).map(|a| parse_number(a, "day")).transpose()?;
+            let days: Vec<&Day> = DAYS.iter()
+                .filter(|d| year.is_none_or(|y| d.year == y))
+                .filter(|d| day.is_none_or(|n| d.day == n))
+                .collect();
+            if days.is_empty() {
+                return Err("no Rust solutions match".to_string());
+            }
+            return test(&days);
+        },
+        _ => {
+            return Err(USAGE.to_string());
+        },
+    }
+}